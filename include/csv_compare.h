@@ -0,0 +1,36 @@
+#ifndef CSV_COMPARE_H
+#define CSV_COMPARE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Files and command line options of a comparison */
+typedef struct CsvCompareOptions {
+    const char *file1;
+    const char *file2;
+    /* Command line options, e.g. {"--separator", ";"}. Can be NULL if options_len is 0 */
+    const char *const *options;
+    size_t options_len;
+} CsvCompareOptions;
+
+/*
+ * Compares the files and writes the JSON report into report_json (release it with csv_compare_free_string).
+ * Returns the exit code the command line would return (0 when files are identical). When a file can't be read,
+ * it's the exit code of failed comparisons (70 by default, --exit-code-failed) and the "error" of the report
+ * explains why. Returns -1 when the comparison can't start (null pointers, invalid strings or options), in which
+ * case report_json contains the error message.
+ */
+int32_t csv_compare_run(const CsvCompareOptions *options, char **report_json);
+
+/* Releases a string returned by csv_compare_run */
+void csv_compare_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
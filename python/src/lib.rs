@@ -0,0 +1,39 @@
+use csv_compare_core::output::Printer;
+use csv_compare_core::{compare_files, Args};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Compares two CSV files and returns the report as a dict.
+/// `options` are the same as in the command line, e.g. ["--separator", ";", "--report-all"]
+#[pyfunction]
+#[pyo3(signature = (file1, file2, options=None))]
+fn compare(
+    py: Python<'_>,
+    file1: &str,
+    file2: &str,
+    options: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    let args = Args::from_options(file1, file2, &options.unwrap_or_default())
+        .map_err(PyValueError::new_err)?;
+
+    let report = py
+        .allow_threads(|| {
+            let printer = Printer::new(true, false, false);
+            catch_unwind(AssertUnwindSafe(|| {
+                compare_files(&args, &printer, file1, file2)
+            }))
+        })
+        .map_err(|_| PyRuntimeError::new_err("Comparison failed"))?;
+
+    let json = serde_json::to_string(&report)
+        .map_err(|error| PyRuntimeError::new_err(error.to_string()))?;
+    let report = py.import("json")?.call_method1("loads", (json,))?;
+    Ok(report.into())
+}
+
+#[pymodule]
+fn csv_compare(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(compare, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::compare::{as_text, get_text};
+use anyhow::{Context, Result};
+use polars::prelude::{col, lit, ChunkCompare, JoinArgs, JoinType, LazyFrame, Series};
+
+/// Status of a cell of the first file compared with the same cell of the second file
+const EQUAL: &str = "equal";
+const CHANGED: &str = "changed";
+const MISSING: &str = "missing";
+
+const PRESENT_COLUMN: &str = "__csv_compare_present";
+
+/// Writes the rows of the first file with some difference, adding a `<column>_status` column
+/// (equal/changed/missing) for every compared column. Rows are matched by the key column.
+/// Returns the number of rows written
+pub fn write_annotated(
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+    key: &str,
+    columns: &[String],
+    separator: char,
+    output_path: &str,
+) -> Result<u64> {
+    let joined = first_lazy_frame
+        .clone()
+        .join(
+            second_lazy_frame
+                .clone()
+                .with_column(lit(true).alias(PRESENT_COLUMN)),
+            [col(key)],
+            [col(key)],
+            JoinArgs::new(JoinType::Left),
+        )
+        .collect()
+        .context("Couldn't match the rows of both files to annotate them")?;
+
+    let is_present = joined.column(PRESENT_COLUMN)?.is_not_null();
+    let mut statuses = vec![];
+    for column in columns.iter().filter(|column| *column != key) {
+        let first_series = joined.column(column)?;
+        let second_series = joined.column(&format!("{column}_right"))?;
+        let is_different = first_series
+            .not_equal_missing(second_series)
+            .with_context(|| format!("Couldn't compare values of column {column}"))?;
+        let column_statuses: Vec<&str> = is_present
+            .into_iter()
+            .zip(is_different.into_iter())
+            .map(|(is_present, is_different)| {
+                if !is_present.unwrap_or(false) {
+                    MISSING
+                } else if is_different.unwrap_or(false) {
+                    CHANGED
+                } else {
+                    EQUAL
+                }
+            })
+            .collect();
+        statuses.push((column, column_statuses));
+    }
+
+    let first_columns: Vec<Series> = first_lazy_frame
+        .clone()
+        .limit(0)
+        .collect()?
+        .get_column_names()
+        .iter()
+        .map(|name| joined.column(name).map(as_text))
+        .collect::<Result<_, _>>()?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(separator as u8)
+        .from_path(output_path)
+        .with_context(|| format!("Couldn't create file {output_path}"))?;
+    let mut header: Vec<String> = first_columns
+        .iter()
+        .map(|series| series.name().to_string())
+        .collect();
+    header.extend(
+        statuses
+            .iter()
+            .map(|(column, _)| format!("{column}_status")),
+    );
+    writer.write_record(&header)?;
+
+    let mut rows_written = 0;
+    for row in 0..joined.height() {
+        if statuses
+            .iter()
+            .all(|(_, column_statuses)| column_statuses[row] == EQUAL)
+        {
+            continue;
+        }
+        let mut record: Vec<String> = first_columns
+            .iter()
+            .map(|series| get_text(series, row).unwrap_or_default())
+            .collect();
+        record.extend(
+            statuses
+                .iter()
+                .map(|(_, column_statuses)| column_statuses[row].to_string()),
+        );
+        writer.write_record(&record)?;
+        rows_written += 1;
+    }
+    writer.flush()?;
+
+    Ok(rows_written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    #[test]
+    fn writes_the_different_rows_with_their_status() {
+        let first = df!(
+            "id" => ["1", "2", "3"],
+            "name" => ["Ann", "Bob", "Carl"],
+            "age" => ["30", "40", "50"]
+        )
+        .unwrap()
+        .lazy();
+        let second = df!(
+            "id" => ["1", "2"],
+            "name" => ["Ann", "Bobby"],
+            "age" => ["30", "40"]
+        )
+        .unwrap()
+        .lazy();
+        let columns = ["id", "name", "age"].map(String::from);
+        let output = tempfile::NamedTempFile::new().unwrap();
+        let output_path = output.path().to_str().unwrap();
+
+        let rows = write_annotated(&first, &second, "id", &columns, ',', output_path).unwrap();
+        assert_eq!(rows, 2);
+        assert_eq!(
+            std::fs::read_to_string(output_path).unwrap(),
+            "id,name,age,name_status,age_status\n2,Bob,40,changed,equal\n3,Carl,50,missing,missing\n"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(feature = "archives")]
+use crate::inputs::temporary_file;
+use anyhow::Result;
+#[cfg(feature = "archives")]
+use anyhow::{anyhow, bail, Context};
+#[cfg(feature = "archives")]
+use flate2::read::GzDecoder;
+#[cfg(feature = "archives")]
+use std::fs::File;
+#[cfg(feature = "archives")]
+use std::io::{Read, Write};
+use tempfile::NamedTempFile;
+
+/// Kind of archive, given by the extension of its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub(crate) fn of(file_path: &str) -> Option<Self> {
+        let file_path = file_path.to_ascii_lowercase();
+        if file_path.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if file_path.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_path.ends_with(".tar.gz") || file_path.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts a member of the archive to a temporary file. Without member, the archive must contain a single file
+#[cfg(feature = "archives")]
+pub(crate) fn extract(
+    archive_path: &str,
+    format: ArchiveFormat,
+    member: Option<&str>,
+) -> Result<NamedTempFile> {
+    let file = File::open(archive_path).with_context(|| format!("Couldn't open {archive_path}"))?;
+    let extracted = match format {
+        ArchiveFormat::Zip => extract_zip(file, member),
+        ArchiveFormat::Tar => extract_tar(tar::Archive::new(file), member),
+        ArchiveFormat::TarGz => extract_tar(tar::Archive::new(GzDecoder::new(file)), member),
+    };
+    extracted.with_context(|| format!("Couldn't extract from {archive_path}"))
+}
+
+#[cfg(not(feature = "archives"))]
+pub(crate) fn extract(
+    archive_path: &str,
+    _format: ArchiveFormat,
+    _member: Option<&str>,
+) -> Result<NamedTempFile> {
+    anyhow::bail!(
+        "Can't extract from {archive_path}: archive support is not included in this build (feature archives)"
+    )
+}
+
+#[cfg(feature = "archives")]
+fn extract_zip(file: File, member: Option<&str>) -> Result<NamedTempFile> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let name = match member {
+        Some(member) => member.to_string(),
+        None => {
+            let names: Vec<&str> = archive
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .collect();
+            match names[..] {
+                [name] => name.to_string(),
+                [] => bail!("The archive has no files"),
+                [first_name, second_name, ..] => {
+                    return Err(several_files(first_name, second_name))
+                }
+            }
+        }
+    };
+    let mut entry = archive
+        .by_name(&name)
+        .with_context(|| format!("{name} not found"))?;
+    copy_to_temporary_file(&mut entry)
+}
+
+#[cfg(feature = "archives")]
+fn extract_tar<R: Read>(
+    mut archive: tar::Archive<R>,
+    member: Option<&str>,
+) -> Result<NamedTempFile> {
+    let mut extracted: Option<(String, NamedTempFile)> = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.display().to_string();
+        match member {
+            Some(member) if name.trim_start_matches("./") == member.trim_start_matches("./") => {
+                return copy_to_temporary_file(&mut entry);
+            }
+            Some(_) => {}
+            None => match &extracted {
+                Some((first_name, _)) => return Err(several_files(first_name, &name)),
+                None => extracted = Some((name, copy_to_temporary_file(&mut entry)?)),
+            },
+        }
+    }
+    match (member, extracted) {
+        (Some(member), _) => bail!("{member} not found"),
+        (None, Some((_, file))) => Ok(file),
+        (None, None) => bail!("The archive has no files"),
+    }
+}
+
+#[cfg(feature = "archives")]
+fn several_files(first_name: &str, second_name: &str) -> anyhow::Error {
+    anyhow!(
+        "The archive has several files ({first_name}, {second_name}...), \
+        choose one with archive!file or --archive-member"
+    )
+}
+
+#[cfg(feature = "archives")]
+fn copy_to_temporary_file(reader: &mut impl Read) -> Result<NamedTempFile> {
+    let mut file = temporary_file()?;
+    std::io::copy(reader, &mut file)?;
+    file.flush()?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_format_by_the_extension() {
+        assert_eq!(ArchiveFormat::of("exports.ZIP"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::of("exports.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::of("exports.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(
+            ArchiveFormat::of("exports.tar.gz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::of("data.csv"), None);
+    }
+
+    #[cfg(feature = "archives")]
+    fn zip_archive(files: &[(&str, &str)]) -> NamedTempFile {
+        let archive = tempfile::Builder::new().suffix(".zip").tempfile().unwrap();
+        let mut writer = zip::ZipWriter::new(archive.reopen().unwrap());
+        for (name, content) in files {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        archive
+    }
+
+    #[cfg(feature = "archives")]
+    fn tar_archive(files: &[(&str, &str)]) -> NamedTempFile {
+        let archive = tempfile::Builder::new().suffix(".tar").tempfile().unwrap();
+        let mut builder = tar::Builder::new(archive.reopen().unwrap());
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.finish().unwrap();
+        archive
+    }
+
+    #[cfg(feature = "archives")]
+    fn extracted(
+        archive: &NamedTempFile,
+        format: ArchiveFormat,
+        member: Option<&str>,
+    ) -> Result<String> {
+        let file = extract(archive.path().to_str().unwrap(), format, member)?;
+        Ok(std::fs::read_to_string(file.path())?)
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn extracts_the_single_file_or_the_member() {
+        for (archive, format) in [
+            (zip_archive(&[("data.csv", "id\n1\n")]), ArchiveFormat::Zip),
+            (tar_archive(&[("data.csv", "id\n1\n")]), ArchiveFormat::Tar),
+        ] {
+            assert_eq!(extracted(&archive, format, None).unwrap(), "id\n1\n");
+        }
+        let files = [("a.csv", "id\n1\n"), ("b.csv", "id\n2\n")];
+        for (archive, format) in [
+            (zip_archive(&files), ArchiveFormat::Zip),
+            (tar_archive(&files), ArchiveFormat::Tar),
+        ] {
+            assert_eq!(
+                extracted(&archive, format, Some("b.csv")).unwrap(),
+                "id\n2\n"
+            );
+            assert!(extracted(&archive, format, None).is_err());
+            assert!(extracted(&archive, format, Some("c.csv")).is_err());
+        }
+    }
+}
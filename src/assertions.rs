@@ -0,0 +1,267 @@
+use crate::report::AssertionFailure;
+use anyhow::{bail, Context, Result};
+use polars::prelude::{
+    col, lit, BooleanChunked, DataFrame, DataType, Expr, GetOutput, IntoSeries, LazyFrame, Series,
+};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Rules that the values of a column must follow in both files
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnAssertions {
+    /// Regular expression the values must match
+    pub pattern: Option<String>,
+    /// Minimum numeric value
+    pub min: Option<f64>,
+    /// Maximum numeric value
+    pub max: Option<f64>,
+    /// The only values allowed
+    pub allowed: Option<Vec<String>>,
+    /// The column can't have nulls (empty values)
+    #[serde(default)]
+    pub not_null: bool,
+}
+
+/// Data quality assertions checked on both files while comparing them, read from a YAML file with the
+/// assertions of every column:
+/// ```yaml
+/// email:
+///   pattern: '^[^@]+@[^@]+$'
+///   not_null: true
+/// amount: { min: 0, max: 10000 }
+/// status: { allowed: [open, closed] }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Assertions {
+    columns: BTreeMap<String, ColumnAssertions>,
+}
+
+/// One assertion of a column, with the expression counting the values that don't follow it
+struct Check {
+    column: String,
+    assertion: String,
+    violations: Expr,
+}
+
+impl Assertions {
+    pub fn load(path: &str) -> Result<Assertions> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read assertions {path}"))?;
+        let columns = serde_yaml::from_str(&content)
+            .with_context(|| format!("Couldn't parse assertions {path}"))?;
+        Ok(Assertions { columns })
+    }
+
+    /// Assertions that some value of the files doesn't follow, with how many values don't follow them in each
+    /// file. Every file is read once, checking all the assertions at the same time
+    pub fn check(
+        &self,
+        first_lazy_frame: &LazyFrame,
+        second_lazy_frame: &LazyFrame,
+        columns: &[String],
+    ) -> Result<Vec<AssertionFailure>> {
+        if let Some(column) = self.columns.keys().find(|column| !columns.contains(column)) {
+            bail!("Column {column} of the assertions doesn't exist");
+        }
+        let checks = self.checks()?;
+        let first_violations = count_violations(first_lazy_frame, &checks)?;
+        let second_violations = count_violations(second_lazy_frame, &checks)?;
+        Ok(checks
+            .into_iter()
+            .zip(first_violations.into_iter().zip(second_violations))
+            .filter(|(_, (first_file, second_file))| *first_file > 0 || *second_file > 0)
+            .map(|(check, (first_file, second_file))| AssertionFailure {
+                column: check.column,
+                assertion: check.assertion,
+                first_file,
+                second_file,
+            })
+            .collect())
+    }
+
+    fn checks(&self) -> Result<Vec<Check>> {
+        let mut checks = vec![];
+        for (column, assertions) in &self.columns {
+            let check = |assertion: String, violations: Expr| Check {
+                column: column.to_string(),
+                assertion,
+                violations,
+            };
+            if let Some(pattern) = &assertions.pattern {
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("Invalid pattern of column {column}"))?;
+                checks.push(check(
+                    format!("pattern {pattern}"),
+                    not_matching(col(column), regex),
+                ));
+            }
+            if assertions.min.is_some() || assertions.max.is_some() {
+                let number = col(column).cast(DataType::Float64);
+                let mut out_of_range = number.clone().is_null().and(col(column).is_not_null());
+                if let Some(min) = assertions.min {
+                    out_of_range = out_of_range.or(number.clone().lt(lit(min)));
+                }
+                if let Some(max) = assertions.max {
+                    out_of_range = out_of_range.or(number.gt(lit(max)));
+                }
+                let range =
+                    |limit: Option<f64>| limit.map_or("-".to_string(), |limit| limit.to_string());
+                checks.push(check(
+                    format!(
+                        "between {} and {}",
+                        range(assertions.min),
+                        range(assertions.max)
+                    ),
+                    out_of_range,
+                ));
+            }
+            if let Some(allowed) = &assertions.allowed {
+                let allowed_values = Series::new("allowed", allowed.clone());
+                checks.push(check(
+                    format!("one of {}", allowed.join(", ")),
+                    col(column)
+                        .cast(DataType::Utf8)
+                        .is_in(lit(allowed_values))
+                        .not()
+                        .and(col(column).is_not_null()),
+                ));
+            }
+            if assertions.not_null {
+                checks.push(check("not null".to_string(), col(column).is_null()));
+            }
+        }
+        Ok(checks)
+    }
+}
+
+/// Whether the text of the value doesn't match the regular expression (null for nulls)
+fn not_matching(column: Expr, regex: Regex) -> Expr {
+    column.map(
+        move |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let not_matching: BooleanChunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.map(|value| !regex.is_match(value)))
+                .collect();
+            Ok(Some(not_matching.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Boolean),
+    )
+}
+
+fn count_violations(lazy_frame: &LazyFrame, checks: &[Check]) -> Result<Vec<u64>> {
+    if checks.is_empty() {
+        return Ok(vec![]);
+    }
+    let counts: DataFrame = lazy_frame
+        .clone()
+        .select(
+            checks
+                .iter()
+                .enumerate()
+                .map(|(index, check)| {
+                    check
+                        .violations
+                        .clone()
+                        .cast(DataType::UInt64)
+                        .sum()
+                        .alias(&format!("check_{index}"))
+                })
+                .collect::<Vec<_>>(),
+        )
+        .collect()
+        .context("Couldn't check the assertions")?;
+    (0..checks.len())
+        .map(|index| {
+            Ok(counts
+                .column(&format!("check_{index}"))?
+                .u64()?
+                .get(0)
+                .unwrap_or_default())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const ASSERTIONS: &str = r#"
+email:
+  pattern: '^[^@]+@[^@]+$'
+  not_null: true
+amount: { min: 0, max: 10000 }
+status: { allowed: [open, closed] }
+"#;
+
+    fn load(content: &str) -> Result<Assertions> {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        Assertions::load(file.path().to_str().unwrap())
+    }
+
+    #[test]
+    fn counts_the_values_that_dont_follow_the_assertions() {
+        let first = df!(
+            "email" => [Some("a@b"), Some("bad"), None],
+            "amount" => ["5", "-1", "x"],
+            "status" => [Some("open"), Some("open"), Some("closed")]
+        )
+        .unwrap()
+        .lazy();
+        let second = df!(
+            "email" => [Some("a@b"), Some("c@d"), Some("e@f")],
+            "amount" => ["5", "20000", "3"],
+            "status" => [Some("open"), Some("void"), None]
+        )
+        .unwrap()
+        .lazy();
+        let columns = ["email", "amount", "status"].map(String::from);
+        let failures = load(ASSERTIONS)
+            .unwrap()
+            .check(&first, &second, &columns)
+            .unwrap();
+        let summary: Vec<(&str, &str, u64, u64)> = failures
+            .iter()
+            .map(|failure| {
+                (
+                    failure.column.as_str(),
+                    failure.assertion.as_str(),
+                    failure.first_file,
+                    failure.second_file,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("amount", "between 0 and 10000", 2, 1),
+                ("email", "pattern ^[^@]+@[^@]+$", 1, 0),
+                ("email", "not null", 1, 0),
+                ("status", "one of open, closed", 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_assertions() {
+        assert!(load("email: { regex: '.*' }").is_err());
+        let lazy_frame = df!("email" => ["a@b"]).unwrap().lazy();
+        let columns = ["email".to_string()];
+        let unknown_column = load("amount: { min: 0 }").unwrap();
+        assert!(unknown_column
+            .check(&lazy_frame, &lazy_frame, &columns)
+            .is_err());
+        let invalid_pattern = load("email: { pattern: '(' }").unwrap();
+        assert!(invalid_pattern
+            .check(&lazy_frame, &lazy_frame, &columns)
+            .is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::inputs::temporary_file;
+use anyhow::{bail, Context, Result};
+use apache_avro::schema::{RecordSchema, Schema};
+use apache_avro::types::Value;
+use apache_avro::Reader;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use tempfile::NamedTempFile;
+
+/// Writes the records of an Avro file as a CSV file with a column per field. Nulls are written as empty fields,
+/// bytes in hexadecimal, nested values (records, arrays and maps) as JSON, and logical types
+/// (dates, timestamps...) as their underlying value
+pub fn convert(file_path: &str, separator: char) -> Result<NamedTempFile> {
+    let file = File::open(file_path).with_context(|| format!("Couldn't open {file_path}"))?;
+    let reader = Reader::new(BufReader::new(file))
+        .with_context(|| format!("Error reading Avro file {file_path}"))?;
+    let Schema::Record(RecordSchema { fields, .. }) = reader.writer_schema() else {
+        bail!("The records of Avro file {file_path} aren't records");
+    };
+    let names: Vec<String> = fields.iter().map(|field| field.name.clone()).collect();
+
+    let mut output = temporary_file()?;
+    {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(separator as u8)
+            .from_writer(&mut output);
+        writer.write_record(&names)?;
+        for (index, record) in reader.enumerate() {
+            let record = record.with_context(|| {
+                format!(
+                    "Error reading Avro file {file_path} at record {}",
+                    index + 1
+                )
+            })?;
+            let Value::Record(values) = record else {
+                bail!(
+                    "Error reading Avro file {file_path} at record {}: not a record",
+                    index + 1
+                );
+            };
+            writer.write_record(values.into_iter().map(|(_, value)| as_text(value)))?;
+        }
+        writer.flush()?;
+    }
+    output.flush()?;
+    Ok(output)
+}
+
+fn as_text(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Int(int) | Value::Date(int) | Value::TimeMillis(int) => int.to_string(),
+        Value::Long(long)
+        | Value::TimeMicros(long)
+        | Value::TimestampMillis(long)
+        | Value::TimestampMicros(long)
+        | Value::LocalTimestampMillis(long)
+        | Value::LocalTimestampMicros(long) => long.to_string(),
+        Value::Float(float) => float.to_string(),
+        Value::Double(double) => double.to_string(),
+        Value::String(text) => text,
+        Value::Enum(_, symbol) => symbol,
+        Value::Uuid(uuid) => uuid.to_string(),
+        Value::Bytes(bytes) | Value::Fixed(_, bytes) => {
+            bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+        }
+        Value::Union(_, value) => as_text(*value),
+        value => serde_json::Value::try_from(value)
+            .map(|json| json.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apache_avro::types::Record;
+    use apache_avro::Writer;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "row",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "name", "type": ["null", "string"]},
+            {"name": "data", "type": "bytes"},
+            {"name": "tags", "type": {"type": "array", "items": "string"}}
+        ]
+    }"#;
+
+    #[test]
+    fn converts_the_records_to_csv() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, vec![]);
+        for (id, name) in [(1i64, Some("Ann")), (2, None)] {
+            let mut record = Record::new(writer.schema()).unwrap();
+            record.put("id", id);
+            record.put("name", name.map(str::to_string));
+            record.put("data", Value::Bytes(vec![0x0a, 0xff]));
+            record.put("tags", Value::Array(vec![Value::String("a".to_string())]));
+            writer.append(record).unwrap();
+        }
+        let mut avro_file = tempfile::NamedTempFile::new().unwrap();
+        avro_file.write_all(&writer.into_inner().unwrap()).unwrap();
+
+        let csv_file = convert(avro_file.path().to_str().unwrap(), ',').unwrap();
+        assert_eq!(
+            std::fs::read_to_string(csv_file.path()).unwrap(),
+            "id,name,data,tags\n1,Ann,0aff,\"[\"\"a\"\"]\"\n2,,0aff,\"[\"\"a\"\"]\"\n"
+        );
+    }
+
+    #[test]
+    fn invalid_files_are_errors() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"id,name\n1,Ann\n").unwrap();
+        assert!(convert(file.path().to_str().unwrap(), ',').is_err());
+    }
+}
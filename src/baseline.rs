@@ -0,0 +1,306 @@
+use crate::canonicalize::read_normalized;
+use crate::compare::{as_text, get_text};
+use crate::errors::SourceFile;
+use crate::i18n::{tr, tr_format};
+use crate::output::{Printer, Themed};
+use crate::report::{ColumnFingerprint, ComparisonReport, ComparisonResult};
+use crate::{catch_panic, get_column_names, inputs, table, Args};
+use anyhow::{bail, Context, Result};
+use colored::*;
+use polars::prelude::{col, LazyFrame};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::BufReader;
+
+/// Fingerprint of every column of the file, the key (first column) first. The key column is hashed with its
+/// sorted values, and every other column with the sorted hashes of its (key, value) pairs, so they don't
+/// depend on the order of the rows. Only the key and one column are read at a time
+pub(crate) fn fingerprints(lazy_frame: &LazyFrame, key: &str) -> Result<Vec<ColumnFingerprint>> {
+    let columns = get_column_names(lazy_frame)?;
+    let mut fingerprints = vec![];
+    for column in &columns {
+        let selected = if column == key {
+            vec![col(key)]
+        } else {
+            vec![col(key), col(column)]
+        };
+        let data_frame = lazy_frame
+            .clone()
+            .select(selected)
+            .collect()
+            .with_context(|| format!("Couldn't read column {column}"))?;
+        let keys = as_text(data_frame.column(key)?);
+        let values = as_text(data_frame.column(column)?);
+        let mut row_hashes: Vec<[u8; 32]> = (0..data_frame.height())
+            .map(|row| {
+                let mut hasher = Sha256::new();
+                if column != key {
+                    hash_value(&mut hasher, get_text(&keys, row));
+                }
+                hash_value(&mut hasher, get_text(&values, row));
+                hasher.finalize().into()
+            })
+            .collect();
+        row_hashes.sort_unstable();
+        let mut hasher = Sha256::new();
+        for row_hash in &row_hashes {
+            hasher.update(row_hash);
+        }
+        fingerprints.push(ColumnFingerprint {
+            column: column.to_string(),
+            hash: hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            nulls: values.null_count() as u64,
+        });
+    }
+    Ok(fingerprints)
+}
+
+fn hash_value(hasher: &mut Sha256, value: Option<String>) {
+    match value {
+        Some(value) => {
+            hasher.update([1u8]);
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+        }
+        None => hasher.update([0u8]),
+    }
+}
+
+/// Compares the file with the fingerprints saved in the report of an earlier comparison (--fingerprints),
+/// instead of with the original file
+pub fn compare_with_baseline(
+    args: &Args,
+    printer: &Printer,
+    baseline_report_path: &str,
+    file_path: &str,
+) -> ComparisonReport {
+    let mut report = ComparisonReport::default();
+    if let Err(error) = catch_panic(|| {
+        run_baseline_comparison(&mut report, args, printer, baseline_report_path, file_path)
+    }) {
+        let message = format!("{:#}", error);
+        printer.result(format!("{}: {}", tr("ERROR").fail_status(), message));
+        printer.summary(format!("ERROR: {}", message));
+        report.error = Some(message);
+        report.result = ComparisonResult::Failed;
+    }
+    report
+}
+
+fn run_baseline_comparison(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    baseline_report_path: &str,
+    file_path: &str,
+) -> Result<()> {
+    let baseline_file = File::open(baseline_report_path)
+        .with_context(|| format!("Couldn't open report {baseline_report_path}"))?;
+    let baseline: ComparisonReport = serde_json::from_reader(BufReader::new(baseline_file))
+        .with_context(|| format!("Couldn't parse report {baseline_report_path}"))?;
+    if baseline.fingerprints.is_empty() {
+        bail!("Report {baseline_report_path} has no fingerprints (written with --fingerprints)");
+    }
+    printer.info(format!(
+        "Comparing file {} with the fingerprints in {}",
+        file_path, baseline_report_path
+    ));
+
+    let input = inputs::fetch(file_path, args)?;
+    let (lazy_frame, key) = read_normalized(args, file_path, input.path(), None)?;
+    let source = SourceFile::new(file_path, input.path(), args.separator);
+    report.fingerprints = fingerprints(&lazy_frame, &key).with_context(|| source.context())?;
+    report.rows = lazy_frame
+        .select([col(&key)])
+        .collect()
+        .with_context(|| source.context())?
+        .height() as u64;
+
+    let baseline_columns: Vec<&str> = baseline
+        .fingerprints
+        .iter()
+        .map(|fingerprint| fingerprint.column.as_str())
+        .collect();
+    let columns: Vec<&str> = report
+        .fingerprints
+        .iter()
+        .map(|fingerprint| fingerprint.column.as_str())
+        .collect();
+    printer.result(fingerprints_table(
+        &baseline.fingerprints,
+        &report.fingerprints,
+    ));
+    if baseline_columns != columns {
+        printer.result(format!(
+            "{}: {} [{}], {} [{}]",
+            tr("FILE IS DIFFERENT").fail_status(),
+            tr("baseline columns").fail(),
+            baseline_columns.join(", "),
+            tr("current columns").fail(),
+            columns.join(", ")
+        ));
+        printer.summary("DIFFERENT COLUMNS: the columns are not the ones of the baseline");
+        report.result = ComparisonResult::DifferentColumns;
+        return Ok(());
+    }
+    if baseline.rows != report.rows {
+        printer.result(format!(
+            "{}: {} {} {} {}",
+            tr("FILE IS DIFFERENT").fail_status(),
+            tr("the baseline had").fail(),
+            baseline.rows,
+            tr("rows, now it has").fail(),
+            report.rows
+        ));
+        printer.summary(format!(
+            "DIFFERENT ROWS: {} in the baseline, {} now",
+            baseline.rows, report.rows
+        ));
+        report.result = ComparisonResult::DifferentRows;
+        return Ok(());
+    }
+    let changed_columns: Vec<&str> = baseline
+        .fingerprints
+        .iter()
+        .zip(&report.fingerprints)
+        .filter(|(baseline, current)| baseline.hash != current.hash)
+        .map(|(_, current)| current.column.as_str())
+        .collect();
+    if !changed_columns.is_empty() {
+        printer.result(format!(
+            "{}: {} {}",
+            tr("FILE IS DIFFERENT").fail_status(),
+            tr("Values changed since the baseline in column(s)").fail(),
+            changed_columns.join(" | ").fail().bold()
+        ));
+        printer.summary(format!("DIFFERENT VALUES: {}", changed_columns.join(" | ")));
+        report.result = ComparisonResult::DifferentValues;
+        return Ok(());
+    }
+    printer.result(tr_format(
+        "File {} {}",
+        &[
+            &file_path.bold(),
+            &tr("IS IDENTICAL TO THE BASELINE").pass_status(),
+        ],
+    ));
+    printer.summary(format!("IDENTICAL: {} rows", report.rows));
+    report.result = ComparisonResult::Identical;
+    Ok(())
+}
+
+/// Fingerprints of the baseline and the current file, column by column
+fn fingerprints_table(baseline: &[ColumnFingerprint], current: &[ColumnFingerprint]) -> String {
+    let mut table = table::new_table(vec![
+        "Column",
+        "Baseline hash",
+        "Current hash",
+        "Baseline nulls",
+        "Current nulls",
+    ]);
+    for baseline_fingerprint in baseline {
+        let current_fingerprint = current
+            .iter()
+            .find(|fingerprint| fingerprint.column == baseline_fingerprint.column);
+        table.add_row(vec![
+            table::truncate(&baseline_fingerprint.column),
+            short_hash(&baseline_fingerprint.hash),
+            current_fingerprint
+                .map_or("-".to_string(), |fingerprint| short_hash(&fingerprint.hash)),
+            baseline_fingerprint.nulls.to_string(),
+            current_fingerprint
+                .map_or("-".to_string(), |fingerprint| fingerprint.nulls.to_string()),
+        ]);
+    }
+    table::align_right(&mut table, &[3, 4]);
+    table.to_string()
+}
+
+fn short_hash(hash: &str) -> String {
+    hash.chars().take(12).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn baseline_report() -> NamedTempFile {
+        let lazy_frame = df!("id" => ["1", "2"], "name" => [Some("Ann"), None])
+            .unwrap()
+            .lazy();
+        let report = ComparisonReport {
+            rows: 2,
+            fingerprints: fingerprints(&lazy_frame, "id").unwrap(),
+            ..Default::default()
+        };
+        file(&serde_json::to_string(&report).unwrap())
+    }
+
+    fn compare(baseline: &NamedTempFile, content: &str) -> ComparisonReport {
+        let file = file(content);
+        let path = file.path().to_str().unwrap();
+        let args = Args::from_options(path, path, &[]).unwrap();
+        compare_with_baseline(
+            &args,
+            &Printer::new(true, false, false),
+            baseline.path().to_str().unwrap(),
+            path,
+        )
+    }
+
+    #[test]
+    fn fingerprints_dont_depend_on_the_order_of_the_rows() {
+        let first = df!("id" => ["1", "2"], "name" => ["Ann", "Bob"])
+            .unwrap()
+            .lazy();
+        let second = df!("id" => ["2", "1"], "name" => ["Bob", "Ann"])
+            .unwrap()
+            .lazy();
+        let swapped = df!("id" => ["1", "2"], "name" => ["Bob", "Ann"])
+            .unwrap()
+            .lazy();
+        let first = fingerprints(&first, "id").unwrap();
+        assert_eq!(first, fingerprints(&second, "id").unwrap());
+        let swapped = fingerprints(&swapped, "id").unwrap();
+        assert_eq!(first[0], swapped[0]);
+        assert_ne!(first[1], swapped[1]);
+    }
+
+    #[test]
+    fn compares_the_file_with_the_fingerprints() {
+        let baseline = baseline_report();
+        let result = |content| compare(&baseline, content).result;
+        assert_eq!(result("id,name\n2,\n1,Ann\n"), ComparisonResult::Identical);
+        assert_eq!(
+            result("id,name\n1,Ann\n2,Bob\n"),
+            ComparisonResult::DifferentValues
+        );
+        assert_eq!(result("id,name\n1,Ann\n"), ComparisonResult::DifferentRows);
+        assert_eq!(
+            result("id,city\n1,Ann\n2,\n"),
+            ComparisonResult::DifferentColumns
+        );
+    }
+
+    #[test]
+    fn the_baseline_needs_fingerprints() {
+        let baseline = file(&serde_json::to_string(&ComparisonReport::default()).unwrap());
+        let report = compare(&baseline, "id,name\n1,Ann\n");
+        assert_eq!(report.result, ComparisonResult::Failed);
+        assert!(report.error.unwrap().contains("has no fingerprints"));
+    }
+}
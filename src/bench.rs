@@ -0,0 +1,180 @@
+use crate::output::Printer;
+use crate::report::ComparisonResult;
+use crate::{compare_files, table, Args};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Options changed between the runs of the benchmark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchSettings {
+    pub number_of_columns: usize,
+    pub key_partitions: usize,
+    pub typed: bool,
+    pub low_memory: bool,
+}
+
+impl fmt::Display for BenchSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "-n {}", self.number_of_columns)?;
+        if self.key_partitions > 1 {
+            write!(f, " --key-partitions {}", self.key_partitions)?;
+        }
+        if self.typed {
+            write!(f, " --typed")?;
+        }
+        if self.low_memory {
+            write!(f, " --low-memory")?;
+        }
+        Ok(())
+    }
+}
+
+/// Times of the runs with some settings
+#[derive(Debug)]
+pub struct BenchResult {
+    pub settings: BenchSettings,
+    pub result: ComparisonResult,
+    pub timings: Vec<Duration>,
+}
+
+impl BenchResult {
+    pub fn fastest(&self) -> Duration {
+        self.timings.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.timings.is_empty() {
+            return Duration::ZERO;
+        }
+        self.timings.iter().sum::<Duration>() / self.timings.len() as u32
+    }
+}
+
+/// Every combination of the columns at a time and key partitions given, with and without --typed
+/// and --low-memory
+pub fn settings_grid(columns: &[usize], key_partitions: &[usize]) -> Vec<BenchSettings> {
+    let mut grid = vec![];
+    for &number_of_columns in columns {
+        for &partitions in key_partitions {
+            for typed in [false, true] {
+                for low_memory in [false, true] {
+                    grid.push(BenchSettings {
+                        number_of_columns,
+                        key_partitions: partitions,
+                        typed,
+                        low_memory,
+                    });
+                }
+            }
+        }
+    }
+    grid
+}
+
+/// Compares the files `repeat` times with every settings of the grid (the rest of the options are the given ones),
+/// without printing the results of the comparisons. Returns the results, the fastest first
+pub fn run(
+    args: &Args,
+    printer: &Printer,
+    first_file_path: &str,
+    second_file_path: &str,
+    grid: &[BenchSettings],
+    repeat: usize,
+) -> Vec<BenchResult> {
+    let quiet_printer = Printer::new(true, false, false);
+    let mut results = vec![];
+    for (index, settings) in grid.iter().enumerate() {
+        printer.info(format!("[{}/{}] {}", index + 1, grid.len(), settings));
+        let mut run_args = args.clone();
+        run_args.number_of_columns = settings.number_of_columns;
+        run_args.key_partitions = settings.key_partitions;
+        run_args.typed = settings.typed;
+        run_args.low_memory = settings.low_memory;
+        run_args.no_progress = true;
+        let mut result = BenchResult {
+            settings: *settings,
+            result: ComparisonResult::Identical,
+            timings: vec![],
+        };
+        for _ in 0..repeat.max(1) {
+            let started = Instant::now();
+            let report =
+                compare_files(&run_args, &quiet_printer, first_file_path, second_file_path);
+            result.timings.push(started.elapsed());
+            result.result = report.result;
+        }
+        results.push(result);
+    }
+    results.sort_by_key(|result| result.fastest());
+    results
+}
+
+/// Table with the times of every settings, the fastest first
+pub fn results_table(results: &[BenchResult]) -> String {
+    let mut table = table::new_table(vec!["Options", "Result", "Fastest", "Mean", "Runs"]);
+    for result in results {
+        table.add_row(vec![
+            result.settings.to_string(),
+            result.result.name().to_string(),
+            format!("{:.3}s", result.fastest().as_secs_f64()),
+            format!("{:.3}s", result.mean().as_secs_f64()),
+            result.timings.len().to_string(),
+        ]);
+    }
+    table::align_right(&mut table, &[2, 3, 4]);
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn the_grid_has_every_combination() {
+        let grid = settings_grid(&[1, 10], &[1, 4]);
+        assert_eq!(grid.len(), 16);
+        assert_eq!(grid[0].to_string(), "-n 1");
+        assert_eq!(
+            grid[15].to_string(),
+            "-n 10 --key-partitions 4 --typed --low-memory"
+        );
+    }
+
+    #[test]
+    fn summarizes_the_timings() {
+        let result = BenchResult {
+            settings: settings_grid(&[1], &[1])[0],
+            result: ComparisonResult::Identical,
+            timings: vec![Duration::from_millis(300), Duration::from_millis(100)],
+        };
+        assert_eq!(result.fastest(), Duration::from_millis(100));
+        assert_eq!(result.mean(), Duration::from_millis(200));
+        assert!(results_table(&[result]).contains("0.100s"));
+    }
+
+    #[test]
+    fn runs_every_settings() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"id,name\n1,Ann\n2,Bob\n").unwrap();
+        let path = file.path().to_str().unwrap();
+        let args = Args::from_options(path, path, &[]).unwrap();
+        let grid = settings_grid(&[1], &[1, 2]);
+        let results = run(
+            &args,
+            &Printer::new(true, false, false),
+            path,
+            path,
+            &grid,
+            2,
+        );
+        assert_eq!(results.len(), 8);
+        assert!(results.iter().all(
+            |result| result.timings.len() == 2 && result.result == ComparisonResult::Identical
+        ));
+        assert!(results
+            .windows(2)
+            .all(|pair| pair[0].fastest() <= pair[1].fastest()));
+    }
+}
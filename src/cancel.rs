@@ -0,0 +1,118 @@
+use crate::output::Printer;
+use crate::report::ComparisonReport;
+use crate::{compare_files_with_cancellation, Args};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Flag shared with a running comparison to ask it to stop. The comparison stops before the next
+/// batch of columns and returns a partial report with the result `Aborted`
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// A comparison running in its own thread, that can be cancelled from the host application.
+/// Async code can wait for it with `tokio::task::spawn_blocking(move || handle.join())`
+pub struct ComparisonHandle {
+    token: CancellationToken,
+    thread: JoinHandle<ComparisonReport>,
+}
+
+impl ComparisonHandle {
+    /// Starts comparing the files in a new thread, without printing anything
+    pub fn spawn(args: Args, first_file_path: String, second_file_path: String) -> Self {
+        let token = CancellationToken::new();
+        let thread_token = token.clone();
+        let thread = thread::spawn(move || {
+            let printer = Printer::new(true, false, false);
+            compare_files_with_cancellation(
+                &args,
+                &printer,
+                &first_file_path,
+                &second_file_path,
+                &thread_token,
+            )
+        });
+        ComparisonHandle { token, thread }
+    }
+
+    /// Asks the comparison to stop as soon as possible
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the comparison to finish. Returns None if the comparison panicked
+    pub fn join(self) -> Option<ComparisonReport> {
+        self.thread.join().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ComparisonResult;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn clones_share_the_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn a_cancelled_comparison_is_aborted() {
+        let (first, second) = (file("id,name\n1,Ann\n"), file("id,name\n1,Bob\n"));
+        let (first, second) = (
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+        );
+        let args = Args::from_options(first, second, &[]).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let printer = Printer::new(true, false, false);
+        let report = compare_files_with_cancellation(&args, &printer, first, second, &token);
+        assert_eq!(report.result, ComparisonResult::Aborted);
+    }
+
+    #[test]
+    fn joins_the_comparison_thread() {
+        let (first, second) = (file("id,name\n1,Ann\n"), file("id,name\n1,Ann\n"));
+        let (first, second) = (
+            first.path().to_str().unwrap().to_string(),
+            second.path().to_str().unwrap().to_string(),
+        );
+        let args = Args::from_options(&first, &second, &[]).unwrap();
+        let handle = ComparisonHandle::spawn(args, first, second);
+        let report = handle.join().unwrap();
+        assert_eq!(report.result, ComparisonResult::Identical);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::compare::{as_text, get_text};
+use crate::errors::SourceFile;
+use crate::{apply_normalizations, filters, get_column_names, get_lazy_frame, inputs, Args};
+use anyhow::{bail, Context, Result};
+use polars::prelude::{DataFrame, LazyFrame, Series, SortOptions};
+use std::fs::File;
+use std::io::Write;
+
+/// Applies the exclusions and normalizations of the arguments to the file, sorts it by its first column
+/// and writes it as CSV (to the standard output when there's no output file).
+/// Returns the number of rows written
+pub fn canonicalize_file(args: &Args, file_path: &str, output: Option<&str>) -> Result<u64> {
+    let input = inputs::fetch(file_path, args)?;
+    let (lazy_frame, key) = read_normalized(args, file_path, input.path(), None)?;
+    let data_frame = lazy_frame
+        .sort(
+            &key,
+            SortOptions {
+                maintain_order: true,
+                ..args.sort_options()
+            },
+        )
+        .collect()
+        .with_context(|| SourceFile::new(file_path, input.path(), args.separator).context())?;
+
+    let output_writer: Box<dyn Write> = match output {
+        Some(output_path) => Box::new(
+            File::create(output_path)
+                .with_context(|| format!("Couldn't create file {output_path}"))?,
+        ),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    write_csv(&data_frame, args.separator, output_writer)?;
+
+    Ok(data_frame.height() as u64)
+}
+
+/// Writes the data frame as CSV, with the values as text (nulls as empty fields)
+pub(crate) fn write_csv(
+    data_frame: &DataFrame,
+    separator: char,
+    output_writer: impl Write,
+) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(separator as u8)
+        .from_writer(output_writer);
+    writer.write_record(data_frame.get_column_names())?;
+    let values: Vec<Series> = data_frame.get_columns().iter().map(as_text).collect();
+    for row in 0..data_frame.height() {
+        writer.write_record(
+            values
+                .iter()
+                .map(|series| get_text(series, row).unwrap_or_default()),
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Opens the file with the exclusions and normalizations of the arguments applied.
+/// Returns it with the key column used to exclude rows (the first column when not given)
+pub(crate) fn read_normalized(
+    args: &Args,
+    file_path: &str,
+    local_path: &str,
+    key: Option<&str>,
+) -> Result<(LazyFrame, String)> {
+    let source = SourceFile::new(file_path, local_path, args.separator);
+    let lazy_frame = get_lazy_frame(local_path, args.separator, args.typed, args.low_memory)?;
+    let columns = get_column_names(&lazy_frame).with_context(|| source.context())?;
+    let key = match key {
+        Some(key) if columns.iter().any(|column| column == key) => key.to_string(),
+        Some(key) => bail!("Column {key} not found in {file_path}"),
+        None => columns[0].to_string(),
+    };
+    let excluded_keys = match &args.exclude_keys {
+        Some(keys_file) => filters::read_keys(keys_file)?,
+        None => vec![],
+    };
+    Ok((
+        apply_normalizations(lazy_frame, args, &key, &excluded_keys),
+        key,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn canonicalize(content: &str, options: &[&str]) -> (u64, String) {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let path = file.path().to_str().unwrap();
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        let args = Args::from_options(path, path, &options).unwrap();
+        let output = NamedTempFile::new().unwrap();
+        let rows = canonicalize_file(&args, path, Some(output.path().to_str().unwrap())).unwrap();
+        (rows, std::fs::read_to_string(output.path()).unwrap())
+    }
+
+    #[test]
+    fn sorts_the_rows_by_the_first_column() {
+        assert_eq!(
+            canonicalize("id,name\nb,Bob\na,\nc,Carl\n", &[]),
+            (3, "id,name\na,\nb,Bob\nc,Carl\n".to_string())
+        );
+    }
+
+    #[test]
+    fn applies_the_exclusions() {
+        let (rows, content) = canonicalize(
+            "id,name\nb,Bob\na,Ann\nc,Carl\n",
+            &["--exclude-rows", "name=~^C"],
+        );
+        assert_eq!(rows, 2);
+        assert_eq!(content, "id,name\na,Ann\nb,Bob\n");
+    }
+
+    #[test]
+    fn the_key_must_exist() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"id,name\n1,Ann\n").unwrap();
+        let path = file.path().to_str().unwrap();
+        let args = Args::from_options(path, path, &[]).unwrap();
+        assert!(read_normalized(&args, path, path, Some("missing")).is_err());
+        let (_, key) = read_normalized(&args, path, path, None).unwrap();
+        assert_eq!(key, "id");
+    }
+}
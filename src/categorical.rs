@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use polars::prelude::{col, DataType, Expr, LazyFrame};
+
+/// Rows read to estimate how repetitive the columns are
+const SAMPLE_ROWS: u32 = 10_000;
+
+/// Text columns (but the key) whose distinct values are at most this ratio of the rows, in a sample at the start
+/// of the file. Values like countries or statuses, that take less memory and are faster to compare as categories
+pub(crate) fn repetitive_columns(
+    lazy_frame: &LazyFrame,
+    key: &str,
+    columns: &[String],
+    threshold: f64,
+) -> Result<Vec<String>> {
+    let schema = lazy_frame
+        .schema()
+        .context("Couldn't read the types of the columns")?;
+    let text_columns: Vec<&String> = columns
+        .iter()
+        .filter(|column| column.as_str() != key)
+        .filter(|column| schema.get(column.as_str()) == Some(&DataType::Utf8))
+        .collect();
+    if text_columns.is_empty() {
+        return Ok(vec![]);
+    }
+    let sample = lazy_frame
+        .clone()
+        .limit(SAMPLE_ROWS)
+        .select(
+            text_columns
+                .iter()
+                .map(|column| col(column.as_str()).n_unique())
+                .collect::<Vec<Expr>>(),
+        )
+        .collect()
+        .context("Couldn't count the distinct values of the columns")?;
+    let sample_rows = lazy_frame
+        .clone()
+        .limit(SAMPLE_ROWS)
+        .select([col(key)])
+        .collect()
+        .context("Couldn't count the rows of the sample")?
+        .height();
+    if sample_rows == 0 {
+        return Ok(vec![]);
+    }
+    let mut repetitive_columns = vec![];
+    for column in text_columns {
+        let distinct_values = sample
+            .column(column)?
+            .cast(&DataType::UInt64)?
+            .u64()?
+            .get(0)
+            .unwrap_or(0);
+        if distinct_values as f64 <= threshold * sample_rows as f64 {
+            repetitive_columns.push(column.to_string());
+        }
+    }
+    Ok(repetitive_columns)
+}
+
+/// Reads the columns as categories. Needs the global string cache to compare categories of different files
+pub(crate) fn as_categorical(lazy_frame: LazyFrame, columns: &[String]) -> LazyFrame {
+    if columns.is_empty() {
+        return lazy_frame;
+    }
+    lazy_frame.with_columns(
+        columns
+            .iter()
+            .map(|column| col(column).cast(DataType::Categorical(None, Default::default())))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    #[test]
+    fn finds_the_repetitive_text_columns() {
+        let lazy_frame = df!(
+            "id" => ["1", "2", "3", "4"],
+            "country" => ["ES", "ES", "FR", "ES"],
+            "name" => ["Ann", "Bob", "Carl", "Dan"],
+            "age" => [1, 1, 1, 1]
+        )
+        .unwrap()
+        .lazy();
+        let columns = ["id", "country", "name", "age"].map(String::from);
+        assert_eq!(
+            repetitive_columns(&lazy_frame, "id", &columns, 0.5).unwrap(),
+            ["country"]
+        );
+        assert_eq!(
+            repetitive_columns(&lazy_frame, "id", &columns, 1.0).unwrap(),
+            ["country", "name"]
+        );
+    }
+
+    #[test]
+    fn reads_the_columns_as_categories() {
+        let lazy_frame = df!("id" => ["1"], "country" => ["ES"]).unwrap().lazy();
+        let data_frame = as_categorical(lazy_frame, &["country".to_string()])
+            .collect()
+            .unwrap();
+        assert!(matches!(
+            data_frame.column("country").unwrap().dtype(),
+            DataType::Categorical(..)
+        ));
+        assert_eq!(data_frame.column("id").unwrap().dtype(), &DataType::Utf8);
+    }
+}
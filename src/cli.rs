@@ -0,0 +1,826 @@
+use crate::dirs::PairRule;
+use crate::filters::{KeyWindow, RowCountTolerance, RowExclusion};
+use crate::headers::SortColumn;
+use crate::i18n::Lang;
+use crate::key::KeyType;
+use crate::logging::LogFormat;
+use crate::normalize::{BinaryColumn, ListColumn};
+use crate::notify::NotifyFormat;
+use crate::output::{ColorChoice, Theme};
+use crate::progress::ProgressFormat;
+use crate::ragged::RaggedHandling;
+use crate::redact::RedactMode;
+use crate::report::ComparisonResult;
+use crate::shard::Shard;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use polars::prelude::SortOptions;
+use std::time::Duration;
+
+#[derive(Parser, Debug, Clone)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_override_self = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// First file to compare
+    #[arg(required = true)]
+    pub file1: Option<String>,
+
+    /// Second file to compare
+    #[arg(required_unless_present = "baseline_report")]
+    pub file2: Option<String>,
+
+    /// Whether files are required to have the columns in the same order (default: allow unordered)
+    #[arg(
+        default_value = "false",
+        long,
+        short,
+        env = "CSV_COMPARE_STRICT_COLUMN_ORDER"
+    )]
+    pub strict_column_order: bool,
+
+    /// How many columns to compare at the same time (0 for all of them).
+    /// The bigger the number the faster, but will also increase the memory consumption
+    #[arg(
+        default_value = "1",
+        long,
+        short,
+        env = "CSV_COMPARE_NUMBER_OF_COLUMNS"
+    )]
+    pub number_of_columns: usize,
+
+    /// Compare all the columns at the same time (same as -n 0), the fastest for files that fit in memory
+    #[arg(long, env = "CSV_COMPARE_ALL_COLUMNS")]
+    pub all_columns: bool,
+
+    /// Compare first the columns more likely to be different, guessed from their nulls, distinct values, minimum
+    /// and maximum in the first rows of both files, so different files are found sooner
+    #[arg(long, env = "CSV_COMPARE_LIKELY_DIFFERENT_FIRST")]
+    pub likely_different_first: bool,
+
+    /// Column used to sort the rows of both files (the first one by default), by name or by position in the first
+    /// file as @3 (1-based), for headers with unreliable or duplicated names
+    #[arg(long, env = "CSV_COMPARE_SORT_BY")]
+    pub sort_by: Option<SortColumn>,
+
+    /// Sort the key as text, integers or decimal numbers. By default numeric keys are sorted as text (unless --typed)
+    /// and a warning is shown when that can misalign their rows ("9" and "09")
+    #[arg(long, value_enum, env = "CSV_COMPARE_KEY_TYPE")]
+    pub key_type: Option<KeyType>,
+
+    /// Sort the rows of both files with the null keys first (the default), as ORDER BY ... NULLS FIRST.
+    /// Takes precedence over a --nulls-last set in the config file
+    #[arg(long, conflicts_with = "nulls_last", env = "CSV_COMPARE_NULLS_FIRST")]
+    pub nulls_first: bool,
+
+    /// Sort the rows of both files with the null keys last, as ORDER BY ... NULLS LAST
+    #[arg(long, env = "CSV_COMPARE_NULLS_LAST")]
+    pub nulls_last: bool,
+
+    /// Sort the rows of both files in descending order of the key
+    #[arg(long, env = "CSV_COMPARE_DESCENDING")]
+    pub descending: bool,
+
+    /// Skip the columns before this one (e.g. the ones already compared by a previous run that failed)
+    #[arg(long, env = "CSV_COMPARE_START_AT_COLUMN")]
+    pub start_at_column: Option<String>,
+
+    /// Compare as categories the text columns whose distinct values are at most this ratio of the rows
+    /// (e.g. 0.01), estimated with the first rows. Uses less memory for columns like countries or statuses
+    #[arg(long, env = "CSV_COMPARE_CATEGORICAL_THRESHOLD")]
+    pub categorical_threshold: Option<f64>,
+
+    /// Read the files using less memory (smaller buffers, no rechunking), which is slower.
+    /// Combine it with fewer columns at a time (-n) and --key-partitions for containers with little memory
+    #[arg(long, env = "CSV_COMPARE_LOW_MEMORY")]
+    pub low_memory: bool,
+
+    /// Split the files in this number of ranges of the key (first column), comparing one range at a time,
+    /// so the memory used depends on the size of the ranges instead of the size of the files
+    #[arg(default_value_t = 1, long, env = "CSV_COMPARE_KEY_PARTITIONS")]
+    pub key_partitions: usize,
+
+    /// Column separator character
+    #[arg(default_value = ",", long, short = 'p', env = "CSV_COMPARE_SEPARATOR")]
+    pub separator: char,
+
+    /// What to do with rows that have more or fewer fields than the header
+    #[arg(
+        default_value = "ignore",
+        long,
+        value_enum,
+        env = "CSV_COMPARE_ON_RAGGED"
+    )]
+    pub on_ragged: RaggedHandling,
+
+    /// Infer the data type of the columns (numbers, dates...) instead of comparing every value as text.
+    /// The comparison fails early if a column has a different type in each file
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_TYPED")]
+    pub typed: bool,
+
+    /// Only check the column names (their order with --strict-column-order, their types with --typed)
+    /// and exit without reading the values
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_HEADERS_ONLY")]
+    pub headers_only: bool,
+
+    /// Only compare the number of rows of the files and exit without comparing the columns or values
+    #[arg(
+        default_value = "false",
+        long,
+        conflicts_with = "headers_only",
+        env = "CSV_COMPARE_COUNT_ONLY"
+    )]
+    pub count_only: bool,
+
+    /// Compare the lines of the files as text, without parsing them as CSV, e.g. for malformed files that can't be
+    /// read. Only the hashes of the lines are kept in memory
+    #[arg(
+        default_value = "false",
+        long,
+        conflicts_with_all = ["headers_only", "count_only"],
+        env = "CSV_COMPARE_RAW"
+    )]
+    pub raw: bool,
+
+    /// With --raw, sort the lines before comparing them, so their order doesn't matter
+    #[arg(
+        default_value = "false",
+        long,
+        requires = "raw",
+        env = "CSV_COMPARE_RAW_SORT"
+    )]
+    pub raw_sort: bool,
+
+    /// YAML file with data quality assertions of some columns (pattern, min, max, allowed values, not_null),
+    /// checked on both files and reported in their own table
+    #[arg(long, env = "CSV_COMPARE_ASSERTIONS")]
+    pub assertions: Option<String>,
+
+    /// Fail if the key has the same value in more than one row of any of the files, listing the most
+    /// repeated ones (up to --samples)
+    #[arg(long, env = "CSV_COMPARE_ASSERT_UNIQUE_KEY")]
+    pub assert_unique_key: bool,
+
+    /// Only compare the number of rows and of nulls of every column, reading each file once. The fastest check
+    /// for values dropped by a new loader
+    #[arg(
+        long,
+        conflicts_with_all = ["headers_only", "count_only"],
+        env = "CSV_COMPARE_NULLS_ONLY"
+    )]
+    pub nulls_only: bool,
+
+    /// Only compare the number of distinct values of every column, e.g. to find a column whose values collapsed
+    /// because of a bad join even if the files have the same rows
+    #[arg(
+        long,
+        conflicts_with_all = ["headers_only", "count_only", "nulls_only"],
+        env = "CSV_COMPARE_CARDINALITY"
+    )]
+    pub cardinality: bool,
+
+    /// Only compare the K most frequent values of every column, finding the columns whose most frequent values
+    /// are different (e.g. a country recoded from GB to UK)
+    #[arg(
+        long,
+        value_name = "K",
+        conflicts_with_all = ["headers_only", "count_only", "nulls_only", "cardinality"],
+        env = "CSV_COMPARE_TOP_VALUES"
+    )]
+    pub top_values: Option<usize>,
+
+    /// Only compare statistics of the columns (mean, null rate, distinct values) with the drift allowed for each
+    /// column in a YAML file, failing or only warning when they drift more
+    #[arg(
+        long,
+        conflicts_with_all = ["headers_only", "count_only", "nulls_only", "cardinality", "top_values"],
+        env = "CSV_COMPARE_DRIFT_RULES"
+    )]
+    pub drift_rules: Option<String>,
+
+    /// Change (in percentage) of the distinct values of a column allowed with --cardinality
+    #[arg(default_value = "0", long, env = "CSV_COMPARE_CARDINALITY_TOLERANCE")]
+    pub cardinality_tolerance: f64,
+
+    /// Also compare the text of the rows, reporting those whose values are the same but encoded differently
+    /// (quoted or unquoted fields, escaped quotes), which tools like diff see as different. Hashes of the rows of
+    /// the first file are kept in memory, about 100 bytes per row. Not available with --redact, because the rows are shown as they are written
+    #[arg(
+        long,
+        conflicts_with = "redact",
+        env = "CSV_COMPARE_ENCODING_DIFFERENCES"
+    )]
+    pub encoding_differences: bool,
+
+    /// Before comparing the values, compare how many rows every key has in both files and stop if some key
+    /// has a different number of rows (duplicated or dropped records), listing up to --samples of them
+    #[arg(long, env = "CSV_COMPARE_KEY_COUNTS")]
+    pub key_counts: bool,
+
+    /// Remove this prefix from the column names of the first file before matching them (e.g. src_)
+    #[arg(long, env = "CSV_COMPARE_STRIP_PREFIX1")]
+    pub strip_prefix1: Option<String>,
+
+    /// Remove this suffix from the column names of the first file before matching them (e.g. _v1)
+    #[arg(long, env = "CSV_COMPARE_STRIP_SUFFIX1")]
+    pub strip_suffix1: Option<String>,
+
+    /// Remove this prefix from the column names of the second file before matching them
+    #[arg(long, env = "CSV_COMPARE_STRIP_PREFIX2")]
+    pub strip_prefix2: Option<String>,
+
+    /// Remove this suffix from the column names of the second file before matching them
+    #[arg(long, env = "CSV_COMPARE_STRIP_SUFFIX2")]
+    pub strip_suffix2: Option<String>,
+
+    /// YAML file with the other names of some columns (e.g. `customer_id: [cust_id, client_id]`), so columns
+    /// renamed in some version of the files are still compared
+    #[arg(long, env = "CSV_COMPARE_ALIASES")]
+    pub aliases: Option<String>,
+
+    /// Compare the columns by their position instead of their name, for files with the same layout
+    /// but different headers. The columns of the second file take the names of the first file
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_MATCH_BY_POSITION")]
+    pub match_by_position: bool,
+
+    /// Match the column names ignoring their case (Customer_ID and customer_id are the same column).
+    /// Spaces around the names are always ignored
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_IGNORE_COLUMN_CASE")]
+    pub ignore_column_case: bool,
+
+    /// File with keys (values of the first column), one per line, whose rows are left out of the comparison
+    #[arg(long, env = "CSV_COMPARE_EXCLUDE_KEYS")]
+    pub exclude_keys: Option<String>,
+
+    /// Leave out of the comparison the rows whose column matches a regular expression: column=~regex.
+    /// Can be repeated
+    #[arg(long, env = "CSV_COMPARE_EXCLUDE_ROWS")]
+    pub exclude_rows: Vec<RowExclusion>,
+
+    /// Compare only the rows whose key (first column) is in a window: lower..upper, or lower..=upper to include the
+    /// upper key. Either bound can be left out. Keys are compared as numbers when they are numeric (typed or
+    /// --key-type) and as text otherwise, so numeric bounds need a numeric key
+    #[arg(long, env = "CSV_COMPARE_KEY_RANGE")]
+    pub key_range: Option<KeyWindow>,
+
+    /// Accept a different number of rows in both files up to N rows or P% of the rows (e.g. 10 or 0.5%), comparing
+    /// only the rows with keys in both files and reporting the missing and added ones
+    #[arg(long, env = "CSV_COMPARE_ROW_COUNT_TOLERANCE")]
+    pub row_count_tolerance: Option<RowCountTolerance>,
+
+    /// Comma separated list of columns where null, empty and whitespace-only values are all equal
+    #[arg(long, value_delimiter = ',', env = "CSV_COMPARE_NULLISH_COLUMNS")]
+    pub nullish_columns: Vec<String>,
+
+    /// Comma separated list of columns with JSON values, compared structurally (ignoring the order of the keys
+    /// and whitespace)
+    #[arg(long, value_delimiter = ',', env = "CSV_COMPARE_JSON_COLUMNS")]
+    pub json_columns: Vec<String>,
+
+    /// Comma separated list of paths ignored in the JSON columns, e.g. meta.updated_at or items.*.id
+    #[arg(long, value_delimiter = ',', env = "CSV_COMPARE_IGNORE_JSON_PATHS")]
+    pub ignore_json_paths: Vec<String>,
+
+    /// Columns with lists of elements compared in any order: column[:delimiter[:sorted|set]]
+    /// (default delimiter ;). Can be repeated
+    #[arg(long, env = "CSV_COMPARE_LIST_COLUMNS")]
+    pub list_columns: Vec<ListColumn>,
+
+    /// Columns with binary values encoded as text, decoded before comparing their bytes:
+    /// column:base64|hex[:bytes|size|hash]. With size or hash only the number of bytes or their SHA-256 is
+    /// compared, for large payloads. Can be repeated
+    #[arg(long, env = "CSV_COMPARE_BINARY_COLUMNS")]
+    pub binary_columns: Vec<BinaryColumn>,
+
+    /// Rename duplicate column names (amount, amount_2, amount_3...) instead of failing
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_DEDUPE_HEADERS")]
+    pub dedupe_headers: bool,
+
+    /// Exit code used when the files have a different number of rows
+    #[arg(default_value = "4", long, env = "CSV_COMPARE_EXIT_CODE_ROWS")]
+    pub exit_code_rows: i32,
+
+    /// Exit code used when the files have different columns
+    #[arg(default_value = "2", long, env = "CSV_COMPARE_EXIT_CODE_COLUMNS")]
+    pub exit_code_columns: i32,
+
+    /// Exit code used when the values of some column are different
+    #[arg(default_value = "3", long, env = "CSV_COMPARE_EXIT_CODE_VALUES")]
+    pub exit_code_values: i32,
+
+    /// Exit code used when a column has a different data type in each file (only with --typed)
+    #[arg(default_value = "7", long, env = "CSV_COMPARE_EXIT_CODE_TYPES")]
+    pub exit_code_types: i32,
+
+    /// Exit code used when the comparison is aborted before comparing all the columns
+    #[arg(default_value = "5", long, env = "CSV_COMPARE_EXIT_CODE_ABORTED")]
+    pub exit_code_aborted: i32,
+
+    /// Exit code used when the comparison takes longer than --timeout
+    #[arg(default_value = "8", long, env = "CSV_COMPARE_EXIT_CODE_TIMEOUT")]
+    pub exit_code_timeout: i32,
+
+    /// Exit code used when both files are empty or have no rows
+    #[arg(default_value = "0", long, env = "CSV_COMPARE_EXIT_CODE_EMPTY")]
+    pub exit_code_empty: i32,
+
+    /// Exit code used when the files are identical but their columns are in a different order. 0 by default, as
+    /// the order doesn't change the comparison (e.g. 9 to fail on it)
+    #[arg(default_value = "0", long, env = "CSV_COMPARE_EXIT_CODE_ORDER")]
+    pub exit_code_order: i32,
+
+    /// Exit code used when a file is not valid (validate command, or ragged rows with --on-ragged error)
+    #[arg(default_value = "6", long, env = "CSV_COMPARE_EXIT_CODE_INVALID")]
+    pub exit_code_invalid: i32,
+
+    /// Exit code used when the key has duplicated values (only with --assert-unique-key)
+    #[arg(
+        default_value = "10",
+        long,
+        env = "CSV_COMPARE_EXIT_CODE_DUPLICATED_KEYS"
+    )]
+    pub exit_code_duplicated_keys: i32,
+
+    /// Exit code used when the files are identical but some values don't follow the assertions (--assertions)
+    #[arg(default_value = "11", long, env = "CSV_COMPARE_EXIT_CODE_ASSERTIONS")]
+    pub exit_code_assertions: i32,
+
+    /// Exit code used when the comparison couldn't run (a file missing or malformed, not enough memory...),
+    /// far from the codes of the results so failures can be retried but differences not. Even with --exit-zero
+    #[arg(default_value = "70", long, env = "CSV_COMPARE_EXIT_CODE_FAILED")]
+    pub exit_code_failed: i32,
+
+    /// Always exit with code 0, even if the files are different (report-only runs)
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_EXIT_ZERO")]
+    pub exit_zero: bool,
+
+    /// Stop the comparison after this time (e.g. 90s, 30m, 2h), printing the columns compared so far.
+    /// It's checked before every batch of columns
+    #[arg(long, value_parser = humantime::parse_duration, env = "CSV_COMPARE_TIMEOUT")]
+    pub timeout: Option<Duration>,
+
+    /// Add the fingerprints of the columns of the first file to the JSON report, so it can be used later
+    /// as --baseline-report
+    #[arg(long, env = "CSV_COMPARE_FINGERPRINTS")]
+    pub fingerprints: bool,
+
+    /// Compare the (only) file with the fingerprints saved in the JSON report of an earlier comparison
+    /// (written with --fingerprints) instead of with a second file
+    #[arg(long, env = "CSV_COMPARE_BASELINE_REPORT")]
+    pub baseline_report: Option<String>,
+
+    /// Compare only the rows after this number of rows, e.g. the rows appended since the last comparison
+    #[arg(long, env = "CSV_COMPARE_SINCE_OFFSET")]
+    pub since_offset: Option<u64>,
+
+    /// Compare only the rows whose key (first column) is greater than this one, for files whose keys only grow
+    #[arg(long, env = "CSV_COMPARE_SINCE_KEY")]
+    pub since_key: Option<String>,
+
+    /// JSON file with the point where the next comparison starts. It's read when neither --since-offset
+    /// nor --since-key are given, and updated after every identical comparison
+    #[arg(long, env = "CSV_COMPARE_CHECKPOINT")]
+    pub checkpoint: Option<String>,
+
+    /// Compare only a part of the columns, as index/count (e.g. 2/4), so several runs (on different machines)
+    /// compare all the columns between them. Their JSON reports can be combined with merge-reports
+    #[arg(long, env = "CSV_COMPARE_SHARD")]
+    pub shard: Option<Shard>,
+
+    /// Don't print anything, only set the exit code
+    #[arg(
+        default_value = "false",
+        long,
+        short,
+        conflicts_with = "summary_only",
+        env = "CSV_COMPARE_QUIET"
+    )]
+    pub quiet: bool,
+
+    /// Only print a single final line with the result of the comparison
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_SUMMARY_ONLY")]
+    pub summary_only: bool,
+
+    /// Increase the verbosity of the diagnostic logs written to stderr (-v debug, -vv trace).
+    /// From the environment it's the number of levels, e.g. CSV_COMPARE_VERBOSE=2
+    #[arg(long, short, action = clap::ArgAction::Count, env = "CSV_COMPARE_VERBOSE")]
+    pub verbose: u8,
+
+    /// Format of the diagnostic logs
+    #[arg(
+        default_value = "text",
+        long,
+        value_enum,
+        env = "CSV_COMPARE_LOG_FORMAT"
+    )]
+    pub log_format: LogFormat,
+
+    /// age identity file used to decrypt .age files
+    #[arg(long, env = "CSV_COMPARE_AGE_IDENTITY")]
+    pub age_identity: Option<String>,
+
+    /// GPG secret key file used to decrypt .gpg/.pgp/.asc files (the keyring of the user when not given)
+    #[arg(long, env = "CSV_COMPARE_GPG_KEY")]
+    pub gpg_key: Option<String>,
+
+    /// File compared inside zip, tar and tar.gz archives, e.g. data/extract.csv (the only file of the archive
+    /// when not given). It can also be given with the path of the archive: archive.zip!data/extract.csv
+    #[arg(long, env = "CSV_COMPARE_ARCHIVE_MEMBER")]
+    pub archive_member: Option<String>,
+
+    /// Proxy used to download http(s):// files, e.g. http://proxy.corp:3128 (HTTPS_PROXY/HTTP_PROXY when not given).
+    /// Hosts in NO_PROXY are downloaded directly
+    #[arg(long, env = "CSV_COMPARE_PROXY")]
+    pub proxy: Option<String>,
+
+    /// AWS profile used to read s3://bucket/key files (default credentials chain when not given)
+    #[arg(long, env = "CSV_COMPARE_AWS_PROFILE")]
+    pub aws_profile: Option<String>,
+
+    /// ARN of the role assumed to read s3://bucket/key files
+    #[arg(long, env = "CSV_COMPARE_AWS_ROLE_ARN")]
+    pub aws_role_arn: Option<String>,
+
+    /// Custom S3 endpoint (e.g. MinIO at http://localhost:9000). Buckets are addressed by path
+    #[arg(long, env = "CSV_COMPARE_S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+
+    /// Service account file used to read gs://bucket/key files (default credentials when not given)
+    #[arg(long, env = "CSV_COMPARE_GCS_SERVICE_ACCOUNT")]
+    pub gcs_service_account: Option<String>,
+
+    /// OTLP (HTTP) endpoint where the spans of the comparison are exported, e.g. http://localhost:4318/v1/traces
+    #[arg(long, env = "CSV_COMPARE_OTEL_ENDPOINT")]
+    pub otel_endpoint: Option<String>,
+
+    /// When to use colors in the output (NO_COLOR env variable is honored in auto mode)
+    #[arg(default_value = "auto", long, value_enum, env = "CSV_COMPARE_COLOR")]
+    pub color: ColorChoice,
+
+    /// Disable colors in the output. Same as --color never
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_NO_COLOR")]
+    pub no_color: bool,
+
+    /// Colors of the passed, warning and failed checks. The ascii theme uses no colors, but [OK], [WARN] and [FAIL]
+    /// markers
+    #[arg(default_value = "default", long, value_enum, env = "CSV_COMPARE_THEME")]
+    pub theme: Theme,
+
+    /// Language of the messages (en or es). By default, the one of the locale (LC_ALL, LC_MESSAGES or LANG)
+    #[arg(long, value_enum, env = "CSV_COMPARE_LANG")]
+    pub lang: Option<Lang>,
+
+    /// Don't show the progress of the comparison. When stderr is not a terminal,
+    /// periodic plain text lines are printed instead of the progress bar
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_NO_PROGRESS")]
+    pub no_progress: bool,
+
+    /// How to report the progress: a progress bar or JSON events (one per line) in stderr
+    #[arg(
+        default_value = "bar",
+        long,
+        value_enum,
+        env = "CSV_COMPARE_PROGRESS_FORMAT"
+    )]
+    pub progress_format: ProgressFormat,
+
+    /// Print a table with the time and peak memory spent comparing each batch of columns, and the
+    /// resources used by the whole comparison (time, peak memory, bytes read, temporary disk)
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_TIMINGS")]
+    pub timings: bool,
+
+    /// Keep comparing after finding a different column, to report all the different columns
+    /// ranked by number of different rows (default: stop at the first difference)
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_REPORT_ALL")]
+    pub report_all: bool,
+
+    /// How many examples of different values to show for each different column
+    #[arg(default_value = "5", long, env = "CSV_COMPARE_SAMPLES")]
+    pub samples: usize,
+
+    /// Comma separated list of sensitive columns whose values are hidden in the examples of differences and the
+    /// most frequent values (and in the listed keys when the key is redacted)
+    #[arg(long, value_delimiter = ',', env = "CSV_COMPARE_REDACT")]
+    pub redact: Vec<String>,
+
+    /// How the values of the redacted columns are hidden
+    #[arg(
+        default_value = "mask",
+        long,
+        value_enum,
+        env = "CSV_COMPARE_REDACT_MODE"
+    )]
+    pub redact_mode: RedactMode,
+
+    /// File with the secret key of the hashes of --redact-mode hash, so the hashes of the runs with the same key can
+    /// be matched. Without it every run uses a random key
+    #[arg(long, env = "CSV_COMPARE_REDACT_KEY_FILE")]
+    pub redact_key_file: Option<String>,
+
+    /// Write the rows of the first file with differences to this CSV file, with a <column>_status column
+    /// (equal/changed/missing) for every compared column
+    #[arg(long, env = "CSV_COMPARE_ANNOTATE")]
+    pub annotate: Option<String>,
+
+    /// Write the report of the comparison as JSON to this file
+    #[arg(long, env = "CSV_COMPARE_REPORT_JSON")]
+    pub report_json: Option<String>,
+
+    /// Write metrics of the comparison (rows, differences, duration, result) to this file in the Prometheus
+    /// text format, for the textfile collector of the node exporter
+    #[arg(long, env = "CSV_COMPARE_METRICS_FILE")]
+    pub metrics_file: Option<String>,
+
+    /// Webhook URL where a summary of the comparison is POSTed when it finishes (also when it fails)
+    #[arg(long, env = "CSV_COMPARE_NOTIFY_URL")]
+    pub notify_url: Option<String>,
+
+    /// Body of the notification: the summary as JSON or a Slack message
+    #[arg(
+        default_value = "json",
+        long,
+        value_enum,
+        env = "CSV_COMPARE_NOTIFY_FORMAT"
+    )]
+    pub notify_format: NotifyFormat,
+
+    /// Don't use $PAGER (or less) when the result doesn't fit in the terminal
+    #[arg(default_value = "false", long, env = "CSV_COMPARE_NO_PAGER")]
+    pub no_pager: bool,
+
+    /// Config file with default options and profiles (default: ~/.config/csv-compare/config.toml)
+    #[arg(long, env = "CSV_COMPARE_CONFIG")]
+    pub config: Option<String>,
+
+    /// Name of the profile of the config file whose options are used
+    #[arg(long, env = "CSV_COMPARE_PROFILE")]
+    pub profile: Option<String>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Compare a file in two git revisions (default: HEAD with the working tree)
+    Git {
+        /// Path of the file in the repository
+        path: String,
+
+        /// Revisions to compare: A..B compares A with B, A compares A with the working tree
+        #[arg(long)]
+        rev: Option<String>,
+    },
+    /// Compare the files passed by git difftool ($LOCAL $REMOTE) or by a diff driver (7 arguments)
+    Difftool {
+        #[arg(num_args = 2..=7)]
+        paths: Vec<String>,
+    },
+    /// Compare the files of two directories (and their subdirectories), pairing the files with the same name
+    Dirs {
+        /// First directory
+        first_dir: String,
+
+        /// Second directory
+        second_dir: String,
+
+        /// Pair files with different names, e.g. '{name}_v1.csv={name}_final.csv'. Can be repeated,
+        /// the first rule that finds a file in the second directory wins
+        #[arg(long = "pair", env = "CSV_COMPARE_PAIR", value_delimiter = ',')]
+        pairs: Vec<PairRule>,
+
+        /// Pairs compared at the same time. With more than one, only the table with the results is printed
+        #[arg(long, default_value_t = 1, env = "CSV_COMPARE_JOBS")]
+        jobs: usize,
+    },
+    /// Check that a single file is well formed: parseable, same number of fields in every row,
+    /// no duplicate column names and, optionally, the expected columns
+    Validate {
+        /// File to validate
+        file: String,
+
+        /// Comma separated list of the columns the file must have
+        #[arg(long, value_delimiter = ',', env = "CSV_COMPARE_EXPECTED_COLUMNS")]
+        expected_columns: Vec<String>,
+
+        /// Print the result as JSON
+        #[arg(default_value = "false", long, env = "CSV_COMPARE_VALIDATE_JSON")]
+        json: bool,
+    },
+    /// Apply the exclusions and normalizations of the options to a file and write it sorted by its first column,
+    /// so it can be compared with plain diff or hashed
+    Canonicalize {
+        /// File to canonicalize
+        file: String,
+
+        /// File where the result is written (default: standard output)
+        #[arg(long, short, env = "CSV_COMPARE_CANONICALIZE_OUTPUT")]
+        output: Option<String>,
+    },
+    /// Print a hash of the content of a file that doesn't depend on the order of the rows and columns,
+    /// with the exclusions and normalizations of the options applied
+    Hash {
+        /// File to hash
+        file: String,
+
+        /// Key column used to exclude rows with --exclude-keys (default: first column)
+        #[arg(long, env = "CSV_COMPARE_KEY")]
+        key: Option<String>,
+
+        /// Print the result as JSON
+        #[arg(default_value = "false", long, env = "CSV_COMPARE_HASH_JSON")]
+        json: bool,
+    },
+    /// Combine the JSON reports of many comparisons (written with --report-json) into a single roll-up
+    /// with the totals and the worst columns
+    MergeReports {
+        /// JSON reports to combine
+        #[arg(required = true)]
+        reports: Vec<String>,
+
+        /// File where the roll-up is written, as HTML if it ends with .html and as JSON otherwise
+        #[arg(long, short, env = "CSV_COMPARE_MERGE_REPORTS_OUTPUT")]
+        output: Option<String>,
+    },
+    /// Print the JSON schema of the reports written with --report-json
+    ReportSchema,
+    /// Compare two files several times with every combination of columns at a time, key partitions,
+    /// --typed and --low-memory, and print the times, the fastest first
+    Bench {
+        /// First file to compare
+        file1: String,
+
+        /// Second file to compare
+        file2: String,
+
+        /// Columns at a time (-n) to try
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_value = "1,5,20",
+            env = "CSV_COMPARE_BENCH_COLUMNS"
+        )]
+        columns: Vec<usize>,
+
+        /// Key partitions (--key-partitions) to try
+        #[arg(
+            long,
+            value_delimiter = ',',
+            default_value = "1",
+            env = "CSV_COMPARE_BENCH_PARTITIONS"
+        )]
+        partitions: Vec<usize>,
+
+        /// Times every combination is run
+        #[arg(long, default_value_t = 3, env = "CSV_COMPARE_BENCH_REPEAT")]
+        repeat: usize,
+    },
+    /// Run an HTTP service that compares files on request and returns JSON reports
+    Serve {
+        /// Address to listen on. The service has no authentication, listen on other addresses only behind a proxy
+        /// that has it
+        #[arg(default_value = "127.0.0.1", long, env = "CSV_COMPARE_HOST")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(default_value = "8080", long, env = "CSV_COMPARE_PORT")]
+        port: u16,
+
+        /// Directory the files of the requests must be in, given relative to it. Without it, the requests can
+        /// compare any file or URL the process can read
+        #[arg(long, env = "CSV_COMPARE_ROOT")]
+        root: Option<String>,
+
+        /// Seconds the finished jobs are kept for GET /jobs/{id}
+        #[arg(default_value = "3600", long, env = "CSV_COMPARE_JOB_TTL")]
+        job_ttl: u64,
+
+        /// Number of comparisons that can run at the same time (default: number of CPUs)
+        #[arg(long, env = "CSV_COMPARE_WORKERS")]
+        workers: Option<usize>,
+    },
+}
+
+impl ComparisonResult {
+    /// Exit code of the process for this result, as configured in the arguments
+    pub fn exit_code(&self, args: &Args) -> i32 {
+        if args.exit_zero && *self != ComparisonResult::Failed {
+            return 0;
+        }
+        match self {
+            ComparisonResult::Identical => 0,
+            ComparisonResult::DifferentColumns => args.exit_code_columns,
+            ComparisonResult::DifferentValues => args.exit_code_values,
+            ComparisonResult::DifferentRows => args.exit_code_rows,
+            ComparisonResult::DifferentTypes => args.exit_code_types,
+            ComparisonResult::Aborted => args.exit_code_aborted,
+            ComparisonResult::TimedOut => args.exit_code_timeout,
+            ComparisonResult::InvalidFile => args.exit_code_invalid,
+            ComparisonResult::Empty => args.exit_code_empty,
+            ComparisonResult::DifferentColumnOrder => args.exit_code_order,
+            ComparisonResult::Failed => args.exit_code_failed,
+            ComparisonResult::DuplicatedKeys => args.exit_code_duplicated_keys,
+            ComparisonResult::FailedAssertions => args.exit_code_assertions,
+        }
+    }
+}
+
+impl Args {
+    /// Builds the arguments of a comparison as if they were given in the command line. Only the options given
+    /// count: the environment variables (CSV_COMPARE_*) configure the command line, not the comparisons of the
+    /// library or of the service
+    pub fn from_options(file1: &str, file2: &str, options: &[String]) -> Result<Args, String> {
+        let mut command_line = vec!["csv-compare".to_string()];
+        command_line.extend(options.iter().cloned());
+        command_line.push(file1.to_string());
+        command_line.push(file2.to_string());
+        let matches = Args::command()
+            .mut_args(|arg| arg.env(None::<&str>))
+            .try_get_matches_from(command_line)
+            .map_err(|error| error.to_string())?;
+        Args::from_arg_matches(&matches).map_err(|error| error.to_string())
+    }
+
+    /// Arguments with the defaults of the command line and no files, for comparisons built from code
+    pub fn defaults() -> Args {
+        let mut args = Args::from_options("first", "second", &[])
+            .expect("The defaults of the command line are valid");
+        args.file1 = None;
+        args.file2 = None;
+        args
+    }
+
+    /// How the rows of both files are sorted by the key (--descending, --nulls-last). Rows with the same key keep
+    /// the order of the file, so the differences found don't depend on the threads of the sort
+    pub fn sort_options(&self) -> SortOptions {
+        SortOptions {
+            descending: self.descending,
+            nulls_last: self.nulls_last && !self.nulls_first,
+            maintain_order: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether all the columns are compared at the same time (--all-columns or -n 0)
+    pub fn compares_all_columns(&self) -> bool {
+        self.all_columns || self.number_of_columns == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &[&str]) -> Args {
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        Args::from_options("a.csv", "b.csv", &options).unwrap()
+    }
+
+    #[test]
+    fn options_ignore_the_environment() {
+        std::env::set_var("CSV_COMPARE_REPORT_ALL", "true");
+        let report_all = args(&[]).report_all;
+        std::env::remove_var("CSV_COMPARE_REPORT_ALL");
+        assert!(!report_all);
+        assert!(args(&["--report-all"]).report_all);
+    }
+
+    #[test]
+    fn nulls_first_takes_precedence_over_nulls_last() {
+        assert!(!args(&[]).sort_options().nulls_last);
+        assert!(args(&["--nulls-last"]).sort_options().nulls_last);
+        let mut both = args(&["--nulls-last"]);
+        both.nulls_first = true;
+        assert!(!both.sort_options().nulls_last);
+        let options = ["--nulls-first", "--nulls-last"].map(String::from);
+        assert!(Args::from_options("a.csv", "b.csv", &options).is_err());
+    }
+
+    #[test]
+    fn subcommands_have_their_own_environment_variables() {
+        let env_vars: Vec<String> = Args::command()
+            .get_subcommands()
+            .flat_map(|subcommand| subcommand.get_arguments())
+            .filter_map(|arg| arg.get_env())
+            .map(|env_var| env_var.to_string_lossy().to_string())
+            .collect();
+        let unique: std::collections::HashSet<_> = env_vars.iter().collect();
+        assert_eq!(unique.len(), env_vars.len());
+    }
+
+    #[test]
+    fn a_different_column_order_exits_with_zero_by_default() {
+        assert_eq!(
+            ComparisonResult::DifferentColumnOrder.exit_code(&args(&[])),
+            0
+        );
+        assert_eq!(
+            ComparisonResult::DifferentColumnOrder.exit_code(&args(&["--exit-code-order", "9"])),
+            9
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::inputs::temporary_file;
+use crate::Args;
+use anyhow::{anyhow, Context, Result};
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::BehaviorVersion;
+use futures::StreamExt;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::ObjectStore;
+use std::io::Write;
+use tempfile::NamedTempFile;
+use tracing::debug;
+
+/// Downloads s3://bucket/key to a temporary file. The credentials are taken from the AWS profile
+/// (or the default chain), assuming the role when given, from the custom endpoint if any (e.g. MinIO)
+pub fn download_s3(location: &str, args: &Args) -> Result<NamedTempFile> {
+    let (bucket, key) = split_location(location)?;
+    debug!("Downloading s3://{}/{}", bucket, key);
+    runtime()?.block_on(async {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(profile) = &args.aws_profile {
+            loader = loader.profile_name(profile);
+        }
+        if let Some(endpoint) = &args.s3_endpoint {
+            loader = loader.endpoint_url(endpoint);
+        }
+        let sdk_config = loader.load().await;
+
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            // Custom endpoints (MinIO...) usually don't support virtual-hosted buckets
+            .force_path_style(args.s3_endpoint.is_some());
+        if let Some(role_arn) = &args.aws_role_arn {
+            let role_provider = AssumeRoleProvider::builder(role_arn)
+                .session_name(env!("CARGO_PKG_NAME"))
+                .configure(&sdk_config)
+                .build()
+                .await;
+            s3_config = s3_config.credentials_provider(role_provider);
+        }
+        let client = aws_sdk_s3::Client::from_conf(s3_config.build());
+
+        let mut object = client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Couldn't download s3://{location}"))?;
+        let mut file = temporary_file()?;
+        while let Some(bytes) = object
+            .body
+            .try_next()
+            .await
+            .with_context(|| format!("Couldn't download s3://{location}"))?
+        {
+            file.write_all(&bytes)?;
+        }
+        file.flush()?;
+        Ok(file)
+    })
+}
+
+/// Downloads gs://bucket/key to a temporary file, authenticating with the service account file
+/// when given (or with the default credentials of the environment)
+pub fn download_gcs(location: &str, args: &Args) -> Result<NamedTempFile> {
+    let (bucket, key) = split_location(location)?;
+    debug!("Downloading gs://{}/{}", bucket, key);
+    let mut builder = GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket);
+    if let Some(service_account) = &args.gcs_service_account {
+        builder = builder.with_service_account_path(service_account);
+    }
+    let store = builder
+        .build()
+        .with_context(|| format!("Couldn't connect to gs://{bucket}"))?;
+
+    runtime()?.block_on(async {
+        let mut stream = store
+            .get(&object_store::path::Path::from(key))
+            .await
+            .with_context(|| format!("Couldn't download gs://{location}"))?
+            .into_stream();
+        let mut file = temporary_file()?;
+        while let Some(bytes) = stream.next().await {
+            file.write_all(&bytes.with_context(|| format!("Couldn't download gs://{location}"))?)?;
+        }
+        file.flush()?;
+        Ok(file)
+    })
+}
+
+fn split_location(location: &str) -> Result<(&str, &str)> {
+    location
+        .split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| anyhow!("Expected bucket/key, found {location}"))
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Couldn't start the runtime to download the files")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_bucket_and_the_key() {
+        assert_eq!(
+            split_location("bucket/exports/2024/data.csv").unwrap(),
+            ("bucket", "exports/2024/data.csv")
+        );
+        assert!(split_location("bucket").is_err());
+        assert!(split_location("bucket/").is_err());
+        assert!(split_location("/data.csv").is_err());
+    }
+
+    #[test]
+    fn invalid_locations_fail_before_connecting() {
+        let args = Args::from_options("a.csv", "b.csv", &[]).unwrap();
+        assert!(download_s3("bucket", &args).is_err());
+        assert!(download_gcs("bucket/", &args).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use crate::report::{ColumnDifference, DifferenceSample, KeyCountDifference};
+use anyhow::{Context, Result};
+use polars::frame::DataFrame;
+use polars::prelude::{
+    col, concat, count, lit, ChunkCompare, DataType, JoinArgs, JoinType, LazyFrame, Series,
+    SortOptions, UnionArgs,
+};
+
+/// Counts, for every column, how many rows have a different value in the two (already sorted) data frames,
+/// classifying them as changed values or values that are null only in one of the files.
+/// Only the columns with at least one different row (or a different data type) are returned,
+/// with up to `max_samples` examples of different values
+pub fn count_differences(
+    first_data_frame: &DataFrame,
+    second_data_frame: &DataFrame,
+    key: &str,
+    columns: &[&String],
+    max_samples: usize,
+) -> Result<Vec<ColumnDifference>> {
+    let mut differences = vec![];
+    for column in columns {
+        let first_series = first_data_frame
+            .column(column)
+            .expect("Compared column must exist in first file");
+        let second_series = second_data_frame
+            .column(column)
+            .expect("Compared column must exist in second file");
+
+        if first_series.dtype() != second_series.dtype() {
+            differences.push(ColumnDifference {
+                column: column.to_string(),
+                differing_rows: first_series.len() as u64,
+                values_changed: 0,
+                null_on_one_side: 0,
+                whitespace_only: 0,
+                case_only: 0,
+                dtype_mismatch: true,
+                samples: vec![],
+            });
+            continue;
+        }
+
+        let differing_row_indexes: Vec<usize> = first_series
+            .not_equal_missing(second_series)
+            .with_context(|| format!("Couldn't compare values of column {column}"))?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, is_different)| is_different.unwrap_or(false))
+            .map(|(index, _)| index)
+            .collect();
+        let differing_rows = differing_row_indexes.len() as u64;
+
+        if differing_rows > 0 {
+            let null_on_one_side = first_series
+                .is_null()
+                .into_iter()
+                .zip(second_series.is_null().into_iter())
+                .filter(|(first_is_null, second_is_null)| first_is_null != second_is_null)
+                .count() as u64;
+            let (whitespace_only, case_only) =
+                count_cosmetic_differences(first_series, second_series, &differing_row_indexes);
+
+            differences.push(ColumnDifference {
+                column: column.to_string(),
+                differing_rows,
+                values_changed: differing_rows - null_on_one_side,
+                null_on_one_side,
+                whitespace_only,
+                case_only,
+                dtype_mismatch: false,
+                samples: get_samples(
+                    first_data_frame,
+                    key,
+                    first_series,
+                    second_series,
+                    &differing_row_indexes[..differing_row_indexes.len().min(max_samples)],
+                ),
+            });
+        }
+    }
+    Ok(differences)
+}
+
+/// Number of rows of the first frame whose key doesn't exist in the second frame
+pub fn count_missing_keys(
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+    key: &str,
+) -> Result<u64> {
+    let missing_keys = first_lazy_frame
+        .clone()
+        .select([col(key)])
+        .join(
+            second_lazy_frame.clone().select([col(key)]),
+            [col(key)],
+            [col(key)],
+            JoinArgs::new(JoinType::Anti),
+        )
+        .collect()
+        .with_context(|| format!("Couldn't look for missing keys in column {key}"))?;
+    Ok(missing_keys.height() as u64)
+}
+
+/// How many of the different values are the same once trimmed, and how many once trimmed and lowercased
+fn count_cosmetic_differences(
+    first_series: &Series,
+    second_series: &Series,
+    row_indexes: &[usize],
+) -> (u64, u64) {
+    let first_values = as_text(first_series);
+    let second_values = as_text(second_series);
+    let mut whitespace_only = 0;
+    let mut case_only = 0;
+    for &index in row_indexes {
+        let (Some(first_value), Some(second_value)) = (
+            get_text(&first_values, index),
+            get_text(&second_values, index),
+        ) else {
+            continue;
+        };
+        let (first_value, second_value) = (first_value.trim(), second_value.trim());
+        if first_value == second_value {
+            whitespace_only += 1;
+        } else if first_value.to_lowercase() == second_value.to_lowercase() {
+            case_only += 1;
+        }
+    }
+    (whitespace_only, case_only)
+}
+
+/// Keys with a different number of rows in each file (duplicated or dropped rows). Returns how many keys
+/// are different and up to `max_keys` of them, in order
+pub fn count_rows_per_key(
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+    key: &str,
+    max_keys: usize,
+) -> Result<(u64, Vec<KeyCountDifference>)> {
+    let counts = |lazy_frame: &LazyFrame, count_name: &str, other_count_name: &str| {
+        lazy_frame
+            .clone()
+            .group_by([col(key)])
+            .agg([count().cast(DataType::UInt64).alias(count_name)])
+            .with_column(lit(0u64).alias(other_count_name))
+            .select([col(key), col("first_count"), col("second_count")])
+    };
+    let different_counts = concat(
+        [
+            counts(first_lazy_frame, "first_count", "second_count"),
+            counts(second_lazy_frame, "second_count", "first_count"),
+        ],
+        UnionArgs::default(),
+    )?
+    .group_by([col(key)])
+    .agg([col("first_count").sum(), col("second_count").sum()])
+    .filter(col("first_count").neq(col("second_count")))
+    .sort(key, SortOptions::default())
+    .collect()
+    .with_context(|| format!("Couldn't count the rows of every key of column {key}"))?;
+
+    let keys = as_text(different_counts.column(key)?);
+    let first_counts = different_counts.column("first_count")?.u64()?;
+    let second_counts = different_counts.column("second_count")?.u64()?;
+    let differences = (0..different_counts.height().min(max_keys))
+        .map(|index| KeyCountDifference {
+            key: get_text(&keys, index),
+            first_count: first_counts.get(index).unwrap_or_default(),
+            second_count: second_counts.get(index).unwrap_or_default(),
+        })
+        .collect();
+    Ok((different_counts.height() as u64, differences))
+}
+
+/// Number of rows of the file and nulls of every column, in a single pass
+pub fn count_nulls(lazy_frame: &LazyFrame, columns: &[String]) -> Result<(u64, Vec<u64>)> {
+    let mut expressions = vec![count().cast(DataType::UInt64).alias("\u{0}rows")];
+    expressions.extend(
+        columns
+            .iter()
+            .map(|column| col(column).null_count().cast(DataType::UInt64)),
+    );
+    let counts = lazy_frame
+        .clone()
+        .select(expressions)
+        .collect()
+        .context("Couldn't count the nulls of the columns")?;
+    let count_at = |index: usize| -> Result<u64> {
+        Ok(counts.get_columns()[index]
+            .u64()?
+            .get(0)
+            .unwrap_or_default())
+    };
+    let rows = count_at(0)?;
+    let nulls = (1..=columns.len()).map(count_at).collect::<Result<_>>()?;
+    Ok((rows, nulls))
+}
+
+/// Keys that appear in more than one row, the most repeated first (and in alphabetical order when they have
+/// the same rows). Returns how many keys are duplicated and up to `max_keys` of them with their number of rows
+pub fn find_duplicated_keys(
+    lazy_frame: &LazyFrame,
+    key: &str,
+    max_keys: usize,
+) -> Result<(u64, Vec<(Option<String>, u64)>)> {
+    let duplicated_keys = lazy_frame
+        .clone()
+        .group_by([col(key)])
+        .agg([count().cast(DataType::UInt64).alias("rows")])
+        .filter(col("rows").gt(lit(1u64)))
+        .sort_by_exprs(
+            [col("rows"), col(key).cast(DataType::Utf8)],
+            [true, false],
+            false,
+            false,
+        )
+        .collect()
+        .with_context(|| format!("Couldn't look for duplicated keys in column {key}"))?;
+    let keys = as_text(duplicated_keys.column(key)?);
+    let rows = duplicated_keys.column("rows")?.u64()?;
+    let top_keys = (0..duplicated_keys.height().min(max_keys))
+        .map(|index| (get_text(&keys, index), rows.get(index).unwrap_or_default()))
+        .collect();
+    Ok((duplicated_keys.height() as u64, top_keys))
+}
+
+fn get_samples(
+    first_data_frame: &DataFrame,
+    key: &str,
+    first_series: &Series,
+    second_series: &Series,
+    row_indexes: &[usize],
+) -> Vec<DifferenceSample> {
+    if row_indexes.is_empty() {
+        return vec![];
+    }
+    let keys = as_text(first_data_frame.column(key).expect("Key column must exist"));
+    let first_values = as_text(first_series);
+    let second_values = as_text(second_series);
+
+    row_indexes
+        .iter()
+        .map(|&index| DifferenceSample {
+            key: get_text(&keys, index),
+            first_value: get_text(&first_values, index),
+            second_value: get_text(&second_values, index),
+        })
+        .collect()
+}
+
+pub(crate) fn as_text(series: &Series) -> Series {
+    series
+        .cast(&DataType::Utf8)
+        .unwrap_or_else(|error| panic!("Couldn't cast column {} to text: {error}", series.name()))
+}
+
+pub(crate) fn get_text(series: &Series, index: usize) -> Option<String> {
+    series
+        .utf8()
+        .expect("Series must be casted to text")
+        .get(index)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    fn frames() -> (DataFrame, DataFrame) {
+        (
+            df!("id" => ["1", "2", "3", "4"], "name" => [Some("Ann"), Some("Bob"), Some("carl"), None]).unwrap(),
+            df!("id" => ["1", "2", "3", "4"], "name" => [Some("Ann"), Some(" Bob"), Some("Carl"), Some("Dan")]).unwrap(),
+        )
+    }
+
+    #[test]
+    fn classifies_the_different_values() {
+        let (first, second) = frames();
+        let name = "name".to_string();
+        let differences = count_differences(&first, &second, "id", &[&name], 2).unwrap();
+        assert_eq!(differences.len(), 1);
+        let difference = &differences[0];
+        assert_eq!(difference.differing_rows, 3);
+        assert_eq!(difference.values_changed, 2);
+        assert_eq!(difference.null_on_one_side, 1);
+        assert_eq!((difference.whitespace_only, difference.case_only), (1, 1));
+        assert_eq!(difference.samples.len(), 2);
+        assert_eq!(difference.samples[0].key.as_deref(), Some("2"));
+        assert_eq!(difference.samples[0].second_value.as_deref(), Some(" Bob"));
+    }
+
+    #[test]
+    fn columns_without_differences_are_not_returned() {
+        let (first, second) = frames();
+        let id = "id".to_string();
+        assert!(count_differences(&first, &second, "id", &[&id], 5)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn counts_missing_keys_and_rows_per_key() {
+        let first = df!("id" => ["1", "2", "2", "3"]).unwrap().lazy();
+        let second = df!("id" => ["1", "2", "4"]).unwrap().lazy();
+        assert_eq!(count_missing_keys(&first, &second, "id").unwrap(), 1);
+        let (keys, differences) = count_rows_per_key(&first, &second, "id", 2).unwrap();
+        assert_eq!(keys, 3);
+        assert_eq!(differences[0].key.as_deref(), Some("2"));
+        assert_eq!(
+            (differences[0].first_count, differences[0].second_count),
+            (2, 1)
+        );
+        assert_eq!(differences[1].key.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn finds_the_most_duplicated_keys_first() {
+        let lazy_frame = df!("id" => ["b", "a", "b", "c", "a", "b"]).unwrap().lazy();
+        let (keys, duplicated) = find_duplicated_keys(&lazy_frame, "id", 5).unwrap();
+        assert_eq!(keys, 2);
+        assert_eq!(
+            duplicated,
+            [(Some("b".to_string()), 3), (Some("a".to_string()), 2)]
+        );
+    }
+
+    #[test]
+    fn counts_rows_and_nulls() {
+        let (first, _) = frames();
+        let columns = ["id".to_string(), "name".to_string()];
+        assert_eq!(
+            count_nulls(&first.lazy(), &columns).unwrap(),
+            (4, vec![0, 1])
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use crate::cli::Args;
+use clap::builder::BoolishValueParser;
+use clap::{ArgAction, Command, CommandFactory};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// Name of the table with the options applied to every run
+const DEFAULT_SECTION: &str = "default";
+
+/// Location of the configuration file: --config, or ~/.config/csv-compare/config.toml
+fn config_path(cli_args: &[OsString]) -> Option<PathBuf> {
+    if let Some(path) = find_option_value(cli_args, "--config") {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("csv-compare").join("config.toml"))
+}
+
+/// Environment variable that can be used instead of an option, e.g. CSV_COMPARE_NUMBER_OF_COLUMNS
+fn env_var_name(option: &str) -> String {
+    format!(
+        "CSV_COMPARE_{}",
+        option
+            .trim_start_matches("--")
+            .replace('-', "_")
+            .to_uppercase()
+    )
+}
+
+/// Value of an option given as `--name value` or `--name=value` in the command line,
+/// or in its environment variable
+fn find_option_value(cli_args: &[OsString], name: &str) -> Option<String> {
+    find_cli_option_value(cli_args, name).or_else(|| std::env::var(env_var_name(name)).ok())
+}
+
+fn find_cli_option_value(cli_args: &[OsString], name: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    let mut args = cli_args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next().map(|value| value.to_string());
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Parser of the command line with the options of the config file (the [default] section and the selected
+/// --profile) as the defaults of the options. Precedence is: command line, then CSV_COMPARE_* environment
+/// variables, then config file. The flags take an optional value (--typed=false), so the ones set in the config
+/// file can be turned off
+pub fn command_with_config(cli_args: &[OsString]) -> Result<Command, String> {
+    let profile = find_option_value(cli_args, "--profile");
+    let explicit_config = find_option_value(cli_args, "--config").is_some();
+    let command = switchable_flags(Args::command());
+
+    let Some(path) = config_path(cli_args) else {
+        return Ok(command);
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) if !explicit_config && profile.is_none() => return Ok(command),
+        Err(error) => {
+            return Err(format!(
+                "Couldn't read config file {}: {error}",
+                path.display()
+            ))
+        }
+    };
+    let config: Table = content
+        .parse()
+        .map_err(|error| format!("Invalid config file {}: {error}", path.display()))?;
+    with_config_defaults(command, &config, profile.as_deref())
+        .map_err(|error| format!("{error} in config file {}", path.display()))
+}
+
+/// Sets the options of the [default] section and of the profile (which take precedence) as defaults
+fn with_config_defaults(
+    mut command: Command,
+    config: &Table,
+    profile: Option<&str>,
+) -> Result<Command, String> {
+    let mut options = vec![];
+    if let Some(Value::Table(defaults)) = config.get(DEFAULT_SECTION) {
+        options.extend(table_to_options(defaults)?);
+    }
+    if let Some(profile) = profile {
+        let profile_table = config
+            .get("profiles")
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(Value::as_table)
+            .ok_or_else(|| format!("Profile {profile} not found"))?;
+        options.extend(table_to_options(profile_table)?);
+    }
+    for (name, values) in options {
+        let id = name.replace('-', "_");
+        if !command
+            .get_arguments()
+            .any(|arg| arg.get_id() == id.as_str())
+        {
+            return Err(format!("Unknown option {name}"));
+        }
+        command = command.mut_arg(id, |arg| arg.default_values(values));
+    }
+    Ok(command)
+}
+
+/// Flags that take an optional value after an equals sign (--typed, --typed=true or --typed=false)
+fn switchable_flags(command: Command) -> Command {
+    command.mut_args(|arg| {
+        if !matches!(arg.get_action(), ArgAction::SetTrue) {
+            return arg;
+        }
+        let arg = if arg.get_default_values().is_empty() {
+            arg.default_value("false")
+        } else {
+            arg
+        };
+        arg.action(ArgAction::Set)
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("true")
+            .value_parser(BoolishValueParser::new())
+    })
+}
+
+/// Values of the options of a table: `separator = ";"` as `;`, `strict-column-order = true` as `true`, a list as
+/// its values...
+fn table_to_options(table: &Table) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut options = vec![];
+    for (name, value) in table {
+        let values = match value {
+            Value::Boolean(flag) => vec![flag.to_string()],
+            Value::String(text) => vec![text.to_string()],
+            Value::Integer(number) => vec![number.to_string()],
+            Value::Float(number) => vec![number.to_string()],
+            Value::Array(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(text) => text.to_string(),
+                    other => other.to_string(),
+                })
+                .collect(),
+            _ => return Err(format!("Unsupported value for option {name}")),
+        };
+        options.push((name.to_string(), values));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::FromArgMatches;
+
+    const CONFIG: &str = r#"
+[default]
+typed = true
+separator = ";"
+redact = ["iban", "name"]
+
+[profiles.strict]
+strict-column-order = true
+separator = "|"
+"#;
+
+    fn parse(profile: Option<&str>, cli_args: &[&str]) -> Args {
+        let config: Table = CONFIG.parse().unwrap();
+        let command =
+            with_config_defaults(switchable_flags(Args::command()), &config, profile).unwrap();
+        let mut command_line = vec!["csv-compare"];
+        command_line.extend(cli_args);
+        command_line.extend(["a.csv", "b.csv"]);
+        let matches = command.try_get_matches_from(command_line).unwrap();
+        Args::from_arg_matches(&matches).unwrap()
+    }
+
+    #[test]
+    fn uses_the_config_as_defaults() {
+        let args = parse(None, &[]);
+        assert!(args.typed);
+        assert_eq!(args.separator, ';');
+        assert_eq!(args.redact, ["iban", "name"]);
+        assert!(!args.strict_column_order);
+    }
+
+    #[test]
+    fn the_profile_takes_precedence() {
+        let args = parse(Some("strict"), &[]);
+        assert!(args.strict_column_order && args.typed);
+        assert_eq!(args.separator, '|');
+    }
+
+    #[test]
+    fn the_command_line_takes_precedence() {
+        let args = parse(
+            Some("strict"),
+            &["--typed=false", "-s=false", "--separator", ","],
+        );
+        assert!(!args.typed && !args.strict_column_order);
+        assert_eq!(args.separator, ',');
+        let args = parse(None, &["--report-all", "--typed"]);
+        assert!(args.report_all && args.typed);
+        assert!(!parse(None, &[]).report_all);
+    }
+
+    #[test]
+    fn flags_are_not_followed_by_their_value() {
+        let args = parse(None, &["--report-all", "--separator=,"]);
+        assert!(args.report_all);
+        assert_eq!(args.file1.as_deref(), Some("a.csv"));
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let config: Table = "[default]\nno-such-option = 1".parse().unwrap();
+        assert!(with_config_defaults(Args::command(), &config, None).is_err());
+        let config: Table = CONFIG.parse().unwrap();
+        assert!(with_config_defaults(Args::command(), &config, Some("missing")).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::inputs::LocalFile;
+use crate::Args;
+use anyhow::{bail, Context, Result};
+#[cfg(target_os = "linux")]
+use std::fs::File;
+#[cfg(all(target_os = "linux", feature = "encryption"))]
+use std::io::BufReader;
+#[cfg(target_os = "linux")]
+use std::process::{Command, Stdio};
+
+/// How a file is encrypted, given by the extension of its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encryption {
+    Age,
+    Gpg,
+}
+
+impl Encryption {
+    pub(crate) fn of(file_path: &str) -> Option<Self> {
+        let file_path = file_path.to_ascii_lowercase();
+        if file_path.ends_with(".age") {
+            Some(Encryption::Age)
+        } else if [".gpg", ".pgp", ".asc"]
+            .iter()
+            .any(|extension| file_path.ends_with(extension))
+        {
+            Some(Encryption::Gpg)
+        } else {
+            None
+        }
+    }
+}
+
+/// Decrypts the file into an in-memory file, so the plaintext is never written to disk
+#[cfg(target_os = "linux")]
+pub(crate) fn decrypt(file_path: &str, encryption: Encryption, args: &Args) -> Result<LocalFile> {
+    let mut plaintext = memfd::MemfdOptions::default()
+        .create("csv-compare-plaintext")
+        .context("Couldn't create an in-memory file")?
+        .into_file();
+    match encryption {
+        Encryption::Age => decrypt_age(file_path, args, &mut plaintext),
+        Encryption::Gpg => decrypt_gpg(file_path, args, &plaintext),
+    }
+    .with_context(|| format!("Couldn't decrypt {file_path}"))?;
+    Ok(LocalFile::in_memory(plaintext))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn decrypt(file_path: &str, _encryption: Encryption, _args: &Args) -> Result<LocalFile> {
+    bail!("Couldn't decrypt {file_path}: encrypted files can only be decrypted in memory in Linux")
+}
+
+#[cfg(all(target_os = "linux", feature = "encryption"))]
+fn decrypt_age(file_path: &str, args: &Args, plaintext: &mut File) -> Result<()> {
+    let Some(identity_path) = &args.age_identity else {
+        bail!("--age-identity is needed to decrypt age files");
+    };
+    let identities = age::IdentityFile::from_file(identity_path.clone())
+        .with_context(|| format!("Couldn't read identity file {identity_path}"))?
+        .into_identities()?;
+    let decryptor = match age::Decryptor::new(BufReader::new(File::open(file_path)?))? {
+        age::Decryptor::Recipients(decryptor) => decryptor,
+        _ => bail!("Files encrypted with a passphrase aren't supported"),
+    };
+    let mut reader = decryptor.decrypt(
+        identities
+            .iter()
+            .map(|identity| identity.as_ref() as &dyn age::Identity),
+    )?;
+    std::io::copy(&mut reader, plaintext)?;
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", not(feature = "encryption")))]
+fn decrypt_age(file_path: &str, _args: &Args, _plaintext: &mut File) -> Result<()> {
+    bail!("Can't read {file_path}: age support is not included in this build (feature encryption)")
+}
+
+/// Decrypts with the gpg command. With --gpg-key, the key is imported into a temporary keyring
+/// instead of the keyring of the user
+#[cfg(target_os = "linux")]
+fn decrypt_gpg(file_path: &str, args: &Args, plaintext: &File) -> Result<()> {
+    let keyring = match &args.gpg_key {
+        Some(key_path) => {
+            let keyring = tempfile::tempdir()?;
+            run_gpg(
+                Command::new("gpg")
+                    .arg("--homedir")
+                    .arg(keyring.path())
+                    .args(["--batch", "--quiet", "--import", key_path])
+                    .stdout(Stdio::null()),
+            )
+            .with_context(|| format!("Couldn't import key {key_path}"))?;
+            Some(keyring)
+        }
+        None => None,
+    };
+    let mut command = Command::new("gpg");
+    if let Some(keyring) = &keyring {
+        command.arg("--homedir").arg(keyring.path());
+    }
+    run_gpg(
+        command
+            .args(["--batch", "--quiet", "--decrypt", file_path])
+            .stdout(plaintext.try_clone()?),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn run_gpg(command: &mut Command) -> Result<()> {
+    let output = command
+        .stderr(Stdio::piped())
+        .output()
+        .context("Couldn't run gpg")?;
+    if !output.status.success() {
+        bail!(
+            "gpg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_encryption_by_the_extension() {
+        assert_eq!(Encryption::of("data.csv.age"), Some(Encryption::Age));
+        for file_path in ["data.csv.gpg", "data.csv.PGP", "data.csv.asc"] {
+            assert_eq!(Encryption::of(file_path), Some(Encryption::Gpg));
+        }
+        assert_eq!(Encryption::of("data.csv"), None);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "encryption"))]
+    #[test]
+    fn decrypts_age_files_in_memory() {
+        use age::secrecy::ExposeSecret;
+        use std::io::Write;
+
+        let identity = age::x25519::Identity::generate();
+        let mut identity_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(identity_file, "{}", identity.to_string().expose_secret()).unwrap();
+        let encrypted = tempfile::Builder::new().suffix(".age").tempfile().unwrap();
+        let encryptor =
+            age::Encryptor::with_recipients(vec![Box::new(identity.to_public())]).unwrap();
+        let mut writer = encryptor.wrap_output(encrypted.reopen().unwrap()).unwrap();
+        writer.write_all(b"id,name\n1,Ann\n").unwrap();
+        writer.finish().unwrap();
+
+        let encrypted_path = encrypted.path().to_str().unwrap();
+        let identity_path = identity_file.path().to_str().unwrap().to_string();
+        let args = Args::from_options(
+            encrypted_path,
+            encrypted_path,
+            &["--age-identity".to_string(), identity_path],
+        )
+        .unwrap();
+        let plaintext = decrypt(encrypted_path, Encryption::Age, &args).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(plaintext.path()).unwrap(),
+            "id,name\n1,Ann\n"
+        );
+
+        let args = Args::from_options(encrypted_path, encrypted_path, &[]).unwrap();
+        assert!(decrypt(encrypted_path, Encryption::Age, &args).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+use crate::compare::{as_text, get_text};
+use crate::errors::SourceFile;
+use crate::inputs::{self, LocalFile};
+use crate::output::Printer;
+use crate::partition::{self, KeyRange};
+use crate::ragged::PreparedFile;
+use crate::report::{ComparisonReport, ComparisonResult};
+use crate::{
+    check_columns, get_column_names, get_rows_num, get_sorted_data_frame_for_columns,
+    prepare_frames, read_csv_frames, Args, CsvFrames,
+};
+use anyhow::{bail, Context, Result};
+use polars::prelude::{ChunkCompare, LazyFrame, Series, SortOptions};
+use std::vec::IntoIter;
+
+/// A cell whose value is different in the two files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// Value of the sorting column (first column of the first file) in the row
+    pub key: Option<String>,
+    pub column: String,
+    pub first_value: Option<String>,
+    pub second_value: Option<String>,
+}
+
+/// Rows of each batch read at most, so the files are compared in about this many rows at a time (split by ranges
+/// of the key, as with --key-partitions)
+const BATCH_ROWS: usize = 1_000_000;
+
+/// Two files ready to be compared cell by cell
+pub struct Comparison {
+    first_file_path: String,
+    second_file_path: String,
+    // The fetched files are kept while the prepared files are read
+    _first_input: LocalFile,
+    _second_input: LocalFile,
+    first_file: PreparedFile,
+    second_file: PreparedFile,
+    separator: char,
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+    key: String,
+    sort_options: SortOptions,
+    columns: Vec<String>,
+    key_ranges: Vec<KeyRange>,
+}
+
+impl Comparison {
+    /// Opens both CSV files as `compare_files` does: ragged rows and duplicated headers handled, columns matched,
+    /// rows excluded and values normalized as configured in the arguments. They must have the same columns (with
+    /// the same types in typed mode) and the same number of rows
+    pub fn open(args: &Args, first_file_path: &str, second_file_path: &str) -> Result<Self> {
+        let first_input = inputs::fetch(first_file_path, args)?;
+        let second_input = inputs::fetch(second_file_path, args)?;
+        // Only the result of the checks is needed, not their messages
+        let printer = Printer::new(true, false, false);
+        let mut report = ComparisonReport::default();
+        let Some(CsvFrames {
+            first_file,
+            second_file,
+            first_lazy_frame,
+            second_lazy_frame,
+        }) = read_csv_frames(
+            &mut report,
+            args,
+            &printer,
+            (first_file_path, &first_input),
+            (second_file_path, &second_input),
+        )?
+        else {
+            bail!(
+                "Files can't be compared cell by cell: {}",
+                report.result.name()
+            );
+        };
+        let first_source = SourceFile::new(first_file_path, first_file.path(), args.separator);
+        let second_source = SourceFile::new(second_file_path, second_file.path(), args.separator);
+        let prepared_frames = prepare_frames(
+            &mut report,
+            args,
+            &printer,
+            (first_lazy_frame, &first_source),
+            (second_lazy_frame, &second_source, second_file_path),
+        )?;
+        let (first_lazy_frame, second_lazy_frame) = (
+            prepared_frames.first_lazy_frame,
+            prepared_frames.second_lazy_frame,
+        );
+
+        let Some(first_file_cols) = check_columns(
+            &mut report,
+            args,
+            &printer,
+            &first_lazy_frame,
+            &first_source,
+            &second_lazy_frame,
+            &second_source,
+        )?
+        else {
+            if report.result == ComparisonResult::DifferentTypes {
+                bail!(
+                    "Columns have a different type in each file: {}",
+                    report
+                        .column_type_differences
+                        .iter()
+                        .map(|difference| format!(
+                            "{} ({} <> {})",
+                            difference.column, difference.first_type, difference.second_type
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            let second_file_cols =
+                get_column_names(&second_lazy_frame).with_context(|| second_source.context())?;
+            bail!(
+                "Files have different columns: [{}] <> [{}]",
+                get_column_names(&first_lazy_frame)
+                    .with_context(|| first_source.context())?
+                    .join(","),
+                second_file_cols.join(",")
+            );
+        };
+        let first_rows = get_rows_num(&first_lazy_frame).with_context(|| first_source.context())?;
+        let second_rows =
+            get_rows_num(&second_lazy_frame).with_context(|| second_source.context())?;
+        if first_rows != second_rows {
+            bail!(
+                "Files have a different number of rows: {} <> {}",
+                first_rows,
+                second_rows
+            );
+        }
+
+        let key = prepared_frames.key;
+        let partitions = args
+            .key_partitions
+            .max((first_rows as usize).div_ceil(BATCH_ROWS));
+        let key_ranges = partition::key_ranges(&first_lazy_frame, &key, partitions)
+            .with_context(|| first_source.context())?;
+        let columns = first_file_cols.into_iter().skip(1).collect();
+        Ok(Comparison {
+            first_file_path: first_file_path.to_string(),
+            second_file_path: second_file_path.to_string(),
+            _first_input: first_input,
+            _second_input: second_input,
+            first_file,
+            second_file,
+            separator: args.separator,
+            first_lazy_frame,
+            second_lazy_frame,
+            key,
+            sort_options: args.sort_options(),
+            columns,
+            key_ranges,
+        })
+    }
+
+    /// Different cells, column by column and, within a column, range by range of the key. Only a batch of rows
+    /// of a column is loaded at a time and nothing else is read once the consumer stops iterating, so the first
+    /// differences of huge files can be streamed without comparing everything. A batch that can't be read once
+    /// the files have been opened (e.g. a malformed row far from the header) yields an error, and the next ones
+    /// are still compared
+    pub fn differences(&self) -> impl Iterator<Item = Result<Difference>> + '_ {
+        self.columns.iter().flat_map(move |column| {
+            self.key_ranges.iter().flat_map(move |key_range| {
+                let (differences, error) = match self.batch_differences(column, key_range) {
+                    Ok(differences) => (Some(differences), None),
+                    Err(error) => (None, Some(Err(error))),
+                };
+                error
+                    .into_iter()
+                    .chain(differences.into_iter().flatten().map(Ok))
+            })
+        })
+    }
+
+    fn batch_differences(
+        &self,
+        column: &String,
+        key_range: &KeyRange,
+    ) -> Result<ColumnDifferences> {
+        let first_source = SourceFile::new(
+            &self.first_file_path,
+            self.first_file.path(),
+            self.separator,
+        );
+        let second_source = SourceFile::new(
+            &self.second_file_path,
+            self.second_file.path(),
+            self.separator,
+        );
+        let first_data_frame = get_sorted_data_frame_for_columns(
+            &key_range.filter(&self.first_lazy_frame, &self.key),
+            &self.key,
+            &vec![column],
+            self.sort_options,
+        )
+        .with_context(|| first_source.context())?;
+        let second_data_frame = get_sorted_data_frame_for_columns(
+            &key_range.filter(&self.second_lazy_frame, &self.key),
+            &self.key,
+            &vec![column],
+            self.sort_options,
+        )
+        .with_context(|| second_source.context())?;
+        let first_series = first_data_frame
+            .column(column)
+            .with_context(|| first_source.context())?;
+        let second_series = second_data_frame
+            .column(column)
+            .with_context(|| second_source.context())?;
+
+        // A range can have more rows in a file than in the other when their keys are different: the rows left
+        // are different in both sides
+        let aligned_rows = first_series.len().min(second_series.len());
+        let mut row_indexes: Vec<usize> = first_series
+            .slice(0, aligned_rows)
+            .not_equal_missing(&second_series.slice(0, aligned_rows))
+            .with_context(|| format!("Couldn't compare column {column}"))?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, is_different)| is_different.unwrap_or(false))
+            .map(|(index, _)| index)
+            .collect();
+        row_indexes.extend(aligned_rows..first_series.len().max(second_series.len()));
+
+        let keys = if first_series.len() >= second_series.len() {
+            first_data_frame.column(&self.key)
+        } else {
+            second_data_frame.column(&self.key)
+        };
+        Ok(ColumnDifferences {
+            column: column.to_string(),
+            keys: as_text(keys.context("Couldn't read the key")?),
+            first_values: as_text(first_series),
+            second_values: as_text(second_series),
+            row_indexes: row_indexes.into_iter(),
+        })
+    }
+}
+
+/// Iterator over the different cells of a single column
+struct ColumnDifferences {
+    column: String,
+    keys: Series,
+    first_values: Series,
+    second_values: Series,
+    row_indexes: IntoIter<usize>,
+}
+
+impl Iterator for ColumnDifferences {
+    type Item = Difference;
+
+    fn next(&mut self) -> Option<Difference> {
+        let index = self.row_indexes.next()?;
+        let text = |series: &Series| (index < series.len()).then(|| get_text(series, index))?;
+        Some(Difference {
+            key: text(&self.keys),
+            column: self.column.to_string(),
+            first_value: text(&self.first_values),
+            second_value: text(&self.second_values),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare_files;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn args(options: &[&str]) -> Args {
+        let mut options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        options.extend(["--report-all", "--samples", "100"].map(String::from));
+        Args::from_options("first.csv", "second.csv", &options).unwrap()
+    }
+
+    /// Differences of the comparison, as (column, key, first value, second value) in order
+    fn differences(
+        args: &Args,
+        first: &str,
+        second: &str,
+    ) -> Vec<(String, String, String, String)> {
+        let (first, second) = (file(first), file(second));
+        let comparison = Comparison::open(
+            args,
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+        )
+        .unwrap();
+        comparison
+            .differences()
+            .map(|difference| {
+                let difference = difference.unwrap();
+                (
+                    difference.column,
+                    difference.key.unwrap_or_default(),
+                    difference.first_value.unwrap_or_default(),
+                    difference.second_value.unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    /// Samples of the report of `compare_files`, in the same shape
+    fn samples(args: &Args, first: &str, second: &str) -> Vec<(String, String, String, String)> {
+        let (first, second) = (file(first), file(second));
+        let report = compare_files(
+            args,
+            &Printer::new(true, false, false),
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+        );
+        report
+            .column_differences
+            .iter()
+            .flat_map(|difference| {
+                difference.samples.iter().map(|sample| {
+                    (
+                        difference.column.to_string(),
+                        sample.key.clone().unwrap_or_default(),
+                        sample.first_value.clone().unwrap_or_default(),
+                        sample.second_value.clone().unwrap_or_default(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    const FIRST: &str = "id,name,city\n3,Carol,Lima\n1,Alice,Oslo\n2,Bob,Rome\n4,Dan,Kyiv\n";
+    const SECOND: &str = "id,name,city\n1,Alice,Bern\n2,Bobby,Rome\n3,Carol,Lima\n4,Dan,Riga\n";
+
+    #[test]
+    fn finds_the_same_differences_as_compare_files() {
+        let args = args(&[]);
+        let differences = differences(&args, FIRST, SECOND);
+        assert_eq!(differences, samples(&args, FIRST, SECOND));
+        assert_eq!(differences.len(), 3);
+    }
+
+    #[test]
+    fn prepares_the_files_as_compare_files() {
+        let second = "ID,Name,City\n1,Alice,Bern\n2,Bobby,Rome\n3,Carol,Lima\n4,Dan,Riga\n";
+        let args = args(&["--ignore-column-case", "--exclude-rows", "id=~^1$"]);
+        let differences = differences(&args, FIRST, second);
+        assert_eq!(differences, samples(&args, FIRST, second));
+        assert_eq!(
+            differences,
+            vec![
+                ("name".into(), "2".into(), "Bob".into(), "Bobby".into()),
+                ("city".into(), "4".into(), "Kyiv".into(), "Riga".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sorts_by_the_column_of_compare_files() {
+        let args = args(&["--sort-by", "name"]);
+        assert_eq!(
+            differences(&args, FIRST, SECOND),
+            samples(&args, FIRST, SECOND)
+        );
+    }
+
+    #[test]
+    fn finds_the_same_differences_in_batches_of_keys() {
+        let all_at_once = differences(&args(&[]), FIRST, SECOND);
+        assert_eq!(
+            differences(&args(&["--key-partitions", "3"]), FIRST, SECOND),
+            all_at_once
+        );
+    }
+
+    #[test]
+    fn rejects_files_with_different_columns() {
+        let (first, second) = (file(FIRST), file("id,name\n1,Alice\n"));
+        let error = Comparison::open(
+            &args(&[]),
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("different columns"));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::output::{OutputMode, Printer};
+use crate::report::{ComparisonReport, ComparisonResult};
+use crate::{
+    compare_files, compare_files_observed, table, Args, CancellationToken, ComparisonObserver,
+};
+use anyhow::{bail, Context, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Rule to pair files whose names are systematically different in each directory, written as
+/// `{name}_v1.csv={name}_final.csv`. The placeholders match any text and must appear on both sides
+#[derive(Debug, Clone)]
+pub struct PairRule {
+    text: String,
+    first: Regex,
+    second: String,
+}
+
+impl FromStr for PairRule {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (first, second) = text
+            .split_once('=')
+            .ok_or_else(|| format!("Expected first_pattern=second_pattern, found {text}"))?;
+        let placeholder = Regex::new(r"\{(\w+)\}").expect("Placeholder regex is valid");
+        let mut pattern = String::from("^");
+        let mut last_end = 0;
+        let mut names = vec![];
+        for captures in placeholder.captures_iter(first) {
+            let whole = captures.get(0).expect("Group 0 is always present");
+            pattern.push_str(&regex::escape(&first[last_end..whole.start()]));
+            pattern.push_str(&format!("(?P<{}>.+?)", &captures[1]));
+            names.push(captures[1].to_string());
+            last_end = whole.end();
+        }
+        pattern.push_str(&regex::escape(&first[last_end..]));
+        pattern.push('$');
+        if let Some(name) = placeholder
+            .captures_iter(second)
+            .map(|captures| captures[1].to_string())
+            .find(|name| !names.contains(name))
+        {
+            return Err(format!("{{{name}}} is not in the first pattern of {text}"));
+        }
+        Ok(PairRule {
+            text: text.to_string(),
+            first: Regex::new(&pattern).map_err(|error| format!("Invalid rule {text}: {error}"))?,
+            second: second.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for PairRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl PairRule {
+    /// Name of the file of the second directory paired with the given file of the first one, if the rule applies
+    fn second_name(&self, first_name: &str) -> Option<String> {
+        let captures = self.first.captures(first_name)?;
+        let mut second_name = self.second.clone();
+        for name in self.first.capture_names().flatten() {
+            second_name = second_name.replace(&format!("{{{name}}}"), &captures[name]);
+        }
+        Some(second_name)
+    }
+}
+
+/// Files of the two directories compared with each other, by their path relative to the directory.
+/// Files without a pair have only one of them
+#[derive(Debug, Clone)]
+pub struct FilePair {
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+/// Pairs the files of both directories (and their subdirectories): first with the rules, in the given order,
+/// then the files with the same name
+pub fn pair_files(first_dir: &str, second_dir: &str, rules: &[PairRule]) -> Result<Vec<FilePair>> {
+    let first_files = list_files(first_dir)?;
+    let mut second_files = list_files(second_dir)?;
+    let mut pairs = vec![];
+    for first_name in first_files {
+        let second_name = rules
+            .iter()
+            .filter_map(|rule| rule.second_name(&first_name))
+            .chain(std::iter::once(first_name.clone()))
+            .find(|second_name| second_files.contains(second_name));
+        if let Some(second_name) = &second_name {
+            second_files.remove(second_name);
+        }
+        pairs.push(FilePair {
+            first: Some(first_name),
+            second: second_name,
+        });
+    }
+    pairs.extend(second_files.into_iter().map(|second_name| FilePair {
+        first: None,
+        second: Some(second_name),
+    }));
+    Ok(pairs)
+}
+
+fn list_files(dir: &str) -> Result<BTreeSet<String>> {
+    if !Path::new(dir).is_dir() {
+        bail!("{dir} is not a directory");
+    }
+    let mut files = BTreeSet::new();
+    let mut pending = vec![String::new()];
+    while let Some(relative_dir) = pending.pop() {
+        let path = Path::new(dir).join(&relative_dir);
+        let entries =
+            fs::read_dir(&path).with_context(|| format!("Couldn't read {}", path.display()))?;
+        for entry in entries {
+            let entry = entry.with_context(|| format!("Couldn't read {}", path.display()))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let relative_path = if relative_dir.is_empty() {
+                name
+            } else {
+                format!("{relative_dir}/{name}")
+            };
+            if entry.file_type()?.is_dir() {
+                pending.push(relative_path);
+            } else {
+                files.insert(relative_path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Outcome of a pair of files (no report when one of them is missing)
+#[derive(Debug)]
+pub struct PairResult {
+    pub pair: FilePair,
+    pub report: Option<ComparisonReport>,
+}
+
+/// Compares every pair of files. With more than one job, the pairs are compared at the same time
+/// without printing their output, showing only how many pairs are done
+pub fn compare_pairs(
+    args: &Args,
+    printer: &Printer,
+    first_dir: &str,
+    second_dir: &str,
+    pairs: Vec<FilePair>,
+    jobs: usize,
+) -> Vec<PairResult> {
+    let pair_paths = |pair: &FilePair| match (&pair.first, &pair.second) {
+        (Some(first_name), Some(second_name)) => Some((
+            Path::new(first_dir).join(first_name).display().to_string(),
+            Path::new(second_dir)
+                .join(second_name)
+                .display()
+                .to_string(),
+        )),
+        _ => None,
+    };
+    if jobs <= 1 {
+        return pairs
+            .into_iter()
+            .map(|pair| PairResult {
+                report: pair_paths(&pair).map(|(first_path, second_path)| {
+                    compare_files(args, printer, &first_path, &second_path)
+                }),
+                pair,
+            })
+            .collect();
+    }
+
+    // One bar with the pairs compared plus a line per job with the pair (and columns) it's comparing
+    let multi_progress = MultiProgress::with_draw_target(
+        if printer.mode() == OutputMode::Normal
+            && !args.no_progress
+            && std::io::stderr().is_terminal()
+        {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        },
+    );
+    let progress_bar = multi_progress.add(ProgressBar::new(pairs.len() as u64));
+    progress_bar.set_style(
+        ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} pairs")
+            .expect("Error creating progress bar. Incorrect Style?. Please raise issue to developers of this tool"));
+    let next_pair = AtomicUsize::new(0);
+    let reports: Mutex<Vec<Option<ComparisonReport>>> =
+        Mutex::new(pairs.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for job in 1..=jobs.min(pairs.len()) {
+            let job_progress_bar = multi_progress.add(ProgressBar::new_spinner());
+            job_progress_bar.set_style(
+                ProgressStyle::with_template("{spinner} [job {prefix}] {elapsed:>4} {wide_msg}")
+                    .expect("Error creating progress bar. Incorrect Style?. Please raise issue to developers of this tool"));
+            job_progress_bar.set_prefix(job.to_string());
+            job_progress_bar.enable_steady_tick(Duration::from_millis(200));
+            let (next_pair, reports, pairs, progress_bar) =
+                (&next_pair, &reports, &pairs, &progress_bar);
+            scope.spawn(move || {
+                let quiet_printer = Printer::new(true, false, false);
+                loop {
+                    let index = next_pair.fetch_add(1, Ordering::Relaxed);
+                    let Some(pair) = pairs.get(index) else {
+                        break;
+                    };
+                    job_progress_bar.reset_elapsed();
+                    let report = pair_paths(pair).map(|(first_path, second_path)| {
+                        let pair_name = pair.first.as_deref().unwrap_or_default();
+                        job_progress_bar.set_message(pair_name.to_string());
+                        compare_files_observed(
+                            args,
+                            &quiet_printer,
+                            &first_path,
+                            &second_path,
+                            &CancellationToken::new(),
+                            &JobProgress {
+                                progress_bar: &job_progress_bar,
+                                pair_name,
+                            },
+                        )
+                    });
+                    reports.lock().expect("No comparison panicked")[index] = report;
+                    progress_bar.inc(1);
+                }
+                job_progress_bar.finish_and_clear();
+            });
+        }
+    });
+    progress_bar.finish_and_clear();
+    pairs
+        .into_iter()
+        .zip(reports.into_inner().expect("No comparison panicked"))
+        .map(|(pair, report)| PairResult { pair, report })
+        .collect()
+}
+
+/// Shows in the line of a job the columns of the pair being compared
+struct JobProgress<'a> {
+    progress_bar: &'a ProgressBar,
+    pair_name: &'a str,
+}
+
+impl ComparisonObserver for JobProgress<'_> {
+    fn columns_started(&self, columns: &[&String]) {
+        self.progress_bar.set_message(format!(
+            "{}: {}",
+            self.pair_name,
+            columns
+                .iter()
+                .map(|column| column.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+}
+
+/// Exit code of the first pair that isn't identical. A file without pair counts as different columns
+pub fn exit_code(results: &[PairResult], args: &Args) -> i32 {
+    results
+        .iter()
+        .map(|result| match &result.report {
+            Some(report) => report.result.exit_code(args),
+            None => ComparisonResult::DifferentColumns.exit_code(args),
+        })
+        .find(|exit_code| *exit_code != 0)
+        .unwrap_or(0)
+}
+
+/// Table with the result of every pair of files
+pub fn pairs_table(results: &[PairResult]) -> String {
+    let mut table = table::new_table(vec!["First file", "Second file", "Result", "Rows"]);
+    for result in results {
+        table.add_row(vec![
+            table::truncate(result.pair.first.as_deref().unwrap_or("-")),
+            table::truncate(result.pair.second.as_deref().unwrap_or("-")),
+            match &result.report {
+                Some(report) => report.result.name().to_string(),
+                None => "missing_file".to_string(),
+            },
+            result
+                .report
+                .as_ref()
+                .map_or(String::new(), |report| report.rows.to_string()),
+        ]);
+    }
+    table::align_right(&mut table, &[3]);
+    table.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_names_with_placeholders() {
+        let rule: PairRule = "{name}_v1.csv={name}_final.csv".parse().unwrap();
+        assert_eq!(
+            rule.second_name("orders_v1.csv"),
+            Some("orders_final.csv".to_string())
+        );
+        assert_eq!(rule.second_name("orders_v2.csv"), None);
+        assert_eq!(rule.to_string(), "{name}_v1.csv={name}_final.csv");
+    }
+
+    #[test]
+    fn placeholders_can_be_reordered() {
+        let rule: PairRule = "{day}/{table}.csv={table}_{day}.csv".parse().unwrap();
+        assert_eq!(
+            rule.second_name("2024-03-01/orders.csv"),
+            Some("orders_2024-03-01.csv".to_string())
+        );
+    }
+
+    #[test]
+    fn rule_text_is_matched_literally() {
+        let rule: PairRule = "{name}.v1.csv={name}.csv".parse().unwrap();
+        assert_eq!(rule.second_name("orders_v1.csv"), None);
+        assert_eq!(
+            rule.second_name("orders.v1.csv"),
+            Some("orders.csv".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!("{name}_v1.csv".parse::<PairRule>().is_err());
+        assert!("{name}_v1.csv={other}.csv".parse::<PairRule>().is_err());
+    }
+}
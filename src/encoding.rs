@@ -0,0 +1,204 @@
+use crate::report::EncodingDifference;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// Row of the first file, without its values or its text
+struct FirstRow {
+    text_hash: [u8; 16],
+    start: u64,
+    length: u64,
+}
+
+/// Rows whose text is different in both files but whose values are the same once parsed, because they're only
+/// encoded differently (quoted or unquoted fields, escaped quotes). Returns how many of them there are and up to
+/// `samples` of them, keyed by their first field. Only the hashes of the rows of the first file and where they are
+/// are kept in memory (about 100 bytes per row), the text of the samples is read again at the end
+pub(crate) fn find_encoding_differences(
+    first_file_path: &str,
+    second_file_path: &str,
+    separator: char,
+    samples: usize,
+) -> Result<(u64, Vec<EncodingDifference>)> {
+    let mut first_rows = HashMap::new();
+    for_each_row(
+        first_file_path,
+        separator,
+        |record, line, (start, length)| {
+            first_rows.entry(values_hash(record)).or_insert(FirstRow {
+                text_hash: text_hash(line),
+                start,
+                length,
+            });
+        },
+    )?;
+
+    let mut rows = 0;
+    let mut found = vec![];
+    for_each_row(second_file_path, separator, |record, line, _| {
+        let Some(first_row) = first_rows.get(&values_hash(record)) else {
+            return;
+        };
+        if first_row.text_hash == text_hash(line) {
+            return;
+        }
+        rows += 1;
+        if found.len() < samples {
+            found.push((
+                record.get(0).map(str::to_string),
+                (first_row.start, first_row.length),
+                line.to_string(),
+            ));
+        }
+    })?;
+    drop(first_rows);
+
+    let mut first_file = File::open(first_file_path)
+        .with_context(|| format!("Couldn't open file {first_file_path}"))?;
+    let mut differences = vec![];
+    for (key, (start, length), second_line) in found {
+        let mut bytes = vec![0; length as usize];
+        first_file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| first_file.read_exact(&mut bytes))
+            .with_context(|| format!("Couldn't read file {first_file_path}"))?;
+        differences.push(EncodingDifference {
+            key,
+            first_line: String::from_utf8_lossy(&bytes).to_string(),
+            second_line,
+        });
+    }
+    Ok((rows, differences))
+}
+
+/// Hash of the parsed values, with their lengths so the values can't be split in other ways with the same hash
+fn values_hash(record: &csv::StringRecord) -> [u8; 16] {
+    let mut hasher = Sha256::new();
+    for field in record {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.finalize()[..16]
+        .try_into()
+        .expect("SHA-256 has more than 16 bytes")
+}
+
+fn text_hash(line: &str) -> [u8; 16] {
+    Sha256::digest(line.as_bytes())[..16]
+        .try_into()
+        .expect("SHA-256 has more than 16 bytes")
+}
+
+/// Calls the function with the parsed fields, the text of every row after the header (without line terminators)
+/// and where the text is in the file (start and length in bytes). The file is read twice at the same time, parsed
+/// and as text, so only one row is in memory
+fn for_each_row(
+    file_path: &str,
+    separator: char,
+    mut function: impl FnMut(&csv::StringRecord, &str, (u64, u64)),
+) -> Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(file_path)
+        .with_context(|| format!("Couldn't open file {file_path}"))?;
+    let mut text = BufReader::new(
+        File::open(file_path).with_context(|| format!("Couldn't open file {file_path}"))?,
+    );
+    let mut record = csv::StringRecord::new();
+    let mut bytes = vec![];
+    let mut start = 0;
+    let mut is_header = true;
+    while reader
+        .read_record(&mut record)
+        .with_context(|| format!("Couldn't parse file {file_path}"))?
+    {
+        let end = reader.position().byte();
+        bytes.resize((end - start) as usize, 0);
+        text.read_exact(&mut bytes)
+            .with_context(|| format!("Couldn't read file {file_path}"))?;
+        let row_start = start;
+        start = end;
+        if is_header {
+            is_header = false;
+            continue;
+        }
+        let is_terminator = |byte: &&u8| matches!(byte, b'\r' | b'\n');
+        let leading = bytes.iter().take_while(is_terminator).count();
+        let trailing = bytes[leading..]
+            .iter()
+            .rev()
+            .take_while(is_terminator)
+            .count();
+        let row = &bytes[leading..bytes.len() - trailing];
+        function(
+            &record,
+            &String::from_utf8_lossy(row),
+            (row_start + leading as u64, row.len() as u64),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn differences(first: &str, second: &str, samples: usize) -> (u64, Vec<EncodingDifference>) {
+        let (first, second) = (file(first), file(second));
+        find_encoding_differences(
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+            ',',
+            samples,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_the_rows_encoded_differently() {
+        let (rows, differences) = differences(
+            "id,name\n1,Ann\n2,\"Bob \"\"B\"\"\"\n3,Carl\n",
+            "id,name\r\n\"1\",\"Ann\"\r\n3,Carl\r\n2,\"Bob \"\"B\"\"\"\r\n",
+            5,
+        );
+        assert_eq!(rows, 1);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].key.as_deref(), Some("1"));
+        assert_eq!(differences[0].first_line, "1,Ann");
+        assert_eq!(differences[0].second_line, "\"1\",\"Ann\"");
+    }
+
+    #[test]
+    fn reads_the_samples_of_the_first_file_again() {
+        let (rows, differences) = differences(
+            "id,name\n1,Ann\n\n2,Bob\n3,\"Carl\"\n",
+            "id,name\n\"1\",Ann\n\"2\",Bob\n3,Carl\n",
+            2,
+        );
+        assert_eq!(rows, 3);
+        let first_lines: Vec<_> = differences
+            .iter()
+            .map(|difference| difference.first_line.as_str())
+            .collect();
+        assert_eq!(first_lines, ["1,Ann", "2,Bob"]);
+    }
+
+    #[test]
+    fn ignores_rows_with_different_values() {
+        let (rows, differences) = differences("id,name\n1,Ann\n", "id,name\n1,\"Anna\"\n", 5);
+        assert_eq!(rows, 0);
+        assert!(differences.is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+const MAX_SNIPPET_LENGTH: usize = 120;
+
+/// A file being compared, used to give context to the errors found while reading it
+pub(crate) struct SourceFile<'a> {
+    /// Path given by the user
+    name: &'a str,
+    /// Path actually read (may be a temporary copy with the ragged rows fixed), none for data frames in memory
+    path: Option<&'a str>,
+    separator: char,
+}
+
+impl<'a> SourceFile<'a> {
+    pub(crate) fn new(name: &'a str, path: &'a str, separator: char) -> Self {
+        SourceFile {
+            name,
+            path: Some(path),
+            separator,
+        }
+    }
+
+    /// A data frame given by the library user, with no file to look for invalid lines
+    pub(crate) fn in_memory(name: &'a str) -> Self {
+        SourceFile {
+            name,
+            path: None,
+            separator: ',',
+        }
+    }
+
+    /// Name of the file and, when a line can't be parsed, its position and content.
+    /// It's only called after an error, so the file is read again from the start looking for it
+    pub(crate) fn context(&self) -> String {
+        match self.find_invalid_line() {
+            Some((line, byte, snippet)) => format!(
+                "Error reading file {} at line {} (byte {}): `{}`",
+                self.name, line, byte, snippet
+            ),
+            None if self.path.is_none() => format!("Error reading {}", self.name),
+            None => format!("Error reading file {}", self.name),
+        }
+    }
+
+    fn find_invalid_line(&self) -> Option<(u64, u64, String)> {
+        let path = self.path?;
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.separator as u8)
+            .from_path(path)
+            .ok()?;
+        let error = reader.records().find_map(Result::err)?;
+        let position = error.position()?;
+        let snippet = read_line_at(path, position.byte())?;
+        Some((position.line(), position.byte(), snippet))
+    }
+}
+
+fn read_line_at(file_path: &str, byte: u64) -> Option<String> {
+    let mut file = File::open(file_path).ok()?;
+    file.seek(SeekFrom::Start(byte)).ok()?;
+    let mut line = vec![];
+    BufReader::new(file).read_until(b'\n', &mut line).ok()?;
+    let line = String::from_utf8_lossy(&line);
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.chars().count() > MAX_SNIPPET_LENGTH {
+        Some(format!(
+            "{}...",
+            line.chars().take(MAX_SNIPPET_LENGTH).collect::<String>()
+        ))
+    } else {
+        Some(line.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn points_to_the_invalid_line() {
+        let file = file("id,name\n1,Ann\n2\n3,Carl\n");
+        let source = SourceFile::new("data.csv", file.path().to_str().unwrap(), ',');
+        assert_eq!(
+            source.context(),
+            "Error reading file data.csv at line 3 (byte 14): `2`"
+        );
+    }
+
+    #[test]
+    fn only_names_the_file_when_no_line_is_invalid() {
+        let file = file("id,name\n1,Ann\n");
+        let source = SourceFile::new("data.csv", file.path().to_str().unwrap(), ',');
+        assert_eq!(source.context(), "Error reading file data.csv");
+        assert_eq!(
+            SourceFile::in_memory("data frame").context(),
+            "Error reading data frame"
+        );
+    }
+
+    #[test]
+    fn shortens_long_lines() {
+        let long_line = "x".repeat(MAX_SNIPPET_LENGTH + 10);
+        let file = file(&format!("{long_line}\n"));
+        let snippet = read_line_at(file.path().to_str().unwrap(), 0).unwrap();
+        assert_eq!(snippet, format!("{}...", &long_line[..MAX_SNIPPET_LENGTH]));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::report::ColumnDifference;
+
+/// Callbacks invoked while comparing, so library users can render their own progress
+/// instead of the progress bar and messages of the command line. All the methods do nothing by default
+pub trait ComparisonObserver {
+    /// Both files have been fetched, with this size in bytes between the two
+    fn input_size(&self, _bytes: u64) {}
+
+    /// Both files have been read and their rows counted
+    fn row_counts(&self, _first_file_rows: u64, _second_file_rows: u64) {}
+
+    /// A batch of columns is going to be compared
+    fn columns_started(&self, _columns: &[&String]) {}
+
+    /// A batch of columns has been compared
+    fn columns_finished(&self, _columns: &[&String], _are_equal: bool) {}
+
+    /// A column with different values has been found
+    fn difference_found(&self, _difference: &ColumnDifference) {}
+}
+
+/// Observer that ignores every event
+pub struct NoopObserver;
+
+impl ComparisonObserver for NoopObserver {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancel::CancellationToken;
+    use crate::output::Printer;
+    use crate::{compare_files_observed, Args};
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl ComparisonObserver for RecordingObserver {
+        fn row_counts(&self, first_file_rows: u64, second_file_rows: u64) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("rows {first_file_rows} {second_file_rows}"));
+        }
+
+        fn columns_finished(&self, columns: &[&String], are_equal: bool) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("finished {columns:?} {are_equal}"));
+        }
+
+        fn difference_found(&self, difference: &ColumnDifference) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("difference {}", difference.column));
+        }
+    }
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn the_observer_is_told_about_every_step() {
+        let first = file("id,name,age\n1,Ann,30\n2,Bob,40\n");
+        let second = file("id,name,age\n1,Ann,31\n2,Bob,40\n");
+        let (first, second) = (
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+        );
+        let args = Args::from_options(first, second, &[]).unwrap();
+        let observer = RecordingObserver::default();
+        compare_files_observed(
+            &args,
+            &Printer::new(true, false, false),
+            first,
+            second,
+            &CancellationToken::new(),
+            &observer,
+        );
+        assert_eq!(
+            observer.events.into_inner().unwrap(),
+            [
+                "rows 2 2",
+                r#"finished ["name"] true"#,
+                r#"finished ["age"] false"#,
+                "difference age",
+            ]
+        );
+    }
+}
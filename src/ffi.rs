@@ -0,0 +1,158 @@
+//! C ABI to embed the comparison in non Rust programs. See include/csv_compare.h
+
+use crate::output::Printer;
+use crate::{compare_files, Args};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Returned when the comparison couldn't start (null pointers, invalid strings or options) or panicked
+const FFI_ERROR: i32 = -1;
+
+/// Files and command line options of a comparison
+#[repr(C)]
+pub struct CsvCompareOptions {
+    pub file1: *const c_char,
+    pub file2: *const c_char,
+    /// Command line options, e.g. {"--separator", ";"}. Can be null if `options_len` is 0
+    pub options: *const *const c_char,
+    pub options_len: usize,
+}
+
+/// Compares the files and writes the JSON report into `report_json` (to be released with
+/// `csv_compare_free_string`). Returns the exit code the command line would return, including the one of
+/// failed comparisons (70 by default, `--exit-code-failed`) when a file can't be read, with the error in the
+/// report. Returns -1 when the comparison can't start (null pointers, invalid strings or options), and
+/// then `report_json` contains the error message, if it's not null.
+///
+/// # Safety
+/// `options` must point to a valid `CsvCompareOptions` whose strings are valid null terminated
+/// UTF-8 strings, and `report_json` must be null or a valid pointer to write the result to
+#[no_mangle]
+pub unsafe extern "C" fn csv_compare_run(
+    options: *const CsvCompareOptions,
+    report_json: *mut *mut c_char,
+) -> i32 {
+    let (exit_code, output) = match run(options) {
+        Ok(result) => result,
+        Err(message) => (FFI_ERROR, message),
+    };
+    if !report_json.is_null() {
+        *report_json = CString::new(output)
+            .map(CString::into_raw)
+            .unwrap_or(ptr::null_mut());
+    }
+    exit_code
+}
+
+/// Releases a string returned by this library
+///
+/// # Safety
+/// `string` must be null or a pointer returned by `csv_compare_run` not released yet
+#[no_mangle]
+pub unsafe extern "C" fn csv_compare_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+unsafe fn run(options: *const CsvCompareOptions) -> Result<(i32, String), String> {
+    let options = options.as_ref().ok_or("Options can't be null")?;
+    let file1 = to_string(options.file1)?;
+    let file2 = to_string(options.file2)?;
+    let mut command_line_options = vec![];
+    if options.options_len > 0 {
+        if options.options.is_null() {
+            return Err("Options array can't be null".to_string());
+        }
+        for index in 0..options.options_len {
+            command_line_options.push(to_string(*options.options.add(index))?);
+        }
+    }
+
+    let args = Args::from_options(&file1, &file2, &command_line_options)?;
+    let printer = Printer::new(true, false, false);
+    let report = catch_unwind(AssertUnwindSafe(|| {
+        compare_files(&args, &printer, &file1, &file2)
+    }))
+    .map_err(|_| "Comparison failed".to_string())?;
+    let json = serde_json::to_string(&report).map_err(|error| error.to_string())?;
+    Ok((report.result.exit_code(&args), json))
+}
+
+unsafe fn to_string(string: *const c_char) -> Result<String, String> {
+    if string.is_null() {
+        return Err("File paths can't be null".to_string());
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map(str::to_string)
+        .map_err(|_| "Strings must be valid UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn run_with(file1: &str, file2: &str, options: &[&str]) -> (i32, String) {
+        let file1 = CString::new(file1).unwrap();
+        let file2 = CString::new(file2).unwrap();
+        let options: Vec<CString> = options
+            .iter()
+            .map(|option| CString::new(*option).unwrap())
+            .collect();
+        let option_pointers: Vec<*const c_char> =
+            options.iter().map(|option| option.as_ptr()).collect();
+        let compare_options = CsvCompareOptions {
+            file1: file1.as_ptr(),
+            file2: file2.as_ptr(),
+            options: option_pointers.as_ptr(),
+            options_len: option_pointers.len(),
+        };
+        let mut report_json = ptr::null_mut();
+        unsafe {
+            let exit_code = csv_compare_run(&compare_options, &mut report_json);
+            let output = CStr::from_ptr(report_json).to_str().unwrap().to_string();
+            csv_compare_free_string(report_json);
+            (exit_code, output)
+        }
+    }
+
+    #[test]
+    fn returns_the_exit_code_and_the_report() {
+        let first = file("id,name\n1,Ann\n2,Bob\n");
+        let second = file("id,name\n1,Ann\n2,Bob\n");
+        let (exit_code, report_json) = run_with(
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+            &[],
+        );
+        assert_eq!(exit_code, 0);
+        assert!(report_json.contains(r#""result":"identical""#));
+    }
+
+    #[test]
+    fn returns_an_error_when_the_comparison_cant_start() {
+        let (exit_code, message) = run_with("a.csv", "b.csv", &["--no-such-option"]);
+        assert_eq!(exit_code, FFI_ERROR);
+        assert!(!message.is_empty());
+        let mut report_json = ptr::null_mut();
+        let exit_code = unsafe { csv_compare_run(ptr::null(), &mut report_json) };
+        assert_eq!(exit_code, FFI_ERROR);
+        unsafe {
+            assert_eq!(
+                CStr::from_ptr(report_json).to_str().unwrap(),
+                "Options can't be null"
+            );
+            csv_compare_free_string(report_json);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+use anyhow::{bail, Context, Result};
+use polars::prelude::{
+    col, lit, BooleanChunked, DataType, Expr, GetOutput, IntoSeries, JoinArgs, JoinType, LazyFrame,
+    NamedFrom, Series,
+};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Keys listed in a file, one per line. Empty lines are ignored
+pub fn read_keys(file_path: &str) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(file_path)
+        .with_context(|| format!("Couldn't read the keys to exclude from {file_path}"))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Drops the rows whose key (compared as text) is one of the given keys
+pub fn exclude_keys(lazy_frame: LazyFrame, key: &str, keys: &[String]) -> LazyFrame {
+    let keys = Series::new("keys", keys);
+    lazy_frame.filter(
+        col(key)
+            .cast(DataType::Utf8)
+            .is_in(lit(keys))
+            .not()
+            .fill_null(lit(true)),
+    )
+}
+
+/// Rows whose value of a column matches a regular expression, written as `column=~regex`
+#[derive(Debug, Clone)]
+pub struct RowExclusion {
+    column: String,
+    regex: Regex,
+}
+
+impl FromStr for RowExclusion {
+    type Err = String;
+
+    fn from_str(exclusion: &str) -> Result<Self, Self::Err> {
+        let (column, pattern) = exclusion
+            .split_once("=~")
+            .ok_or_else(|| format!("Expected column=~regex, found {exclusion}"))?;
+        let regex = Regex::new(pattern)
+            .map_err(|error| format!("Invalid regular expression {pattern}: {error}"))?;
+        Ok(RowExclusion {
+            column: column.trim().to_string(),
+            regex,
+        })
+    }
+}
+
+/// Drops the rows whose value of the column (as text) matches the regular expression
+pub fn exclude_rows(lazy_frame: LazyFrame, exclusion: &RowExclusion) -> LazyFrame {
+    let regex = exclusion.regex.clone();
+    let matches = col(&exclusion.column).map(
+        move |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let mask: BooleanChunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.is_some_and(|value| regex.is_match(value)))
+                .collect();
+            Ok(Some(mask.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Boolean),
+    );
+    lazy_frame.filter(matches.not())
+}
+
+impl fmt::Display for RowExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} matches {}", self.column, self.regex)
+    }
+}
+
+/// Difference allowed between the number of rows of both files: a number of rows, or a percentage of the rows
+/// of the largest file, written as `N` or `P%`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowCountTolerance {
+    Rows(u64),
+    Percent(f64),
+}
+
+impl RowCountTolerance {
+    pub fn allows(&self, first_rows: u64, second_rows: u64) -> bool {
+        let difference = first_rows.abs_diff(second_rows);
+        match self {
+            RowCountTolerance::Rows(rows) => difference <= *rows,
+            RowCountTolerance::Percent(percent) => {
+                difference as f64 <= first_rows.max(second_rows) as f64 * percent / 100.0
+            }
+        }
+    }
+}
+
+impl FromStr for RowCountTolerance {
+    type Err = String;
+
+    fn from_str(tolerance: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("Expected a number of rows or a percentage (N or P%), found {tolerance}");
+        match tolerance.trim().strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(percent) if percent >= 0.0 => Ok(RowCountTolerance::Percent(percent)),
+                _ => Err(invalid()),
+            },
+            None => tolerance
+                .trim()
+                .parse()
+                .map(RowCountTolerance::Rows)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+impl fmt::Display for RowCountTolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowCountTolerance::Rows(rows) => write!(f, "{} rows", rows),
+            RowCountTolerance::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// Leaves in each file only the rows whose key is also in the other file
+pub fn keys_in_both(
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+    key: &str,
+) -> (LazyFrame, LazyFrame) {
+    let keys_in = |lazy_frame: LazyFrame, other_lazy_frame: &LazyFrame| {
+        lazy_frame.join(
+            other_lazy_frame.clone().select([col(key)]),
+            [col(key)],
+            [col(key)],
+            JoinArgs::new(JoinType::Semi),
+        )
+    };
+    (
+        keys_in(first_lazy_frame.clone(), &second_lazy_frame),
+        keys_in(second_lazy_frame, &first_lazy_frame),
+    )
+}
+
+/// Window of keys written as `lower..upper` (upper key excluded) or `lower..=upper` (upper key included). Either
+/// bound can be left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyWindow {
+    lower: Option<String>,
+    upper: Option<String>,
+    inclusive: bool,
+}
+
+impl FromStr for KeyWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let (lower, upper) = window
+            .split_once("..")
+            .ok_or_else(|| format!("Expected lower..upper, found {window}"))?;
+        let (upper, inclusive) = match upper.strip_prefix('=') {
+            Some(upper) => (upper, true),
+            None => (upper, false),
+        };
+        let bound = |bound: &str| Some(bound.trim().to_string()).filter(|bound| !bound.is_empty());
+        let (lower, upper) = (bound(lower), bound(upper));
+        if lower.is_none() && upper.is_none() {
+            return Err(format!("Expected at least one bound in {window}"));
+        }
+        if inclusive && upper.is_none() {
+            return Err(format!("Expected an upper bound after ..= in {window}"));
+        }
+        if let (Some(lower), Some(upper)) = (&lower, &upper) {
+            let order = match (lower.parse::<f64>(), upper.parse::<f64>()) {
+                (Ok(lower), Ok(upper)) => lower.partial_cmp(&upper),
+                _ => Some(lower.cmp(upper)),
+            };
+            if order == Some(Ordering::Greater) || (order == Some(Ordering::Equal) && !inclusive) {
+                return Err(format!("The key range {window} is empty"));
+            }
+        }
+        Ok(KeyWindow {
+            lower,
+            upper,
+            inclusive,
+        })
+    }
+}
+
+impl fmt::Display for KeyWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if self.inclusive { "..=" } else { ".." };
+        write!(
+            f,
+            "{}{}{}",
+            self.lower.as_deref().unwrap_or_default(),
+            separator,
+            self.upper.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Leaves only the rows whose key is in the window, compared as numbers when the key is numeric (typed or cast
+/// with --key-type) and as text otherwise. Numeric bounds are rejected for text keys, where "10" sorts before "9"
+pub fn keys_in_window(lazy_frame: LazyFrame, key: &str, window: &KeyWindow) -> Result<LazyFrame> {
+    let schema = lazy_frame
+        .schema()
+        .with_context(|| format!("Couldn't read the type of column {key}"))?;
+    let numeric = schema
+        .get(key)
+        .with_context(|| format!("Column {key} not found"))?
+        .is_numeric();
+    if !numeric {
+        if let Some(number) = [&window.lower, &window.upper]
+            .into_iter()
+            .flatten()
+            .find(|bound| bound.parse::<f64>().is_ok())
+        {
+            bail!(
+                "The key range {window} has the number {number}, but column {key} is text and its keys would be \
+                 compared as text (\"10\" before \"9\"). Use --key-type int or float, or --typed"
+            );
+        }
+    }
+    let key_expr = if numeric {
+        col(key)
+    } else {
+        col(key).cast(DataType::Utf8)
+    };
+    let bound = |bound: &str| -> Result<Expr> {
+        if !numeric {
+            return Ok(lit(bound.to_string()));
+        }
+        match bound.parse::<f64>() {
+            Ok(number) => Ok(lit(number)),
+            Err(_) => {
+                bail!("The key range bound {bound} isn't a number, but column {key} is numeric")
+            }
+        }
+    };
+    let mut lazy_frame = lazy_frame;
+    if let Some(lower) = &window.lower {
+        lazy_frame = lazy_frame.filter(key_expr.clone().gt_eq(bound(lower)?));
+    }
+    if let Some(upper) = &window.upper {
+        let upper = bound(upper)?;
+        lazy_frame = lazy_frame.filter(if window.inclusive {
+            key_expr.lt_eq(upper)
+        } else {
+            key_expr.lt(upper)
+        });
+    }
+    Ok(lazy_frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    #[test]
+    fn parses_row_count_tolerances() {
+        assert_eq!("10".parse(), Ok(RowCountTolerance::Rows(10)));
+        assert_eq!(" 0.5% ".parse(), Ok(RowCountTolerance::Percent(0.5)));
+        assert!("-1%".parse::<RowCountTolerance>().is_err());
+        assert!("ten".parse::<RowCountTolerance>().is_err());
+        assert!("%".parse::<RowCountTolerance>().is_err());
+    }
+
+    #[test]
+    fn zero_tolerance_only_allows_the_same_row_count() {
+        for tolerance in [RowCountTolerance::Rows(0), RowCountTolerance::Percent(0.0)] {
+            assert!(tolerance.allows(100, 100));
+            assert!(!tolerance.allows(100, 101));
+            assert!(tolerance.allows(0, 0));
+        }
+    }
+
+    #[test]
+    fn tolerance_applies_to_both_directions() {
+        let rows = RowCountTolerance::Rows(2);
+        assert!(rows.allows(10, 12));
+        assert!(rows.allows(12, 10));
+        assert!(!rows.allows(10, 13));
+        let percent = RowCountTolerance::Percent(10.0);
+        assert!(percent.allows(90, 100));
+        assert!(!percent.allows(89, 100));
+    }
+
+    #[test]
+    fn parses_key_windows() {
+        let window: KeyWindow = "1000..2000".parse().unwrap();
+        assert_eq!(window.to_string(), "1000..2000");
+        let window: KeyWindow = "A..=M".parse().unwrap();
+        assert_eq!(window.to_string(), "A..=M");
+        assert_eq!("..5".parse::<KeyWindow>().unwrap().to_string(), "..5");
+        assert_eq!("5..".parse::<KeyWindow>().unwrap().to_string(), "5..");
+        assert!("5..=5".parse::<KeyWindow>().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_and_empty_key_windows() {
+        assert!("1000".parse::<KeyWindow>().is_err());
+        assert!("..".parse::<KeyWindow>().is_err());
+        assert!("5..=".parse::<KeyWindow>().is_err());
+        assert!("5..5".parse::<KeyWindow>().is_err());
+        assert!("2000..1000".parse::<KeyWindow>().is_err());
+        assert!("M..A".parse::<KeyWindow>().is_err());
+        // Compared as numbers, not as text
+        assert!("9..10".parse::<KeyWindow>().is_ok());
+    }
+
+    #[test]
+    fn filters_numeric_and_text_keys_in_window() {
+        let keys = |lazy_frame: LazyFrame| -> Vec<String> {
+            let data_frame = lazy_frame.collect().unwrap();
+            let keys = data_frame
+                .column("id")
+                .unwrap()
+                .cast(&DataType::Utf8)
+                .unwrap();
+            keys.utf8()
+                .unwrap()
+                .into_iter()
+                .map(|key| key.unwrap().to_string())
+                .collect()
+        };
+        let numbers = df!("id" => [5i64, 9, 10, 20]).unwrap().lazy();
+        let window = "9..20".parse().unwrap();
+        assert_eq!(
+            keys(keys_in_window(numbers.clone(), "id", &window).unwrap()),
+            ["9", "10"]
+        );
+        let window = "9..=20".parse().unwrap();
+        assert_eq!(
+            keys(keys_in_window(numbers, "id", &window).unwrap()),
+            ["9", "10", "20"]
+        );
+
+        let texts = df!("id" => ["a", "b", "m", "z"]).unwrap().lazy();
+        let window = "A..b".parse().unwrap();
+        assert_eq!(
+            keys(keys_in_window(texts.clone(), "id", &window).unwrap()),
+            ["a"]
+        );
+        let window = "b..=m".parse().unwrap();
+        assert_eq!(
+            keys(keys_in_window(texts.clone(), "id", &window).unwrap()),
+            ["b", "m"]
+        );
+        let window = "n..".parse().unwrap();
+        assert_eq!(keys(keys_in_window(texts, "id", &window).unwrap()), ["z"]);
+    }
+
+    #[test]
+    fn text_keys_reject_numeric_bounds() {
+        let texts = df!("id" => ["9", "10", "1000", "10000"]).unwrap().lazy();
+        for window in ["9..10", "1000..2000", "..5", "1.5.."] {
+            let window = window.parse().unwrap();
+            assert!(keys_in_window(texts.clone(), "id", &window).is_err());
+        }
+        let numbers = texts.select([col("id").cast(DataType::Int64)]);
+        let window = "1000..2000".parse().unwrap();
+        assert_eq!(
+            keys_in_window(numbers, "id", &window)
+                .unwrap()
+                .collect()
+                .unwrap()
+                .height(),
+            1
+        );
+    }
+
+    #[test]
+    fn numeric_keys_need_numeric_bounds() {
+        let numbers = df!("id" => [1i64, 2]).unwrap().lazy();
+        let window = "a..b".parse().unwrap();
+        assert!(keys_in_window(numbers, "id", &window).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::inputs::temporary_file;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use tempfile::NamedTempFile;
+
+/// How the text of a fixed-width field is written in the CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Text without the padding spaces
+    String,
+    /// Integer without the padding zeros, e.g. `+0000123` is `123`
+    Integer,
+    /// Decimal number without the padding zeros, e.g. `000123.450` is `123.45`
+    Decimal,
+    /// Text as it is, spaces included
+    Raw,
+}
+
+/// A column of a fixed-width file: its name, where it starts (1 is the first character of the line),
+/// how many characters it takes and its type
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub start: usize,
+    pub width: usize,
+    #[serde(rename = "type", default = "default_field_type")]
+    pub field_type: FieldType,
+}
+
+fn default_field_type() -> FieldType {
+    FieldType::String
+}
+
+/// Reads the columns of a fixed-width file from a CSV file with the columns name, start, width and type
+pub fn read_spec(spec_path: &str) -> Result<Vec<ColumnSpec>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(spec_path)
+        .with_context(|| format!("Couldn't read the fixed-width spec {spec_path}"))?;
+    let columns = reader
+        .deserialize()
+        .collect::<Result<Vec<ColumnSpec>, _>>()
+        .with_context(|| format!("Invalid fixed-width spec {spec_path}"))?;
+    if columns.is_empty() {
+        bail!("The fixed-width spec {spec_path} has no columns");
+    }
+    if let Some(column) = columns.iter().find(|column| column.start == 0) {
+        bail!(
+            "Invalid fixed-width spec {spec_path}: column {} starts at 0 (the first position is 1)",
+            column.name
+        );
+    }
+    Ok(columns)
+}
+
+/// Writes the fixed-width file as a CSV file with the columns of the spec
+pub fn convert(file_path: &str, columns: &[ColumnSpec], separator: char) -> Result<NamedTempFile> {
+    let file = File::open(file_path).with_context(|| format!("Couldn't open {file_path}"))?;
+    let mut output = temporary_file()?;
+    {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(separator as u8)
+            .from_writer(&mut output);
+        writer.write_record(columns.iter().map(|column| &column.name))?;
+        for (line_index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("Couldn't read {file_path}"))?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            let characters: Vec<char> = line.chars().collect();
+            let fields = columns
+                .iter()
+                .map(|column| field_value(&characters, column))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| {
+                    format!("Error reading file {file_path} at line {}", line_index + 1)
+                })?;
+            writer.write_record(fields)?;
+        }
+        writer.flush()?;
+    }
+    Ok(output)
+}
+
+fn field_value(characters: &[char], column: &ColumnSpec) -> Result<String> {
+    let start = (column.start - 1).min(characters.len());
+    let end = (start + column.width).min(characters.len());
+    let text: String = characters[start..end].iter().collect();
+    match column.field_type {
+        FieldType::Raw => Ok(text),
+        FieldType::String => Ok(text.trim().to_string()),
+        FieldType::Integer | FieldType::Decimal => normalize_number(text.trim(), column),
+    }
+}
+
+fn normalize_number(text: &str, column: &ColumnSpec) -> Result<String> {
+    if text.is_empty() {
+        return Ok(String::new());
+    }
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (integer_part, decimal_part) = match digits.split_once('.') {
+        Some(_) if column.field_type == FieldType::Integer => {
+            bail!("Invalid integer `{text}` in column {}", column.name)
+        }
+        Some((integer_part, decimal_part)) => (integer_part, decimal_part),
+        None => (digits, ""),
+    };
+    if integer_part.is_empty() && decimal_part.is_empty()
+        || !integer_part.chars().all(|c| c.is_ascii_digit())
+        || !decimal_part.chars().all(|c| c.is_ascii_digit())
+    {
+        bail!("Invalid number `{text}` in column {}", column.name);
+    }
+    let integer_part = match integer_part.trim_start_matches('0') {
+        "" => "0",
+        integer_part => integer_part,
+    };
+    let decimal_part = decimal_part.trim_end_matches('0');
+    let is_zero = integer_part == "0" && decimal_part.is_empty();
+    Ok(format!(
+        "{}{}{}{}",
+        if negative && !is_zero { "-" } else { "" },
+        integer_part,
+        if decimal_part.is_empty() { "" } else { "." },
+        decimal_part
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn column(field_type: FieldType) -> ColumnSpec {
+        ColumnSpec {
+            name: "amount".to_string(),
+            start: 1,
+            width: 10,
+            field_type,
+        }
+    }
+
+    #[test]
+    fn normalizes_numbers() {
+        let decimal = column(FieldType::Decimal);
+        assert_eq!(normalize_number("+000123.450", &decimal).unwrap(), "123.45");
+        assert_eq!(normalize_number("-0000.00", &decimal).unwrap(), "0");
+        assert_eq!(normalize_number("-.5", &decimal).unwrap(), "-0.5");
+        assert_eq!(normalize_number("", &decimal).unwrap(), "");
+        assert!(normalize_number("12a", &decimal).is_err());
+        assert!(normalize_number(".", &decimal).is_err());
+        let integer = column(FieldType::Integer);
+        assert_eq!(normalize_number("+0000123", &integer).unwrap(), "123");
+        assert!(normalize_number("1.5", &integer).is_err());
+    }
+
+    #[test]
+    fn converts_to_csv() {
+        let spec = file("name,start,width,type\nid,1,3,integer\nname,4,6,string\namount,10,8,decimal\ncode,18,3,raw\n");
+        let columns = read_spec(spec.path().to_str().unwrap()).unwrap();
+        let data = file("001Ann   +0012.50 a \r\n\n002Bob   -0000.00\n");
+        let csv_file = convert(data.path().to_str().unwrap(), &columns, ',').unwrap();
+        assert_eq!(
+            std::fs::read_to_string(csv_file.path()).unwrap(),
+            "id,name,amount,code\n1,Ann,12.5, a \n2,Bob,0,\n"
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_numbers() {
+        let columns = vec![column(FieldType::Integer)];
+        let data = file("12\n1x\n");
+        let error = convert(data.path().to_str().unwrap(), &columns, ',').unwrap_err();
+        assert!(format!("{error:#}").contains("at line 2"));
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert!(read_spec(file("name,start,width\n").path().to_str().unwrap()).is_err());
+        let spec = file("name,start,width\nid,0,3\n");
+        assert!(read_spec(spec.path().to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::process::Command;
+use tempfile::NamedTempFile;
+
+/// Revision compared with the working tree when no --rev is given
+const DEFAULT_REVISION: &str = "HEAD";
+
+/// One of the two versions of the file to compare
+pub enum FileVersion {
+    /// The file as it is in the working tree
+    WorkingTree(String),
+    /// The file extracted from a git revision into a temporary file
+    Revision(NamedTempFile),
+}
+
+impl FileVersion {
+    pub fn path(&self) -> String {
+        match self {
+            FileVersion::WorkingTree(path) => path.to_string(),
+            FileVersion::Revision(temp_file) => temp_file.path().display().to_string(),
+        }
+    }
+}
+
+/// Gets the two versions of the file to compare from a revision range:
+/// `A..B` compares revision A with revision B, `A` compares revision A with the working tree
+pub fn get_file_versions(
+    path: &str,
+    revisions: Option<&str>,
+) -> Result<(FileVersion, FileVersion), String> {
+    let revisions = revisions.unwrap_or(DEFAULT_REVISION);
+    match revisions.split_once("..") {
+        Some((first_revision, second_revision)) => Ok((
+            extract_revision(path, first_revision)?,
+            extract_revision(path, second_revision)?,
+        )),
+        None => Ok((
+            extract_revision(path, revisions)?,
+            FileVersion::WorkingTree(path.to_string()),
+        )),
+    }
+}
+
+/// Writes the content of the file in the given revision into a temporary file
+fn extract_revision(path: &str, revision: &str) -> Result<FileVersion, String> {
+    let revision = if revision.is_empty() {
+        DEFAULT_REVISION
+    } else {
+        revision
+    };
+    // ./ makes git resolve the path relative to the current directory instead of the repository root
+    let relative_path = if path.starts_with("./") || path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("./{path}")
+    };
+
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("{revision}:{relative_path}"))
+        .output()
+        .map_err(|error| format!("Couldn't run git: {error}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Couldn't get {path} from revision {revision}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut temp_file = tempfile::Builder::new()
+        .prefix("csv-compare-")
+        .suffix(".csv")
+        .tempfile()
+        .map_err(|error| format!("Couldn't create temporary file: {error}"))?;
+    temp_file
+        .write_all(&output.stdout)
+        .map_err(|error| format!("Couldn't write temporary file: {error}"))?;
+    Ok(FileVersion::Revision(temp_file))
+}
+
+/// Gets the two paths to compare from the arguments passed by git: 2 arguments for
+/// `git difftool` ($LOCAL $REMOTE) or 7 for an external diff driver (path old-file old-hex old-mode new-file new-hex new-mode)
+pub fn get_difftool_paths(paths: &[String]) -> Result<(String, String), String> {
+    match paths {
+        [local, remote] => Ok((local.to_string(), remote.to_string())),
+        [_, old_file, _, _, new_file, _, _] => Ok((old_file.to_string(), new_file.to_string())),
+        _ => Err(format!(
+            "Expected 2 (difftool) or 7 (diff driver) paths from git, but got {}",
+            paths.len()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn gets_the_paths_from_difftool_and_diff_drivers() {
+        assert_eq!(
+            get_difftool_paths(&paths(&["local.csv", "remote.csv"])),
+            Ok(("local.csv".to_string(), "remote.csv".to_string()))
+        );
+        let driver_args = paths(&[
+            "a.csv", "/tmp/old", "abc", "100644", "a.csv", "def", "100644",
+        ]);
+        assert_eq!(
+            get_difftool_paths(&driver_args),
+            Ok(("/tmp/old".to_string(), "a.csv".to_string()))
+        );
+        assert!(get_difftool_paths(&paths(&["a.csv"])).is_err());
+    }
+
+    #[test]
+    fn fails_when_the_file_is_not_in_the_revision() {
+        assert!(get_file_versions("no-such-file.csv", Some("HEAD..")).is_err());
+        assert!(get_file_versions("no-such-file.csv", None).is_err());
+        assert_eq!(
+            FileVersion::WorkingTree("data.csv".to_string()).path(),
+            "data.csv"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::canonicalize::read_normalized;
+use crate::compare::{as_text, get_text};
+use crate::errors::SourceFile;
+use crate::{inputs, Args};
+use anyhow::{Context, Result};
+use polars::prelude::Series;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Hash of the content of a file that doesn't depend on the order of its rows and columns
+#[derive(Debug, Serialize)]
+pub struct ContentHash {
+    /// Hexadecimal SHA-256
+    pub hash: String,
+    pub rows: u64,
+}
+
+/// Hashes the file once the exclusions and normalizations of the arguments are applied.
+/// Every row is hashed with its values in the order of the sorted column names, and the sorted
+/// hashes of the rows are hashed again, so the result is the same whatever the order of rows and columns
+pub fn hash_file(args: &Args, file_path: &str, key: Option<&str>) -> Result<ContentHash> {
+    let input = inputs::fetch(file_path, args)?;
+    let (lazy_frame, _) = read_normalized(args, file_path, input.path(), key)?;
+    let data_frame = lazy_frame
+        .collect()
+        .with_context(|| SourceFile::new(file_path, input.path(), args.separator).context())?;
+
+    let mut columns: Vec<&Series> = data_frame.get_columns().iter().collect();
+    columns.sort_by(|a, b| a.name().cmp(b.name()));
+    let values: Vec<Series> = columns.iter().map(|series| as_text(series)).collect();
+
+    let mut row_hashes: Vec<[u8; 32]> = (0..data_frame.height())
+        .map(|row| {
+            let mut hasher = Sha256::new();
+            for series in &values {
+                match get_text(series, row) {
+                    Some(value) => {
+                        hasher.update([1u8]);
+                        hasher.update((value.len() as u64).to_le_bytes());
+                        hasher.update(value.as_bytes());
+                    }
+                    None => hasher.update([0u8]),
+                }
+            }
+            hasher.finalize().into()
+        })
+        .collect();
+    row_hashes.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for series in &columns {
+        hasher.update((series.name().len() as u64).to_le_bytes());
+        hasher.update(series.name().as_bytes());
+    }
+    for row_hash in &row_hashes {
+        hasher.update(row_hash);
+    }
+    Ok(ContentHash {
+        hash: hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        rows: row_hashes.len() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn hash(content: &str, options: &[&str]) -> ContentHash {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let path = file.path().to_str().unwrap();
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        let args = Args::from_options(path, path, &options).unwrap();
+        hash_file(&args, path, None).unwrap()
+    }
+
+    #[test]
+    fn doesnt_depend_on_the_order_of_rows_and_columns() {
+        let content_hash = hash("id,name\n1,Ann\n2,Bob\n", &[]);
+        assert_eq!(content_hash.rows, 2);
+        assert_eq!(content_hash.hash.len(), 64);
+        assert_eq!(content_hash.hash, hash("name,id\nBob,2\nAnn,1\n", &[]).hash);
+    }
+
+    #[test]
+    fn changes_with_the_values_and_names() {
+        let content_hash = hash("id,name\n1,Ann\n2,Bob\n", &[]).hash;
+        assert_ne!(content_hash, hash("id,name\n1,Ann\n2,Bobby\n", &[]).hash);
+        assert_ne!(
+            content_hash,
+            hash("id,first_name\n1,Ann\n2,Bob\n", &[]).hash
+        );
+        assert_ne!(
+            hash("id,a,b\n1,x,\n", &[]).hash,
+            hash("id,a,b\n1,,x\n", &[]).hash
+        );
+    }
+
+    #[test]
+    fn applies_the_exclusions() {
+        let excluded = hash("id,name\n1,Ann\n2,Bob\n", &["--exclude-rows", "id=~2"]);
+        assert_eq!(excluded.rows, 1);
+        assert_eq!(excluded.hash, hash("id,name\n1,Ann\n", &[]).hash);
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use polars::prelude::{col, Expr, LazyFrame};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Column used to sort the rows of both files: its name, or its position in the first file as `@3` (1-based)
+/// when the names are unreliable or duplicated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortColumn {
+    Name(String),
+    Position(usize),
+}
+
+impl FromStr for SortColumn {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.strip_prefix('@') {
+            Some(position) => match position.trim().parse() {
+                Ok(position) if position > 0 => Ok(SortColumn::Position(position)),
+                _ => Err(format!(
+                    "Expected a position starting at 1 after @, found {text}"
+                )),
+            },
+            None => Ok(SortColumn::Name(text.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for SortColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortColumn::Name(name) => write!(f, "{}", name),
+            SortColumn::Position(position) => write!(f, "@{}", position),
+        }
+    }
+}
+
+impl SortColumn {
+    /// Name of the column in the header of the first file
+    pub fn resolve(&self, header: &[String]) -> Result<String, String> {
+        match self {
+            SortColumn::Name(name) => header
+                .iter()
+                .find(|column| column == &name)
+                .cloned()
+                .ok_or_else(|| format!("Column {} to sort by doesn't exist", name)),
+            SortColumn::Position(position) => header.get(position - 1).cloned().ok_or_else(|| {
+                format!(
+                    "Column @{} to sort by doesn't exist, there are {} columns",
+                    position,
+                    header.len()
+                )
+            }),
+        }
+    }
+}
+
+/// Column names exactly as they are in the first line of the file (polars renames the duplicated ones)
+pub fn read_header(file_path: &str, separator: char) -> Result<Vec<String>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator as u8)
+        .has_headers(true)
+        .from_path(file_path)
+        .map_err(|error| format!("Couldn't open file {file_path}: {error}"))?;
+    let header = reader
+        .headers()
+        .map_err(|error| format!("Couldn't read the header of {file_path}: {error}"))?;
+    Ok(header.iter().map(str::to_string).collect())
+}
+
+/// Column names that appear more than once, in order of appearance
+pub fn find_duplicates(header: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut duplicates = vec![];
+    for column in header {
+        if !seen.insert(column) && !duplicates.contains(column) {
+            duplicates.push(column.to_string());
+        }
+    }
+    duplicates
+}
+
+/// Adds a suffix to the repeated column names: amount, amount_2, amount_3...
+pub fn disambiguate(header: &[String]) -> Vec<String> {
+    let mut occurrences: HashMap<&String, usize> = HashMap::new();
+    header
+        .iter()
+        .map(|column| {
+            let occurrence = occurrences.entry(column).or_insert(0);
+            *occurrence += 1;
+            if *occurrence == 1 {
+                column.to_string()
+            } else {
+                format!("{column}_{occurrence}")
+            }
+        })
+        .collect()
+}
+
+/// Names of the columns of the second file, taking the name of the column of the first file
+/// that is the same once trimmed (and lowercased when ignoring the case)
+pub fn match_names(
+    first_header: &[String],
+    second_header: &[String],
+    ignore_case: bool,
+) -> Vec<String> {
+    let normalize = |name: &str| {
+        if ignore_case {
+            name.trim().to_lowercase()
+        } else {
+            name.trim().to_string()
+        }
+    };
+    second_header
+        .iter()
+        .map(|second_column| {
+            let normalized = normalize(second_column);
+            first_header
+                .iter()
+                .find(|first_column| normalize(first_column) == normalized)
+                .unwrap_or(second_column)
+                .to_string()
+        })
+        .collect()
+}
+
+/// Column names without the prefix and suffix (e.g. src_amount_v2 as amount). A name is kept when it would be
+/// empty or the same as another column once stripped
+pub fn strip_affixes(header: &[String], prefix: Option<&str>, suffix: Option<&str>) -> Vec<String> {
+    let strip = |name: &str| {
+        let name = prefix
+            .and_then(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(name);
+        suffix
+            .and_then(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(name)
+            .to_string()
+    };
+    let mut taken: HashSet<String> = header.iter().cloned().collect();
+    header
+        .iter()
+        .map(|column| {
+            let stripped = strip(column);
+            if stripped == *column || stripped.is_empty() || !taken.insert(stripped.clone()) {
+                column.to_string()
+            } else {
+                stripped
+            }
+        })
+        .collect()
+}
+
+/// Groups of column names that are the same column (e.g. renamed in some version of the system), read from a
+/// YAML file where every name has the list of its other names:
+/// ```yaml
+/// customer_id: [cust_id, client_id]
+/// amount: [amt, total_amount]
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    groups: Vec<Vec<String>>,
+}
+
+impl Aliases {
+    pub fn load(path: &str) -> Result<Aliases> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read aliases {path}"))?;
+        let aliases: BTreeMap<String, Vec<String>> = serde_yaml::from_str(&content)
+            .with_context(|| format!("Couldn't parse aliases {path}"))?;
+        Ok(Aliases {
+            groups: aliases
+                .into_iter()
+                .map(|(name, other_names)| std::iter::once(name).chain(other_names).collect())
+                .collect(),
+        })
+    }
+
+    /// Names of the columns of the second file, taking the name of the column of the first file that is an
+    /// alias of it. The rest keep their name
+    pub fn match_names(&self, first_header: &[String], second_header: &[String]) -> Vec<String> {
+        second_header
+            .iter()
+            .map(|second_column| {
+                if first_header.contains(second_column) {
+                    return second_column.to_string();
+                }
+                self.groups
+                    .iter()
+                    .filter(|group| group.contains(second_column))
+                    .flat_map(|group| first_header.iter().find(|column| group.contains(column)))
+                    .find(|first_column| !second_header.contains(first_column))
+                    .unwrap_or(second_column)
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// Moves the column to the first position, where the key used to sort the rows is
+pub fn move_to_front(lazy_frame: LazyFrame, header: &[String], column: &str) -> LazyFrame {
+    let columns: Vec<Expr> = std::iter::once(column)
+        .chain(
+            header
+                .iter()
+                .map(String::as_str)
+                .filter(|name| *name != column),
+        )
+        .map(col)
+        .collect();
+    lazy_frame.select(columns)
+}
+
+/// Renames all the columns of the frame, position by position
+pub fn rename_columns(
+    lazy_frame: LazyFrame,
+    current_names: &[String],
+    new_names: &[String],
+) -> LazyFrame {
+    lazy_frame.rename(current_names, new_names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_the_header_with_its_duplicates() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"id;amount;amount\n1;2;3\n").unwrap();
+        let header = read_header(file.path().to_str().unwrap(), ';').unwrap();
+        assert_eq!(header, ["id", "amount", "amount"]);
+        assert!(read_header("no-such-file.csv", ',').is_err());
+    }
+
+    #[test]
+    fn finds_and_renames_duplicated_columns() {
+        let header = names(&["amount", "id", "amount", "amount", "id"]);
+        assert_eq!(find_duplicates(&header), ["amount", "id"]);
+        assert_eq!(
+            disambiguate(&header),
+            ["amount", "id", "amount_2", "amount_3", "id_2"]
+        );
+        assert!(find_duplicates(&names(&["id", "amount"])).is_empty());
+    }
+
+    #[test]
+    fn resolves_sort_columns_by_name_or_position() {
+        let header = names(&["id", "name"]);
+        assert_eq!(
+            "@2".parse::<SortColumn>().unwrap().resolve(&header),
+            Ok("name".to_string())
+        );
+        assert_eq!(
+            "id".parse::<SortColumn>().unwrap().resolve(&header),
+            Ok("id".to_string())
+        );
+        assert!("@3"
+            .parse::<SortColumn>()
+            .unwrap()
+            .resolve(&header)
+            .is_err());
+        assert!("@0".parse::<SortColumn>().is_err());
+        assert_eq!(SortColumn::Position(2).to_string(), "@2");
+    }
+
+    #[test]
+    fn matches_names_that_differ_in_whitespace_or_case() {
+        let first = names(&["id", "Name"]);
+        let second = names(&[" id ", "name"]);
+        assert_eq!(match_names(&first, &second, false), ["id", "name"]);
+        assert_eq!(match_names(&first, &second, true), ["id", "Name"]);
+    }
+
+    #[test]
+    fn strips_prefixes_and_suffixes_unless_the_name_is_taken() {
+        let header = names(&["src_id", "src_amount_v2", "amount", "src_"]);
+        assert_eq!(
+            strip_affixes(&header, Some("src_"), Some("_v2")),
+            ["id", "src_amount_v2", "amount", "src_"]
+        );
+    }
+
+    #[test]
+    fn matches_aliases_with_the_first_file() {
+        let aliases = Aliases {
+            groups: vec![names(&["customer_id", "cust_id", "client_id"])],
+        };
+        let first = names(&["customer_id", "amount"]);
+        let second = names(&["cust_id", "amount"]);
+        assert_eq!(
+            aliases.match_names(&first, &second),
+            ["customer_id", "amount"]
+        );
+        let second = names(&["customer_id", "cust_id"]);
+        assert_eq!(
+            aliases.match_names(&first, &second),
+            ["customer_id", "cust_id"]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::compare::{as_text, get_text};
+use anyhow::{Context, Result};
+use polars::prelude::{col, DataFrame, DataType, Expr, LazyFrame};
+
+/// Rows of each file used to guess which columns are different
+const SAMPLE_ROWS: u32 = 100_000;
+
+/// Sorts the columns so the ones more likely to be different are compared first. The nulls, distinct values,
+/// minimum and maximum of every column are calculated in the first rows of each file, and the columns with
+/// more of them different come first (the rest keep their order)
+pub(crate) fn likely_different_first(
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+    columns: &[String],
+) -> Result<Vec<String>> {
+    let first_statistics = statistics(first_lazy_frame, columns)?;
+    let second_statistics = statistics(second_lazy_frame, columns)?;
+    let mut scored_columns: Vec<(usize, &String)> = columns
+        .iter()
+        .map(|column| {
+            let different_statistics = STATISTICS
+                .iter()
+                .filter(|statistic| {
+                    let name = statistic_name(column, statistic);
+                    statistic_value(&first_statistics, &name)
+                        != statistic_value(&second_statistics, &name)
+                })
+                .count();
+            (different_statistics, column)
+        })
+        .collect();
+    scored_columns.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(scored_columns
+        .into_iter()
+        .map(|(_, column)| column.to_string())
+        .collect())
+}
+
+const STATISTICS: [&str; 4] = ["nulls", "distinct", "min", "max"];
+
+fn statistic_name(column: &str, statistic: &str) -> String {
+    format!("{column}\u{0}{statistic}")
+}
+
+fn statistics(lazy_frame: &LazyFrame, columns: &[String]) -> Result<DataFrame> {
+    let expressions: Vec<Expr> = columns
+        .iter()
+        .flat_map(|column| {
+            let text = col(column).cast(DataType::Utf8);
+            [
+                col(column)
+                    .null_count()
+                    .alias(&statistic_name(column, "nulls")),
+                col(column)
+                    .n_unique()
+                    .alias(&statistic_name(column, "distinct")),
+                text.clone().min().alias(&statistic_name(column, "min")),
+                text.max().alias(&statistic_name(column, "max")),
+            ]
+        })
+        .collect();
+    lazy_frame
+        .clone()
+        .limit(SAMPLE_ROWS)
+        .select(expressions)
+        .collect()
+        .context("Couldn't calculate the statistics of the columns")
+}
+
+fn statistic_value(statistics: &DataFrame, name: &str) -> Option<String> {
+    statistics
+        .column(name)
+        .ok()
+        .and_then(|series| get_text(&as_text(series), 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    #[test]
+    fn sorts_the_columns_by_their_different_statistics() {
+        let first = df!(
+            "id" => ["1", "2", "3"],
+            "a" => ["x", "y", "z"],
+            "b" => ["1", "2", "3"],
+            "c" => [Some("p"), Some("q"), Some("r")]
+        )
+        .unwrap()
+        .lazy();
+        let second = df!(
+            "id" => ["1", "2", "3"],
+            "a" => ["x", "y", "z"],
+            "b" => ["1", "2", "4"],
+            "c" => [None, Some("q"), Some("q")]
+        )
+        .unwrap()
+        .lazy();
+        let columns = ["id", "a", "b", "c"].map(String::from);
+        assert_eq!(
+            likely_different_first(&first, &second, &columns).unwrap(),
+            ["c", "b", "id", "a"]
+        );
+    }
+}
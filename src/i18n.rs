@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Language of the messages of the comparison
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Lang {
+    En,
+    Es,
+}
+
+static LANG: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+const SPANISH_CATALOG: &str = include_str!("../locales/es.toml");
+
+/// Globally sets the language of the messages
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+/// Language of the locale of the user (LC_ALL, LC_MESSAGES or LANG, e.g. es_ES.UTF-8), English by default
+pub fn lang_from_locale() -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    if locale.starts_with("es") {
+        Lang::Es
+    } else {
+        Lang::En
+    }
+}
+
+fn lang() -> Lang {
+    if LANG.load(Ordering::Relaxed) == Lang::Es as u8 {
+        Lang::Es
+    } else {
+        Lang::En
+    }
+}
+
+fn spanish_catalog() -> &'static HashMap<String, String> {
+    static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+    CATALOG
+        .get_or_init(|| toml::from_str(SPANISH_CATALOG).expect("The Spanish catalog is valid TOML"))
+}
+
+/// The message in the language of the output. The English text is the key of the catalogs, and it's kept when
+/// the message has no translation
+pub fn tr(message: &'static str) -> &'static str {
+    match lang() {
+        Lang::En => message,
+        Lang::Es => spanish_catalog()
+            .get(message)
+            .map_or(message, String::as_str),
+    }
+}
+
+/// The message in the language of the output, with its `{}` filled in order with the values
+pub fn tr_format(message: &'static str, values: &[&dyn Display]) -> String {
+    let mut parts = tr(message).split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    let mut values = values.iter();
+    for part in parts {
+        if let Some(value) = values.next() {
+            text.push_str(&value.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_the_placeholders_in_order() {
+        assert_eq!(
+            tr_format("File {} {}", &[&"a.csv", &"IS IDENTICAL"]),
+            "File a.csv IS IDENTICAL"
+        );
+        assert_eq!(tr_format("File {} {}", &[&"a.csv"]), "File a.csv ");
+    }
+
+    #[test]
+    fn translations_keep_the_placeholders() {
+        let catalog = spanish_catalog();
+        assert!(!catalog.is_empty());
+        for (message, translation) in catalog {
+            assert!(!translation.is_empty(), "{message} has no translation");
+            assert_eq!(
+                message.matches("{}").count(),
+                translation.matches("{}").count(),
+                "{message}"
+            );
+        }
+    }
+}
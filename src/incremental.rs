@@ -0,0 +1,199 @@
+use crate::compare::{as_text, get_text};
+use crate::Args;
+use anyhow::{Context, Result};
+use polars::prelude::{col, lit, IdxSize, LazyFrame};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Where the comparison of append-only files starts: after a number of rows or after a key
+/// (for files whose keys only grow). Saved in the checkpoint file after every identical comparison
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Rows already compared at the start of both files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Greatest key already compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.offset, &self.key) {
+            (Some(offset), Some(key)) => write!(f, "row {offset} and key {key}"),
+            (Some(offset), None) => write!(f, "row {offset}"),
+            (None, Some(key)) => write!(f, "key {key}"),
+            (None, None) => write!(f, "start of the files"),
+        }
+    }
+}
+
+impl Checkpoint {
+    /// Starting point given with --since-offset or --since-key or, if none is given, the one saved in
+    /// the checkpoint file (the start of the files when there isn't any)
+    pub fn from_args(args: &Args) -> Result<Self> {
+        if args.since_offset.is_some() || args.since_key.is_some() {
+            return Ok(Checkpoint {
+                offset: args.since_offset,
+                key: args.since_key.clone(),
+            });
+        }
+        match &args.checkpoint {
+            Some(checkpoint_path) if Path::new(checkpoint_path).exists() => {
+                let content = std::fs::read_to_string(checkpoint_path)
+                    .with_context(|| format!("Couldn't read checkpoint {checkpoint_path}"))?;
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Invalid checkpoint {checkpoint_path}"))
+            }
+            _ => Ok(Checkpoint::default()),
+        }
+    }
+
+    pub fn is_start(&self) -> bool {
+        self.offset.unwrap_or(0) == 0 && self.key.is_none()
+    }
+
+    /// Leaves out the rows already compared
+    pub(crate) fn skip_compared(&self, lazy_frame: LazyFrame, key: &str) -> Result<LazyFrame> {
+        let mut lazy_frame = lazy_frame;
+        if let Some(offset) = self.offset.filter(|offset| *offset > 0) {
+            lazy_frame = lazy_frame.slice(offset as i64, IdxSize::MAX);
+        }
+        if let Some(since_key) = &self.key {
+            let schema = lazy_frame
+                .schema()
+                .with_context(|| format!("Couldn't read the type of column {key}"))?;
+            let key_type = schema
+                .get(key)
+                .with_context(|| format!("Column {key} not found"))?
+                .clone();
+            lazy_frame = lazy_frame.filter(col(key).gt(lit(since_key.as_str()).cast(key_type)));
+        }
+        Ok(lazy_frame)
+    }
+
+    /// Checkpoint after comparing the given rows (the rows of the first file after this checkpoint,
+    /// before any exclusion)
+    pub(crate) fn next(&self, compared: &LazyFrame, key: &str) -> Result<Self> {
+        let compared_rows = compared
+            .clone()
+            .select([col(key)])
+            .collect()
+            .context("Couldn't count the rows compared")?;
+        if self.key.is_none() {
+            return Ok(Checkpoint {
+                offset: Some(self.offset.unwrap_or(0) + compared_rows.height() as u64),
+                key: None,
+            });
+        }
+        let greatest_key = compared
+            .clone()
+            .select([col(key).max()])
+            .collect()
+            .with_context(|| format!("Couldn't find the greatest key of column {key}"))?;
+        let greatest_key = get_text(&as_text(greatest_key.column(key)?), 0);
+        Ok(Checkpoint {
+            offset: self.offset,
+            key: greatest_key.or_else(|| self.key.clone()),
+        })
+    }
+
+    /// Saves the checkpoint, replacing the previous one atomically
+    pub fn save(&self, checkpoint_path: &str) -> Result<()> {
+        let temporary_path = Path::new(checkpoint_path).with_extension("json.tmp");
+        std::fs::write(
+            &temporary_path,
+            serde_json::to_string_pretty(self).expect("Checkpoint is always serializable"),
+        )
+        .with_context(|| format!("Couldn't write checkpoint {checkpoint_path}"))?;
+        std::fs::rename(&temporary_path, checkpoint_path)
+            .with_context(|| format!("Couldn't write checkpoint {checkpoint_path}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    fn args(options: &[&str]) -> Args {
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        Args::from_options("a.csv", "b.csv", &options).unwrap()
+    }
+
+    fn ids(lazy_frame: LazyFrame) -> Vec<i32> {
+        let data_frame = lazy_frame.collect().unwrap();
+        data_frame
+            .column("id")
+            .unwrap()
+            .i32()
+            .unwrap()
+            .into_no_null_iter()
+            .collect()
+    }
+
+    #[test]
+    fn skips_the_rows_compared() {
+        let lazy_frame = df!("id" => [1, 2, 5, 7]).unwrap().lazy();
+        let offset = Checkpoint {
+            offset: Some(1),
+            key: None,
+        };
+        assert_eq!(
+            ids(offset.skip_compared(lazy_frame.clone(), "id").unwrap()),
+            [2, 5, 7]
+        );
+        let key = Checkpoint {
+            offset: None,
+            key: Some("2".to_string()),
+        };
+        assert_eq!(ids(key.skip_compared(lazy_frame, "id").unwrap()), [5, 7]);
+    }
+
+    #[test]
+    fn moves_forward_after_the_rows_compared() {
+        let compared = df!("id" => [3, 9, 4]).unwrap().lazy();
+        let offset = Checkpoint {
+            offset: Some(10),
+            key: None,
+        };
+        assert_eq!(offset.next(&compared, "id").unwrap().offset, Some(13));
+        let key = Checkpoint {
+            offset: None,
+            key: Some("2".to_string()),
+        };
+        assert_eq!(key.next(&compared, "id").unwrap().key.as_deref(), Some("9"));
+        let nothing_compared = df!("id" => Vec::<i32>::new()).unwrap().lazy();
+        assert_eq!(key.next(&nothing_compared, "id").unwrap(), key);
+    }
+
+    #[test]
+    fn saves_and_reads_the_checkpoint() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let path = directory
+            .path()
+            .join("checkpoint.json")
+            .display()
+            .to_string();
+        assert!(Checkpoint::from_args(&args(&["--checkpoint", &path]))
+            .unwrap()
+            .is_start());
+
+        let checkpoint = Checkpoint {
+            offset: Some(100),
+            key: None,
+        };
+        checkpoint.save(&path).unwrap();
+        assert_eq!(
+            Checkpoint::from_args(&args(&["--checkpoint", &path])).unwrap(),
+            checkpoint
+        );
+        assert_eq!(checkpoint.to_string(), "row 100");
+
+        let given =
+            Checkpoint::from_args(&args(&["--checkpoint", &path, "--since-key", "K"])).unwrap();
+        assert_eq!((given.offset, given.key.as_deref()), (None, Some("K")));
+    }
+}
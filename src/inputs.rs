@@ -0,0 +1,506 @@
+use crate::archive::{self, ArchiveFormat};
+use crate::decrypt::{self, Encryption};
+use crate::fixed_width;
+use crate::source::DataSource;
+use crate::Args;
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+use tracing::debug;
+
+/// A file to compare on the local disk: the file given by the user, a temporary copy of a remote file,
+/// or the decrypted content of an encrypted file in memory
+pub struct LocalFile {
+    path: String,
+    temporary_file: Option<NamedTempFile>,
+    _memory_file: Option<std::fs::File>,
+}
+
+impl LocalFile {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Size of the temporary copy of the file (0 for local files)
+    pub fn temporary_bytes(&self) -> u64 {
+        self.temporary_file
+            .as_ref()
+            .and_then(|temporary_file| temporary_file.as_file().metadata().ok())
+            .map_or(0, |metadata| metadata.len())
+    }
+
+    pub(crate) fn temporary(temporary_file: NamedTempFile) -> Self {
+        LocalFile {
+            path: temporary_file.path().display().to_string(),
+            temporary_file: Some(temporary_file),
+            _memory_file: None,
+        }
+    }
+
+    /// File only in memory, read through its descriptor in /proc/self/fd
+    #[cfg(target_os = "linux")]
+    pub(crate) fn in_memory(memory_file: std::fs::File) -> Self {
+        use std::os::fd::AsRawFd;
+        LocalFile {
+            path: format!("/proc/self/fd/{}", memory_file.as_raw_fd()),
+            temporary_file: None,
+            _memory_file: Some(memory_file),
+        }
+    }
+}
+
+/// Opens the inputs that can be read lazily without converting them to CSV: Parquet files and directories
+/// (partitioned datasets and Delta tables) and SQLite tables (sqlite://checkpoints.db?table=orders). The rest
+/// of inputs are read as CSV files, made available with `fetch`
+pub fn open_source(input: &str, args: &Args) -> Result<Option<Box<dyn DataSource>>> {
+    if is_glob(input) || input.starts_with("fixed://") {
+        return Ok(None);
+    }
+    if Path::new(input).is_dir() || input.to_ascii_lowercase().ends_with(".parquet") {
+        return open_parquet(input, args);
+    }
+    if let Some(location) = input.strip_prefix("sqlite://") {
+        let (database_path, parameters) = split_parameters(location);
+        return open_sqlite(
+            (input, database_path),
+            parameter(&parameters, "table"),
+            parameter(&parameters, "query"),
+            args,
+        );
+    }
+    Ok(None)
+}
+
+#[cfg(feature = "parquet")]
+fn open_parquet(input: &str, args: &Args) -> Result<Option<Box<dyn DataSource>>> {
+    use crate::source::ParquetSource;
+
+    if Path::new(input).is_dir() {
+        return Ok(Some(Box::new(ParquetSource::new(input))));
+    }
+    let file = fetch_archive_member(input, args)?;
+    Ok(Some(Box::new(ParquetSource::fetched(input, file))))
+}
+
+/// Without Parquet support, `fetch` explains why the input can't be read
+#[cfg(not(feature = "parquet"))]
+fn open_parquet(_input: &str, _args: &Args) -> Result<Option<Box<dyn DataSource>>> {
+    Ok(None)
+}
+
+#[cfg(feature = "sqlite")]
+fn open_sqlite(
+    (input, database_path): (&str, &str),
+    table: Option<&str>,
+    query: Option<&str>,
+    args: &Args,
+) -> Result<Option<Box<dyn DataSource>>> {
+    use crate::source::DbSource;
+
+    let database = fetch_archive_member(database_path, args)?;
+    Ok(Some(Box::new(DbSource::fetched(
+        input, database, table, query,
+    ))))
+}
+
+/// Without SQLite support, `fetch` explains why the input can't be read
+#[cfg(not(feature = "sqlite"))]
+fn open_sqlite(
+    _input: (&str, &str),
+    _table: Option<&str>,
+    _query: Option<&str>,
+    _args: &Args,
+) -> Result<Option<Box<dyn DataSource>>> {
+    Ok(None)
+}
+
+/// Makes the input available in the local disk. Glob patterns (exports/2024-*/orders_*.csv) are
+/// concatenated into one temporary file. Remote files (http(s)://..., s3://bucket/key,
+/// gs://bucket/key) are downloaded to a temporary file, and so are the members of archives
+/// (archive.zip!data/extract.csv). Encrypted files (.age, .gpg) are decrypted in memory, and
+/// fixed-width files (fixed://extract.dat?spec=layout.csv), Avro files (.avro), Parquet files and
+/// directories (partitioned datasets and Delta tables) and SQLite tables (sqlite://checkpoints.db?table=orders)
+/// are converted to CSV. The comparison reads the last ones lazily with `open_source` instead
+pub fn fetch(input: &str, args: &Args) -> Result<LocalFile> {
+    if is_glob(input) {
+        return fetch_glob(input, args);
+    }
+    fetch_literal(input, args)
+}
+
+/// Same as `fetch`, taking the input as a path even if it has glob metacharacters (`[`, `*`, `?`), like the
+/// files matched by a pattern
+fn fetch_literal(input: &str, args: &Args) -> Result<LocalFile> {
+    if let Some(location) = input.strip_prefix("fixed://") {
+        let (file_path, parameters) = split_parameters(location);
+        let Some(spec_path) = parameter(&parameters, "spec") else {
+            bail!("Missing the columns of {input}: fixed://file?spec=columns.csv");
+        };
+        let columns = fixed_width::read_spec(spec_path)?;
+        let file = fetch_archive_member(file_path, args)?;
+        return fixed_width::convert(file.path(), &columns, args.separator)
+            .map(LocalFile::temporary);
+    }
+    if Path::new(input).is_dir() {
+        return convert_parquet(input, args);
+    }
+    if input.to_ascii_lowercase().ends_with(".parquet") {
+        let file = fetch_archive_member(input, args)?;
+        return convert_parquet(file.path(), args);
+    }
+    if input.to_ascii_lowercase().ends_with(".avro") {
+        let file = fetch_archive_member(input, args)?;
+        return convert_avro(file.path(), args);
+    }
+    if let Some(location) = input.strip_prefix("sqlite://") {
+        let (database_path, parameters) = split_parameters(location);
+        return fetch_sqlite(
+            database_path,
+            parameter(&parameters, "table"),
+            parameter(&parameters, "query"),
+            args,
+        );
+    }
+    fetch_archive_member(input, args)
+}
+
+#[cfg(feature = "parquet")]
+fn convert_parquet(path: &str, args: &Args) -> Result<LocalFile> {
+    crate::parquet::convert(path, args.separator).map(LocalFile::temporary)
+}
+
+#[cfg(not(feature = "parquet"))]
+fn convert_parquet(path: &str, _args: &Args) -> Result<LocalFile> {
+    bail!("Can't read {path}: Parquet support is not included in this build (feature parquet)")
+}
+
+#[cfg(feature = "avro")]
+fn convert_avro(file_path: &str, args: &Args) -> Result<LocalFile> {
+    crate::avro::convert(file_path, args.separator).map(LocalFile::temporary)
+}
+
+#[cfg(not(feature = "avro"))]
+fn convert_avro(file_path: &str, _args: &Args) -> Result<LocalFile> {
+    bail!("Can't read {file_path}: Avro support is not included in this build (feature avro)")
+}
+
+#[cfg(feature = "sqlite")]
+fn fetch_sqlite(
+    database_path: &str,
+    table: Option<&str>,
+    query: Option<&str>,
+    args: &Args,
+) -> Result<LocalFile> {
+    let database = fetch_archive_member(database_path, args)?;
+    crate::sqlite::export(database.path(), table, query, args.separator).map(LocalFile::temporary)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn fetch_sqlite(
+    database_path: &str,
+    _table: Option<&str>,
+    _query: Option<&str>,
+    _args: &Args,
+) -> Result<LocalFile> {
+    bail!(
+        "Can't read {database_path}: SQLite support is not included in this build (feature sqlite)"
+    )
+}
+
+fn fetch_archive_member(input: &str, args: &Args) -> Result<LocalFile> {
+    if let Some((archive_path, format, member)) = archive_member(input, args) {
+        let archive_file = fetch_file(archive_path, args)?;
+        return archive::extract(archive_file.path(), format, member).map(LocalFile::temporary);
+    }
+    fetch_file(input, args)
+}
+
+/// Local paths with wildcards, e.g. exports/2024-*/orders_*.csv. Existing files are read as they are, even if their
+/// names have those characters (report [final].csv)
+fn is_glob(input: &str) -> bool {
+    !input.contains("://") && input.contains(['*', '?', '[']) && !Path::new(input).exists()
+}
+
+/// Concatenates the files matching the pattern (in alphabetical order) into a temporary file.
+/// All of them must have the same header, which is written only once
+fn fetch_glob(pattern: &str, args: &Args) -> Result<LocalFile> {
+    let paths = glob::glob(pattern)
+        .with_context(|| format!("Invalid pattern {pattern}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Couldn't list the files matching {pattern}"))?;
+    if paths.is_empty() {
+        bail!("No files match {pattern}");
+    }
+    debug!("{} file(s) match {}", paths.len(), pattern);
+
+    let mut output = temporary_file()?;
+    let mut first_header: Option<(String, String)> = None;
+    for path in paths {
+        let path = path.display().to_string();
+        let input = fetch_literal(&path, args)?;
+        let mut reader = BufReader::new(
+            File::open(input.path()).with_context(|| format!("Couldn't open {path}"))?,
+        );
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .with_context(|| format!("Couldn't read {path}"))?;
+        let header = header.trim_end_matches(['\r', '\n']).to_string();
+        match &first_header {
+            Some((first_path, first)) if *first != header => {
+                bail!("The header of {path} is different from the header of {first_path}")
+            }
+            Some(_) => {}
+            None => {
+                writeln!(output, "{header}")?;
+                first_header = Some((path.clone(), header));
+            }
+        }
+        let mut rows = vec![];
+        reader
+            .read_to_end(&mut rows)
+            .with_context(|| format!("Couldn't read {path}"))?;
+        output.write_all(&rows)?;
+        if !rows.is_empty() && !rows.ends_with(b"\n") {
+            writeln!(output)?;
+        }
+    }
+    output.flush()?;
+    Ok(LocalFile::temporary(output))
+}
+
+/// Splits `path?name=value&...` in the path and its parameters. Values can be percent-encoded
+fn split_parameters(location: &str) -> (&str, Vec<(String, String)>) {
+    let Some((path, query)) = location.split_once('?') else {
+        return (location, vec![]);
+    };
+    let parameters = query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            (
+                percent_decode(&name.replace('+', " ")),
+                percent_decode(&value.replace('+', " ")),
+            )
+        })
+        .collect();
+    (path, parameters)
+}
+
+fn parameter<'a>(parameters: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    parameters
+        .iter()
+        .find(|(parameter_name, _)| parameter_name == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Decodes the %XX escapes of the text
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[index + 1..index + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Archive, its format and the member to extract (from archive!member or --archive-member)
+fn archive_member<'a>(
+    input: &'a str,
+    args: &'a Args,
+) -> Option<(&'a str, ArchiveFormat, Option<&'a str>)> {
+    if let Some((archive_path, member)) = input.split_once('!') {
+        if let Some(format) = ArchiveFormat::of(archive_path) {
+            return Some((archive_path, format, Some(member)));
+        }
+    }
+    ArchiveFormat::of(input).map(|format| (input, format, args.archive_member.as_deref()))
+}
+
+fn fetch_file(input: &str, args: &Args) -> Result<LocalFile> {
+    let file = download(input, args)?;
+    match Encryption::of(input) {
+        Some(encryption) => decrypt::decrypt(file.path(), encryption, args),
+        None => Ok(file),
+    }
+}
+
+fn download(input: &str, args: &Args) -> Result<LocalFile> {
+    if input.starts_with("http://") || input.starts_with("https://") {
+        return fetch_http(input, args);
+    }
+    if let Some(location) = input.strip_prefix("s3://") {
+        return fetch_s3(location, args);
+    }
+    if let Some(location) = input.strip_prefix("gs://") {
+        return fetch_gcs(location, args);
+    }
+    Ok(LocalFile {
+        path: input.to_string(),
+        temporary_file: None,
+        _memory_file: None,
+    })
+}
+
+#[cfg(feature = "http")]
+fn fetch_http(url: &str, args: &Args) -> Result<LocalFile> {
+    let mut agent = ureq::AgentBuilder::new();
+    if let Some(proxy) = proxy_for(url, args.proxy.as_deref()) {
+        debug!("Downloading {} through the proxy {}", url, proxy);
+        agent = agent
+            .proxy(ureq::Proxy::new(&proxy).with_context(|| format!("Invalid proxy {proxy}"))?);
+    }
+    let response = agent
+        .build()
+        .get(url)
+        .call()
+        .with_context(|| format!("Couldn't download {url}"))?;
+    let mut file = temporary_file()?;
+    std::io::copy(&mut response.into_reader(), &mut file)
+        .with_context(|| format!("Couldn't download {url}"))?;
+    Ok(LocalFile::temporary(file))
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch_http(url: &str, _args: &Args) -> Result<LocalFile> {
+    bail!("Can't download {url}: HTTP support is not included in this build (feature http)")
+}
+
+/// Proxy to download the url: the one given with --proxy or the one in HTTPS_PROXY/HTTP_PROXY,
+/// unless the host is in NO_PROXY
+#[cfg(feature = "http")]
+fn proxy_for(url: &str, proxy: Option<&str>) -> Option<String> {
+    if is_no_proxy_host(host(url)) {
+        return None;
+    }
+    if let Some(proxy) = proxy {
+        return Some(proxy.to_string());
+    }
+    let variables = if url.starts_with("https://") {
+        ["HTTPS_PROXY", "https_proxy"]
+    } else {
+        ["HTTP_PROXY", "http_proxy"]
+    };
+    variables
+        .iter()
+        .find_map(|variable| std::env::var(variable).ok())
+        .filter(|proxy| !proxy.is_empty())
+}
+
+#[cfg(feature = "http")]
+fn host(url: &str) -> &str {
+    let authority = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    match host.strip_prefix('[') {
+        // IPv6 address, e.g. [::1]:8080
+        Some(address) => address.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// NO_PROXY is a comma separated list of hosts or domains (`example.com` also matches
+/// `files.example.com`), or `*` for all of them
+#[cfg(feature = "http")]
+fn is_no_proxy_host(host: &str) -> bool {
+    let Some(no_proxy) = std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .ok()
+    else {
+        return false;
+    };
+    no_proxy
+        .split(',')
+        .map(|domain| domain.trim().trim_start_matches('.'))
+        .filter(|domain| !domain.is_empty())
+        .any(|domain| {
+            domain == "*"
+                || host.eq_ignore_ascii_case(domain)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+        })
+}
+
+pub(crate) fn temporary_file() -> Result<NamedTempFile> {
+    tempfile::Builder::new()
+        .prefix("csv-compare-")
+        .suffix(".csv")
+        .tempfile()
+        .context("Couldn't create temporary file")
+}
+
+#[cfg(feature = "cloud")]
+fn fetch_s3(location: &str, args: &Args) -> Result<LocalFile> {
+    crate::cloud::download_s3(location, args).map(LocalFile::temporary)
+}
+
+#[cfg(not(feature = "cloud"))]
+fn fetch_s3(location: &str, _args: &Args) -> Result<LocalFile> {
+    anyhow::bail!(
+        "Can't read s3://{location}: cloud support is not included in this build (feature cloud)"
+    )
+}
+
+#[cfg(feature = "cloud")]
+fn fetch_gcs(location: &str, args: &Args) -> Result<LocalFile> {
+    crate::cloud::download_gcs(location, args).map(LocalFile::temporary)
+}
+
+#[cfg(not(feature = "cloud"))]
+fn fetch_gcs(location: &str, _args: &Args) -> Result<LocalFile> {
+    anyhow::bail!(
+        "Can't read gs://{location}: cloud support is not included in this build (feature cloud)"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_are_patterns_unless_the_file_exists() {
+        let directory = tempfile::tempdir().unwrap();
+        let literal = directory.path().join("report [final].csv");
+        std::fs::write(&literal, "id\n1\n").unwrap();
+        assert!(!is_glob(literal.to_str().unwrap()));
+        let pattern = directory.path().join("report [fi]*.csv");
+        assert!(is_glob(pattern.to_str().unwrap()));
+        assert!(!is_glob("https://example.com/orders_*.csv"));
+    }
+
+    #[test]
+    fn fetches_files_with_wildcard_characters_in_their_names() {
+        let directory = tempfile::tempdir().unwrap();
+        let literal = directory.path().join("report [final].csv");
+        std::fs::write(&literal, "id\n1\n").unwrap();
+        let args = Args::from_options("a.csv", "b.csv", &[]).unwrap();
+        let input = fetch(literal.to_str().unwrap(), &args).unwrap();
+        assert_eq!(std::fs::read_to_string(input.path()).unwrap(), "id\n1\n");
+    }
+}
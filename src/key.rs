@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use polars::prelude::{col, DataType, LazyFrame};
+
+/// Keys of the first file checked to warn about numeric keys sorted as text
+const SAMPLE_KEYS: u32 = 10_000;
+
+/// How the values of the key are sorted
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// As text ("10" before "9"), or as inferred with --typed
+    Text,
+    /// As integers. The comparison fails if a key isn't an integer
+    Int,
+    /// As decimal numbers. The comparison fails if a key isn't a number
+    Float,
+}
+
+impl KeyType {
+    fn data_type(&self) -> Option<DataType> {
+        match self {
+            KeyType::Text => None,
+            KeyType::Int => Some(DataType::Int64),
+            KeyType::Float => Some(DataType::Float64),
+        }
+    }
+}
+
+/// Casts the key to the type, so the rows are sorted by its numeric value
+pub(crate) fn cast_key(lazy_frame: LazyFrame, key: &str, key_type: KeyType) -> LazyFrame {
+    match key_type.data_type() {
+        Some(data_type) => lazy_frame.with_column(col(key).strict_cast(data_type)),
+        None => lazy_frame,
+    }
+}
+
+/// Whether the first keys of the file are integers read as text with different lengths, which are sorted
+/// differently as text and as numbers ("10" before "9"), so the same number written differently in each
+/// file ("9" and "09") isn't aligned
+pub(crate) fn is_numeric_text(lazy_frame: &LazyFrame, key: &str) -> Result<bool> {
+    let keys = lazy_frame
+        .clone()
+        .select([col(key)])
+        .limit(SAMPLE_KEYS)
+        .drop_nulls(None)
+        .collect()
+        .with_context(|| format!("Couldn't read the keys of column {key}"))?;
+    let Ok(keys) = keys.column(key)?.utf8() else {
+        return Ok(false);
+    };
+    let mut lengths = keys.into_iter().flatten().map(|key| {
+        key.trim()
+            .parse::<i64>()
+            .map(|_| key.trim().trim_start_matches('-').len())
+    });
+    let Some(Ok(first_length)) = lengths.next() else {
+        return Ok(false);
+    };
+    let mut different_lengths = false;
+    for length in lengths {
+        match length {
+            Ok(length) => different_lengths |= length != first_length,
+            Err(_) => return Ok(false),
+        }
+    }
+    Ok(different_lengths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    fn is_numeric(keys: &[&str]) -> bool {
+        is_numeric_text(&df!("id" => keys).unwrap().lazy(), "id").unwrap()
+    }
+
+    #[test]
+    fn warns_about_integers_of_different_lengths() {
+        assert!(is_numeric(&["9", "10", "-11"]));
+        assert!(!is_numeric(&["09", "10", "11"]));
+        assert!(!is_numeric(&["9", "10", "A"]));
+        assert!(!is_numeric(&[]));
+        let typed = df!("id" => [9, 10]).unwrap().lazy();
+        assert!(!is_numeric_text(&typed, "id").unwrap());
+    }
+
+    #[test]
+    fn casts_the_key_to_sort_it_as_a_number() {
+        let lazy_frame = df!("id" => ["10", "9"]).unwrap().lazy();
+        let sorted = cast_key(lazy_frame.clone(), "id", KeyType::Int)
+            .sort("id", Default::default())
+            .collect()
+            .unwrap();
+        let ids: Vec<i64> = sorted
+            .column("id")
+            .unwrap()
+            .i64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(ids, [9, 10]);
+        let text = cast_key(lazy_frame.clone(), "id", KeyType::Text)
+            .collect()
+            .unwrap();
+        assert_eq!(text.column("id").unwrap().dtype(), &DataType::Utf8);
+        let invalid = df!("id" => ["A"]).unwrap().lazy();
+        assert!(cast_key(invalid, "id", KeyType::Float).collect().is_err());
+    }
+}
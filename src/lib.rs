@@ -0,0 +1,2207 @@
+mod annotate;
+mod archive;
+pub mod assertions;
+#[cfg(feature = "avro")]
+mod avro;
+pub mod baseline;
+pub mod bench;
+pub mod cancel;
+pub mod canonicalize;
+mod categorical;
+pub mod cli;
+#[cfg(feature = "cloud")]
+mod cloud;
+mod compare;
+pub mod config;
+mod decrypt;
+pub mod differences;
+pub mod dirs;
+mod encoding;
+mod errors;
+pub mod events;
+pub mod ffi;
+pub mod filters;
+pub mod fixed_width;
+pub mod git;
+pub mod hash;
+pub mod headers;
+mod heuristics;
+pub mod i18n;
+pub mod incremental;
+pub mod inputs;
+pub mod key;
+pub mod logging;
+pub mod merge;
+pub mod metrics;
+pub mod normalize;
+pub mod notify;
+pub mod options;
+#[cfg(feature = "otel")]
+mod otel;
+pub mod output;
+mod pager;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod partition;
+pub mod progress;
+pub mod ragged;
+mod raw;
+pub mod redact;
+pub mod report;
+mod resources;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod shard;
+pub mod source;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+mod table;
+#[cfg(feature = "test-helpers")]
+pub mod testing;
+pub mod validate;
+
+use anyhow::{Context, Result};
+use colored::*;
+use polars::frame::DataFrame;
+use polars::prelude::{
+    col, DataType, IndexOfSchema, IntoVec, LazyCsvReader, LazyFileListReader, LazyFrame,
+    SortOptions, StringCacheHolder,
+};
+use std::collections::HashSet;
+
+pub use cancel::{CancellationToken, ComparisonHandle};
+pub use cli::Args;
+pub use differences::{Comparison, Difference};
+use errors::SourceFile;
+pub use events::{ComparisonObserver, NoopObserver};
+use filters::RowCountTolerance;
+use i18n::{tr, tr_format};
+use incremental::Checkpoint;
+use inputs::LocalFile;
+pub use options::CompareOptions;
+use output::{Printer, Themed};
+use partition::KeyRange;
+use ragged::{PreparedFile, RaggedHandling};
+use report::{
+    BatchTiming, CardinalityDifference, ColumnDifference, ColumnOrderDifference,
+    ColumnTypeDifference, ComparisonReport, ComparisonResult, DriftSeverity, DuplicatedKey,
+    NullCountDifference, TopValuesDifference, ValueCount,
+};
+use source::{CsvSource, DataSource, FrameSource};
+use std::time::Instant;
+use tracing::{debug, info_span, trace};
+
+/// Rough memory needed per byte of the input files when all the columns are sorted and compared at once
+const MEMORY_PER_INPUT_BYTE: u64 = 3;
+
+/// Compares the two files with the given arguments, printing the progress and results with the printer
+pub fn compare_files(
+    args: &Args,
+    printer: &Printer,
+    first_file_path: &str,
+    second_file_path: &str,
+) -> ComparisonReport {
+    compare_files_with_cancellation(
+        args,
+        printer,
+        first_file_path,
+        second_file_path,
+        &CancellationToken::new(),
+    )
+}
+
+/// Same as `compare_files`, but stops before the next batch of columns when the token is cancelled
+pub fn compare_files_with_cancellation(
+    args: &Args,
+    printer: &Printer,
+    first_file_path: &str,
+    second_file_path: &str,
+    cancellation_token: &CancellationToken,
+) -> ComparisonReport {
+    compare_files_observed(
+        args,
+        printer,
+        first_file_path,
+        second_file_path,
+        cancellation_token,
+        &NoopObserver,
+    )
+}
+
+/// Same as `compare_files_with_cancellation`, notifying the observer of every step of the comparison
+pub fn compare_files_observed(
+    args: &Args,
+    printer: &Printer,
+    first_file_path: &str,
+    second_file_path: &str,
+    cancellation_token: &CancellationToken,
+    observer: &dyn ComparisonObserver,
+) -> ComparisonReport {
+    run_reported(printer, (first_file_path, second_file_path), |report| {
+        run_comparison(
+            report,
+            args,
+            printer,
+            first_file_path,
+            second_file_path,
+            cancellation_token,
+            observer,
+        )
+    })
+}
+
+/// Compares two polars lazy frames already in memory (`data_frame.lazy()` for a `DataFrame`), without
+/// writing them to files. The options about reading the files (ragged rows, duplicated headers...) don't apply
+pub fn compare_frames(
+    args: &Args,
+    printer: &Printer,
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+) -> ComparisonReport {
+    compare_frames_observed(
+        args,
+        printer,
+        first_lazy_frame,
+        second_lazy_frame,
+        &CancellationToken::new(),
+        &NoopObserver,
+    )
+}
+
+/// Same as `compare_frames`, stopping when the token is cancelled and notifying the observer of every step
+pub fn compare_frames_observed(
+    args: &Args,
+    printer: &Printer,
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+    cancellation_token: &CancellationToken,
+    observer: &dyn ComparisonObserver,
+) -> ComparisonReport {
+    compare_sources_observed(
+        args,
+        printer,
+        &FrameSource::new("first frame", first_lazy_frame),
+        &FrameSource::new("second frame", second_lazy_frame),
+        cancellation_token,
+        observer,
+    )
+}
+
+/// Compares two inputs of any format implementing `DataSource`. As with `compare_frames`, the options about
+/// reading the files don't apply
+pub fn compare_sources(
+    args: &Args,
+    printer: &Printer,
+    first_source: &dyn DataSource,
+    second_source: &dyn DataSource,
+) -> ComparisonReport {
+    compare_sources_observed(
+        args,
+        printer,
+        first_source,
+        second_source,
+        &CancellationToken::new(),
+        &NoopObserver,
+    )
+}
+
+/// Same as `compare_sources`, stopping when the token is cancelled and notifying the observer of every step
+pub fn compare_sources_observed(
+    args: &Args,
+    printer: &Printer,
+    first_source: &dyn DataSource,
+    second_source: &dyn DataSource,
+    cancellation_token: &CancellationToken,
+    observer: &dyn ComparisonObserver,
+) -> ComparisonReport {
+    let (first_name, second_name) = (first_source.name(), second_source.name());
+    run_reported(printer, (first_name, second_name), |report| {
+        let deadline = args.timeout.map(|timeout| Instant::now() + timeout);
+        compare_lazy_frames(
+            report,
+            args,
+            printer,
+            (
+                first_source.to_lazyframe()?,
+                &SourceFile::in_memory(first_name),
+                first_name,
+            ),
+            (
+                second_source.to_lazyframe()?,
+                &SourceFile::in_memory(second_name),
+                second_name,
+            ),
+            (cancellation_token, deadline),
+            observer,
+        )
+    })
+}
+
+/// Runs the comparison in its span, turning its errors into a failed report, with the resources used
+fn run_reported(
+    printer: &Printer,
+    (first_name, second_name): (&str, &str),
+    comparison: impl FnOnce(&mut ComparisonReport) -> Result<()>,
+) -> ComparisonReport {
+    let mut report = ComparisonReport::default();
+    let started = Instant::now();
+    let _span = info_span!("comparison", file1 = first_name, file2 = second_name).entered();
+    if let Err(error) = catch_panic(|| comparison(&mut report)) {
+        let message = format!("{:#}", error);
+        printer.result(format!("{}: {}", tr("ERROR").fail_status(), message));
+        printer.summary(format!("ERROR: {}", message));
+        report.error = Some(message);
+        report.result = ComparisonResult::Failed;
+    }
+    report.resources.elapsed = started.elapsed();
+    report.resources.peak_memory_bytes = resources::peak_memory_bytes();
+    report.resources.bytes_read = resources::bytes_read();
+    report
+}
+
+/// Runs the function turning its panics (e.g. inside polars) into errors, so a failed comparison still has a
+/// report and exits with the code of the failures instead of 101
+pub(crate) fn catch_panic<T>(function: impl FnOnce() -> Result<T>) -> Result<T> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(function)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_string());
+        Err(anyhow::anyhow!(
+            "The comparison failed unexpectedly: {}",
+            message
+        ))
+    })
+}
+
+fn run_comparison(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    first_file_path: &str,
+    second_file_path: &str,
+    cancellation_token: &CancellationToken,
+    observer: &dyn ComparisonObserver,
+) -> Result<()> {
+    let deadline = args.timeout.map(|timeout| Instant::now() + timeout);
+    printer.info(format!(
+        "Comparing file {} with file {}. {} column(s) at a time... {}",
+        first_file_path,
+        second_file_path,
+        if args.compares_all_columns() {
+            "All".to_string()
+        } else {
+            args.number_of_columns.to_string()
+        },
+        if args.strict_column_order {
+            tr(" Strict order of columns enforced").warn()
+        } else {
+            "".white()
+        }
+    ));
+
+    if !args.raw {
+        let (first_source, second_source) =
+            info_span!("open_sources").in_scope(|| -> Result<_> {
+                Ok((
+                    inputs::open_source(first_file_path, args)?,
+                    inputs::open_source(second_file_path, args)?,
+                ))
+            })?;
+        if first_source.is_some() || second_source.is_some() {
+            return compare_opened_sources(
+                report,
+                args,
+                printer,
+                (first_file_path, first_source),
+                (second_file_path, second_source),
+                (cancellation_token, deadline),
+                observer,
+            );
+        }
+    }
+
+    let separator = args.separator;
+    let (first_input, second_input) = info_span!("fetch_inputs").in_scope(|| -> Result<_> {
+        Ok((
+            inputs::fetch(first_file_path, args)?,
+            inputs::fetch(second_file_path, args)?,
+        ))
+    })?;
+    report.resources.input_bytes =
+        resources::file_size(first_input.path()) + resources::file_size(second_input.path());
+    observer.input_size(report.resources.input_bytes);
+    if args.raw {
+        return compare_raw_lines(
+            report,
+            args,
+            printer,
+            (first_file_path, first_input.path()),
+            (second_file_path, second_input.path()),
+        );
+    }
+    let Some(CsvFrames {
+        first_file,
+        second_file,
+        first_lazy_frame: first_file_lf,
+        second_lazy_frame: second_file_lf,
+    }) = read_csv_frames(
+        report,
+        args,
+        printer,
+        (first_file_path, &first_input),
+        (second_file_path, &second_input),
+    )?
+    else {
+        return Ok(());
+    };
+    let first_source = SourceFile::new(first_file_path, first_file.path(), separator);
+    let second_source = SourceFile::new(second_file_path, second_file.path(), separator);
+
+    if args.encoding_differences {
+        let (encoding_only_rows, encoding_differences) = info_span!("encoding_differences")
+            .in_scope(|| {
+                encoding::find_encoding_differences(
+                    first_file.path(),
+                    second_file.path(),
+                    separator,
+                    args.samples,
+                )
+            })?;
+        report.encoding_only_rows = encoding_only_rows;
+        report.encoding_differences = encoding_differences;
+        if encoding_only_rows > 0 {
+            printer.info(format!(
+                "{}: {} row(s) have the same values encoded differently (quoted or unquoted, escaped quotes), \
+                 they're equal for the comparison\n{}",
+                tr("Warning").warn_status(),
+                encoding_only_rows,
+                report.encoding_table()
+            ));
+        } else {
+            printer.info("No row has the same values encoded differently".pass());
+        }
+    }
+
+    compare_lazy_frames(
+        report,
+        args,
+        printer,
+        (first_file_lf, &first_source, first_file_path),
+        (second_file_lf, &second_source, second_file_path),
+        (cancellation_token, deadline),
+        observer,
+    )
+}
+
+/// CSV files of both inputs with their ragged rows handled, read as lazy frames with their duplicated headers
+/// renamed
+struct CsvFrames {
+    first_file: PreparedFile,
+    second_file: PreparedFile,
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+}
+
+/// Reads the CSV files of both inputs. None when they can't be compared (invalid or empty files), with the result
+/// already in the report
+fn read_csv_frames(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_path, first_input): (&str, &LocalFile),
+    (second_file_path, second_input): (&str, &LocalFile),
+) -> Result<Option<CsvFrames>> {
+    let separator = args.separator;
+    let prepared_files = info_span!("prepare_files").in_scope(|| {
+        ragged::prepare_file(first_input.path(), separator, args.on_ragged).and_then(|first_file| {
+            ragged::prepare_file(second_input.path(), separator, args.on_ragged)
+                .map(|second_file| (first_file, second_file))
+        })
+    });
+    let (first_file, second_file) = match prepared_files {
+        Ok(prepared_files) => prepared_files,
+        Err(message) => {
+            printer.result(message.fail_status());
+            printer.summary(format!("INVALID FILE: {}", message));
+            report.result = ComparisonResult::InvalidFile;
+            return Ok(None);
+        }
+    };
+    report.resources.temporary_disk_bytes = first_input.temporary_bytes()
+        + second_input.temporary_bytes()
+        + first_file.temporary_bytes()
+        + second_file.temporary_bytes();
+    report.ragged_rows_first = first_file.ragged_rows;
+    report.ragged_rows_second = second_file.ragged_rows;
+    if report.ragged_rows_first > 0 || report.ragged_rows_second > 0 {
+        let message = format!(
+            "Rows with a wrong number of fields: {} in {}, {} in {}",
+            report.ragged_rows_first, first_file_path, report.ragged_rows_second, second_file_path
+        );
+        if args.on_ragged == RaggedHandling::Error {
+            printer.result(format!("{}: {}", tr("INVALID FILE").fail_status(), message));
+            printer.summary(format!("INVALID FILE: {}", message));
+            report.result = ComparisonResult::InvalidFile;
+            return Ok(None);
+        }
+        printer.info(format!("{}: {}", tr("Warning").warn_status(), message));
+    }
+
+    let first_is_empty = is_empty_file(first_file.path());
+    let second_is_empty = is_empty_file(second_file.path());
+    if first_is_empty || second_is_empty {
+        if first_is_empty && second_is_empty {
+            printer.result(tr_format(
+                "Files {} and {} {}",
+                &[
+                    &first_file_path.bold(),
+                    &second_file_path.bold(),
+                    &tr("ARE BOTH EMPTY").pass_status(),
+                ],
+            ));
+            printer.summary("EMPTY: both files have no rows");
+            report.result = ComparisonResult::Empty;
+            return Ok(None);
+        }
+        let (empty_file, other_file) = if first_is_empty {
+            (first_file_path, second_file_path)
+        } else {
+            (second_file_path, first_file_path)
+        };
+        printer.result(format!(
+            "{}: {} {} {} {}",
+            tr("FILES ARE DIFFERENT").fail_status(),
+            tr("File").fail(),
+            empty_file.bold(),
+            tr("is empty, but").fail(),
+            other_file.bold()
+        ));
+        printer.summary(format!("DIFFERENT COLUMNS: {} is empty", empty_file));
+        report.result = ComparisonResult::DifferentColumns;
+        return Ok(None);
+    }
+
+    let first_source = SourceFile::new(first_file_path, first_file.path(), separator);
+    let second_source = SourceFile::new(second_file_path, second_file.path(), separator);
+    let mut first_file_lf = CsvSource::new(first_file.path(), args).to_lazyframe()?;
+    let mut second_file_lf = CsvSource::new(second_file.path(), args).to_lazyframe()?;
+
+    let headers = info_span!("read_headers").in_scope(|| {
+        headers::read_header(first_file.path(), separator).and_then(|first_header| {
+            headers::read_header(second_file.path(), separator)
+                .map(|second_header| (first_header, second_header))
+        })
+    });
+    let (first_header, second_header) = match headers {
+        Ok(headers) => headers,
+        Err(message) => {
+            printer.result(message.fail_status());
+            printer.summary(format!("INVALID FILE: {}", message));
+            report.result = ComparisonResult::InvalidFile;
+            return Ok(None);
+        }
+    };
+    report.duplicate_headers_first = headers::find_duplicates(&first_header);
+    report.duplicate_headers_second = headers::find_duplicates(&second_header);
+    if !report.duplicate_headers_first.is_empty() || !report.duplicate_headers_second.is_empty() {
+        let message = format!(
+            "Duplicate column names: [{}] in {}, [{}] in {}",
+            report.duplicate_headers_first.join(","),
+            first_file_path,
+            report.duplicate_headers_second.join(","),
+            second_file_path
+        );
+        if !args.dedupe_headers {
+            printer.result(format!(
+                "{}: {} ({} to rename them as name_2, name_3...)",
+                tr("INVALID FILE").fail_status(),
+                message,
+                "--dedupe-headers".bold()
+            ));
+            printer.summary(format!("INVALID FILE: {}", message));
+            report.result = ComparisonResult::InvalidFile;
+            return Ok(None);
+        }
+        printer.info(format!("{}: {}", tr("Warning").warn_status(), message));
+        first_file_lf = headers::rename_columns(
+            first_file_lf.clone(),
+            &get_column_names(&first_file_lf).with_context(|| first_source.context())?,
+            &headers::disambiguate(&first_header),
+        );
+        second_file_lf = headers::rename_columns(
+            second_file_lf.clone(),
+            &get_column_names(&second_file_lf).with_context(|| second_source.context())?,
+            &headers::disambiguate(&second_header),
+        );
+    }
+
+    Ok(Some(CsvFrames {
+        first_file,
+        second_file,
+        first_lazy_frame: first_file_lf,
+        second_lazy_frame: second_file_lf,
+    }))
+}
+
+/// Compares the inputs read lazily without converting them to CSV (Parquet, Delta and SQLite) with each other
+/// or with a CSV file. Without --typed their values are compared as text, as in CSV files
+fn compare_opened_sources(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_path, first_source): (&str, Option<Box<dyn DataSource>>),
+    (second_file_path, second_source): (&str, Option<Box<dyn DataSource>>),
+    cancellation: (&CancellationToken, Option<Instant>),
+    observer: &dyn ComparisonObserver,
+) -> Result<()> {
+    // The sources and fetched files are kept until the end, the lazy frames read them when collected
+    let (first_source, first_input) = csv_unless_opened(first_file_path, first_source, args)?;
+    let (second_source, second_input) = csv_unless_opened(second_file_path, second_source, args)?;
+    let read = |source: &dyn DataSource, input: &Option<LocalFile>| -> Result<LazyFrame> {
+        let lazy_frame = source.to_lazyframe()?;
+        Ok(if input.is_some() || args.typed {
+            lazy_frame
+        } else {
+            lazy_frame.select([col("*").cast(DataType::Utf8)])
+        })
+    };
+    compare_lazy_frames(
+        report,
+        args,
+        printer,
+        (
+            read(&*first_source, &first_input)?,
+            &opened_source_file(first_file_path, &first_input, args.separator),
+            first_file_path,
+        ),
+        (
+            read(&*second_source, &second_input)?,
+            &opened_source_file(second_file_path, &second_input, args.separator),
+            second_file_path,
+        ),
+        cancellation,
+        observer,
+    )
+}
+
+/// Context of the errors of an input: its CSV file when it was fetched, or just its name
+fn opened_source_file<'a>(
+    file_path: &'a str,
+    input: &'a Option<LocalFile>,
+    separator: char,
+) -> SourceFile<'a> {
+    match input {
+        Some(input) => SourceFile::new(file_path, input.path(), separator),
+        None => SourceFile::in_memory(file_path),
+    }
+}
+
+/// The opened source, or the CSV file fetched when the input has no other source (returned too, so it's kept
+/// until the comparison ends)
+fn csv_unless_opened(
+    file_path: &str,
+    source: Option<Box<dyn DataSource>>,
+    args: &Args,
+) -> Result<(Box<dyn DataSource>, Option<LocalFile>)> {
+    match source {
+        Some(source) => Ok((source, None)),
+        None => {
+            let input = inputs::fetch(file_path, args)?;
+            Ok((Box::new(CsvSource::new(input.path(), args)), Some(input)))
+        }
+    }
+}
+
+/// Compares the lines of both files as text (--raw), for files that can't be parsed
+fn compare_raw_lines(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_path, first_path): (&str, &str),
+    (second_file_path, second_path): (&str, &str),
+) -> Result<()> {
+    let comparison = info_span!("raw_lines")
+        .in_scope(|| raw::compare_lines(first_path, second_path, args.raw_sort))?;
+    report.rows = comparison.first_lines;
+    report.rows_missing = comparison.lines_missing;
+    report.rows_added = comparison.lines_added;
+    if comparison.is_identical() {
+        printer.result(tr_format(
+            "Files {} and {} {}: {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE THE SAME LINES").pass_status(),
+                &comparison.first_lines,
+            ],
+        ));
+        printer.summary(format!(
+            "IDENTICAL: {} lines, compared as text (--raw)",
+            comparison.first_lines
+        ));
+        return Ok(());
+    }
+
+    let first_difference = match comparison.first_different_line {
+        Some(line) => format!(", the first one in line {}", line),
+        None => "".to_string(),
+    };
+    printer.result(format!(
+        "{}: {} ({} missing, {} added{})",
+        tr("FILES ARE DIFFERENT").fail_status(),
+        tr("Different lines").fail(),
+        comparison.lines_missing.to_string().fail().bold(),
+        comparison.lines_added.to_string().fail().bold(),
+        first_difference
+    ));
+    printer.summary(format!(
+        "DIFFERENT LINES: {} missing, {} added (--raw)",
+        comparison.lines_missing, comparison.lines_added
+    ));
+    report.result = if comparison.first_lines == comparison.second_lines {
+        ComparisonResult::DifferentValues
+    } else {
+        ComparisonResult::DifferentRows
+    };
+    Ok(())
+}
+
+/// Compares the lazy frames read from both files (or given by the library user), from the matching of their
+/// columns to the result
+fn compare_lazy_frames(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_lf, first_source, first_file_path): (LazyFrame, &SourceFile, &str),
+    (second_file_lf, second_source, second_file_path): (LazyFrame, &SourceFile, &str),
+    (cancellation_token, deadline): (&CancellationToken, Option<Instant>),
+    observer: &dyn ComparisonObserver,
+) -> Result<()> {
+    let separator = args.separator;
+    let PreparedFrames {
+        first_lazy_frame: mut first_file_lf,
+        second_lazy_frame: mut second_file_lf,
+        key,
+        since,
+        first_new_rows_lf,
+    } = prepare_frames(
+        report,
+        args,
+        printer,
+        (first_file_lf, first_source),
+        (second_file_lf, second_source, second_file_path),
+    )?;
+    if args.fingerprints {
+        report.fingerprints = info_span!("fingerprints")
+            .in_scope(|| baseline::fingerprints(&first_file_lf, &key))
+            .with_context(|| first_source.context())?;
+    }
+
+    if args.headers_only {
+        if check_columns(
+            report,
+            args,
+            printer,
+            &first_file_lf,
+            first_source,
+            &second_file_lf,
+            second_source,
+        )?
+        .is_some()
+        {
+            printer.result(tr_format(
+                "Files {} and {} {}",
+                &[
+                    &first_file_path.bold(),
+                    &second_file_path.bold(),
+                    &tr("HAVE THE SAME COLUMNS").pass_status(),
+                ],
+            ));
+            printer.summary("SAME COLUMNS: values were not compared (--headers-only)");
+        }
+        return Ok(());
+    }
+
+    if let Some(drift_rules_path) = &args.drift_rules {
+        return compare_drift(
+            report,
+            args,
+            printer,
+            &stats::DriftRules::load(drift_rules_path)?,
+            (&first_file_lf, first_source, first_file_path),
+            (&second_file_lf, second_source, second_file_path),
+        );
+    }
+
+    if let Some(top) = args.top_values {
+        return compare_top_values(
+            report,
+            args,
+            printer,
+            top,
+            (&first_file_lf, first_source, first_file_path),
+            (&second_file_lf, second_source, second_file_path),
+        );
+    }
+
+    if args.cardinality {
+        return compare_cardinality(
+            report,
+            args,
+            printer,
+            (&first_file_lf, first_source, first_file_path),
+            (&second_file_lf, second_source, second_file_path),
+        );
+    }
+
+    if args.nulls_only {
+        return compare_null_counts(
+            report,
+            args,
+            printer,
+            (&first_file_lf, first_source, first_file_path),
+            (&second_file_lf, second_source, second_file_path),
+        );
+    }
+
+    if let Some(assertions_path) = &args.assertions {
+        let assertions = assertions::Assertions::load(assertions_path)?;
+        let columns = get_column_names(&first_file_lf).with_context(|| first_source.context())?;
+        report.assertion_failures = info_span!("assertions")
+            .in_scope(|| assertions.check(&first_file_lf, &second_file_lf, &columns))?;
+        if report.assertion_failures.is_empty() {
+            printer.info(tr("Values of both files follow the assertions").pass());
+        } else {
+            printer.result(format!(
+                "{}: {}\n{}",
+                tr("FAILED ASSERTIONS").fail_status(),
+                tr("Some values don't follow the assertions").fail(),
+                report.assertions_table()
+            ));
+        }
+    }
+
+    if args.assert_unique_key {
+        let duplicated_keys = |lazy_frame: &LazyFrame, source: &SourceFile| {
+            compare::find_duplicated_keys(lazy_frame, &key, args.samples)
+                .with_context(|| source.context())
+        };
+        let (first_duplicated, first_top_keys) = duplicated_keys(&first_file_lf, first_source)?;
+        let (second_duplicated, second_top_keys) = duplicated_keys(&second_file_lf, second_source)?;
+        if first_duplicated > 0 || second_duplicated > 0 {
+            let as_duplicated_keys = |top_keys: Vec<(Option<String>, u64)>| {
+                top_keys
+                    .into_iter()
+                    .map(|(key, rows)| DuplicatedKey { key, rows })
+                    .collect()
+            };
+            report.duplicated_keys_first = as_duplicated_keys(first_top_keys);
+            report.duplicated_keys_second = as_duplicated_keys(second_top_keys);
+            redact::redact_report(report, args, &key)?;
+            printer.result(format!(
+                "{}: {} {} {} in {}, {} in {}\n{}",
+                tr("DUPLICATED KEYS").fail_status(),
+                tr("Column").fail(),
+                key.fail().bold(),
+                tr("has repeated values").fail(),
+                first_duplicated,
+                first_file_path,
+                second_duplicated,
+                second_file_path,
+                report.duplicated_keys_table()
+            ));
+            printer.summary(format!(
+                "DUPLICATED KEYS: {} <> {} repeated keys",
+                first_duplicated, second_duplicated
+            ));
+            report.result = ComparisonResult::DuplicatedKeys;
+            return Ok(());
+        }
+        printer.info(format!(
+            "{}: {}",
+            tr("Key is unique in both files").pass(),
+            key
+        ));
+    }
+
+    if args.key_counts {
+        let (keys_with_different_count, key_count_differences) = info_span!("key_counts")
+            .in_scope(|| {
+                compare::count_rows_per_key(&first_file_lf, &second_file_lf, &key, args.samples)
+            })?;
+        if keys_with_different_count > 0 {
+            report.keys_with_different_count = keys_with_different_count;
+            report.key_count_differences = key_count_differences;
+            redact::redact_report(report, args, &key)?;
+            printer.result(format!(
+                "{}: {} {} {}\n{}",
+                tr("FILES ARE DIFFERENT").fail_status(),
+                keys_with_different_count.to_string().fail().bold(),
+                tr("key(s) have a different number of rows in column").fail(),
+                key.fail().bold(),
+                report.key_counts_table()
+            ));
+            printer.summary(format!(
+                "DIFFERENT ROWS: {} keys with a different number of rows",
+                keys_with_different_count
+            ));
+            report.result = ComparisonResult::DifferentRows;
+            return Ok(());
+        }
+        printer.info(tr("Every key has the same number of rows in both files").pass());
+    }
+
+    if let Some(tolerance) = args.row_count_tolerance {
+        (first_file_lf, second_file_lf) = tolerate_row_counts(
+            report,
+            printer,
+            (tolerance, key.as_str()),
+            (first_file_lf, first_source),
+            (second_file_lf, second_source),
+        )?;
+    }
+    let row_counts = info_span!("row_count").in_scope(|| {
+        assert_both_frames_have_same_row_num(
+            &first_file_lf,
+            first_source,
+            &second_file_lf,
+            second_source,
+            printer,
+            observer,
+        )
+    })?;
+    let row_num = match row_counts {
+        Some(row_num) => row_num,
+        None => {
+            if !args.count_only {
+                classify_rows(report, &first_file_lf, &second_file_lf)?;
+                printer.result(report.categories_table());
+            }
+            report.result = ComparisonResult::DifferentRows;
+            return Ok(());
+        }
+    };
+    printer.info(format!(
+        "{}: {}",
+        tr("Files have same number of rows").pass(),
+        row_num
+    ));
+    report.rows = row_num as u64;
+
+    if args.count_only {
+        printer.result(tr_format(
+            "Files {} and {} {}: {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE THE SAME NUMBER OF ROWS").pass_status(),
+                &row_num,
+            ],
+        ));
+        printer.summary(format!(
+            "SAME ROWS: {} rows, values were not compared (--count-only)",
+            row_num
+        ));
+        return Ok(());
+    }
+
+    let checked_columns = info_span!("schema_check").in_scope(|| {
+        check_columns(
+            report,
+            args,
+            printer,
+            &first_file_lf,
+            first_source,
+            &second_file_lf,
+            second_source,
+        )
+    })?;
+    let first_file_cols = match checked_columns {
+        Some(first_file_cols) => first_file_cols,
+        None => return Ok(()),
+    };
+    let schema_columns = first_file_cols.clone();
+    let first_file_cols = match args.shard {
+        Some(shard) => {
+            let total_columns = first_file_cols.len() - 1;
+            let shard_cols = shard.columns(first_file_cols);
+            printer.info(format!(
+                "Shard {}: comparing {} of {} columns",
+                shard,
+                shard_cols.len() - 1,
+                total_columns
+            ));
+            report.shard = Some(shard);
+            shard_cols
+        }
+        None => first_file_cols,
+    };
+    let first_file_cols = if args.likely_different_first {
+        let mut likely_different_cols = heuristics::likely_different_first(
+            &first_file_lf,
+            &second_file_lf,
+            &first_file_cols[1..],
+        )
+        .with_context(|| first_source.context())?;
+        debug!(
+            "Columns sorted by likely differences: {:?}",
+            likely_different_cols
+        );
+        likely_different_cols.insert(0, first_file_cols[0].clone());
+        likely_different_cols
+    } else {
+        first_file_cols
+    };
+    let first_file_cols = match &args.start_at_column {
+        Some(start_column) => {
+            let Some(start) = first_file_cols[1..]
+                .iter()
+                .position(|column| column == start_column)
+            else {
+                anyhow::bail!("Column \"{}\" to start at is not compared", start_column);
+            };
+            printer.info(format!(
+                "Skipping {} column(s) before column \"{}\"",
+                start, start_column
+            ));
+            let mut remaining_cols = first_file_cols;
+            remaining_cols.drain(1..start + 1);
+            remaining_cols
+        }
+        None => first_file_cols,
+    };
+
+    let sorting_column = &first_file_cols[0];
+    let columns_to_iterate = (first_file_cols.len() - 1) as u64;
+
+    printer.info(format!(
+        "Comparing content of columns in both files when sorted by column \"{}\"...",
+        sorting_column
+    ));
+    // Categories of both files can only be compared when they share the string cache
+    let _string_cache = args
+        .categorical_threshold
+        .map(|_| StringCacheHolder::hold());
+    if let Some(threshold) = args.categorical_threshold {
+        let categorical_columns = categorical::repetitive_columns(
+            &first_file_lf,
+            sorting_column,
+            &first_file_cols,
+            threshold,
+        )
+        .with_context(|| first_source.context())?;
+        if !categorical_columns.is_empty() {
+            printer.info(format!(
+                "Comparing repetitive column(s) as categories: {}",
+                categorical_columns.join(", ")
+            ));
+            first_file_lf = categorical::as_categorical(first_file_lf, &categorical_columns);
+            second_file_lf = categorical::as_categorical(second_file_lf, &categorical_columns);
+        }
+    }
+    let key_ranges = partition::key_ranges(&first_file_lf, sorting_column, args.key_partitions)
+        .with_context(|| first_source.context())?;
+    if key_ranges.len() > 1 {
+        printer.info(format!(
+            "Comparing the files in {} ranges of column \"{}\"",
+            key_ranges.len(),
+            sorting_column
+        ));
+    }
+    let number_of_columns_to_compare = if args.compares_all_columns() {
+        let estimated_memory = report.resources.input_bytes * MEMORY_PER_INPUT_BYTE;
+        if estimated_memory > 0 {
+            printer.info(format!(
+                "{}: comparing all the columns at once needs around {:.1} MB of memory",
+                tr("Warning").warn_status(),
+                estimated_memory as f64 / (1024.0 * 1024.0)
+            ));
+        }
+        columns_to_iterate as usize
+    } else {
+        args.number_of_columns
+    };
+    // Both files are read once per batch of columns and range of keys
+    let number_of_batches = columns_to_iterate.div_ceil(number_of_columns_to_compare.max(1) as u64);
+    let mut progress = printer.progress(
+        columns_to_iterate,
+        report.resources.input_bytes * number_of_batches * key_ranges.len() as u64,
+        !args.no_progress,
+        args.progress_format,
+    );
+    let mut columns_to_compare = vec![];
+    let mut stopped_early = false;
+    for i in 1..first_file_cols.len() {
+        let column_name = &first_file_cols[i];
+        columns_to_compare.push(column_name);
+
+        if columns_to_compare.len() == number_of_columns_to_compare
+            || i == first_file_cols.len() - 1
+        {
+            let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if cancellation_token.is_cancelled() || timed_out {
+                progress.abandon();
+                stopped_early = true;
+                report.result = if timed_out {
+                    ComparisonResult::TimedOut
+                } else {
+                    ComparisonResult::Aborted
+                };
+                break;
+            }
+            debug!("Comparing batch of columns {:?}", columns_to_compare);
+            let _batch_span = info_span!("compare_batch", columns = ?columns_to_compare).entered();
+            observer.columns_started(&columns_to_compare);
+            progress.batch_started(&columns_to_compare, row_num as u64);
+            let batch_start = Instant::now();
+            let (are_equal, differences) = compare_batch(
+                (&first_file_lf, first_source),
+                (&second_file_lf, second_source),
+                (sorting_column, args.sort_options()),
+                &columns_to_compare,
+                &key_ranges,
+                args.samples,
+            )?;
+            report.batches.push(BatchTiming {
+                columns: columns_to_compare.iter().map(|c| c.to_string()).collect(),
+                elapsed: batch_start.elapsed(),
+                peak_memory_bytes: resources::peak_memory_bytes(),
+            });
+
+            observer.columns_finished(&columns_to_compare, are_equal);
+
+            if !are_equal {
+                for difference in &differences {
+                    observer.difference_found(difference);
+                }
+                report.column_differences.extend(differences);
+                let column_names = columns_to_compare
+                    .iter()
+                    .copied()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+
+                printer.result(format!(
+                    "{}: {} \n {} \n {}",
+                    tr("FILES ARE DIFFERENT").fail_status(),
+                    tr("Values for column(s)").fail(),
+                    column_names.fail().bold(),
+                    tr("are different").fail()
+                ));
+
+                if !args.report_all {
+                    progress.abandon();
+                    stopped_early = true;
+                    break;
+                }
+            }
+            progress.batch_done(&columns_to_compare, row_num as u64);
+            report
+                .columns_compared
+                .extend(columns_to_compare.iter().map(|column| column.to_string()));
+            columns_to_compare.clear();
+        }
+    }
+    if !stopped_early {
+        progress.finish();
+    }
+    // Differences are reported in the order of the columns of the first file, whatever order they were compared in
+    report.column_differences.sort_by_key(|difference| {
+        schema_columns
+            .iter()
+            .position(|column| *column == difference.column)
+    });
+
+    if report.result == ComparisonResult::Aborted || report.result == ComparisonResult::TimedOut {
+        let title = if report.result == ComparisonResult::TimedOut {
+            "TIMED OUT"
+        } else {
+            "ABORTED"
+        };
+        printer.result(format!(
+            "{}: {} {}",
+            tr_format("COMPARISON {}", &[&tr(title)]).warn_status(),
+            tr("Columns compared before stopping:"),
+            report.columns_compared.join(", ")
+        ));
+        if !report.column_differences.is_empty() {
+            printer.result(report.differences_table());
+        }
+        printer.summary(format!(
+            "{}: {} columns compared",
+            title,
+            report.columns_compared.len()
+        ));
+        return Ok(());
+    }
+
+    if !report.column_differences.is_empty() {
+        // The rows left out by --row-count-tolerance are already classified
+        if report.rows_missing == 0 && report.rows_added == 0 {
+            classify_rows(report, &first_file_lf, &second_file_lf)?;
+        }
+        if let Some(annotate_path) = &args.annotate {
+            let rows = annotate::write_annotated(
+                &first_file_lf,
+                &second_file_lf,
+                sorting_column,
+                &first_file_cols,
+                separator,
+                annotate_path,
+            )?;
+            printer.info(format!(
+                "{} row(s) with differences written to {}",
+                rows, annotate_path
+            ));
+        }
+        redact::redact_report(report, args, sorting_column)?;
+        printer.result(report.differences_table());
+        printer.result(report.categories_table());
+        let (whitespace_only, case_only) = report.cosmetic_differences();
+        let values_changed: u64 = report
+            .column_differences
+            .iter()
+            .map(|difference| difference.values_changed)
+            .sum();
+        if whitespace_only + case_only > 0 && whitespace_only + case_only == values_changed {
+            printer.result(
+                tr("All the changed values only differ in whitespace around them or in case")
+                    .warn()
+                    .to_string(),
+            );
+        }
+        if args.samples > 0 {
+            printer.result(report.samples_table());
+        }
+        let column_names = report
+            .column_differences
+            .iter()
+            .map(|difference| difference.column.as_str())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        printer.summary(format!("DIFFERENT VALUES: {}", column_names));
+        report.result = ComparisonResult::DifferentValues;
+        return Ok(());
+    }
+
+    if row_num == 0 {
+        printer.result(tr_format(
+            "Files {} and {} {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE THE SAME COLUMNS AND NO ROWS").pass_status(),
+            ],
+        ));
+        printer.summary("EMPTY: both files have the same columns and no rows");
+        report.result = ComparisonResult::Empty;
+        return Ok(());
+    }
+
+    printer.result(tr_format(
+        "Files {} and {} {} {}",
+        &[
+            &first_file_path.bold(),
+            &second_file_path.bold(),
+            &tr("ARE IDENTICAL WHEN SORTED BY COLUMN:").pass_status(),
+            &sorting_column.pass(),
+        ],
+    ));
+    let (result, summary) = identical_result(report, row_num, sorting_column);
+    if result == ComparisonResult::DifferentColumnOrder {
+        printer.result(
+            tr("Columns are in a different order in each file")
+                .warn()
+                .to_string(),
+        );
+    }
+    printer.summary(summary);
+    report.result = result;
+    if let Some(checkpoint_path) = &args.checkpoint {
+        let checkpoint = since
+            .next(&first_new_rows_lf, &key)
+            .with_context(|| first_source.context())?;
+        checkpoint.save(checkpoint_path)?;
+        printer.info(format!(
+            "Checkpoint saved in {}: next comparison starts after {}",
+            checkpoint_path, checkpoint
+        ));
+    }
+
+    Ok(())
+}
+
+/// Lazy frames of both files ready to compare their values
+struct PreparedFrames {
+    first_lazy_frame: LazyFrame,
+    second_lazy_frame: LazyFrame,
+    /// Column the rows are sorted by, the first one of the first frame
+    key: String,
+    since: Checkpoint,
+    /// First frame with the rows after the checkpoint, before leaving out the excluded ones
+    first_new_rows_lf: LazyFrame,
+}
+
+/// Matches the columns of both frames (affixes, aliases, position or case), moves the key to sort by to the front
+/// with its type, leaves out the rows before the checkpoint, outside of the window of keys or excluded, and
+/// normalizes the values, as configured in the arguments
+fn prepare_frames(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (mut first_file_lf, first_source): (LazyFrame, &SourceFile),
+    (mut second_file_lf, second_source, second_file_path): (LazyFrame, &SourceFile, &str),
+) -> Result<PreparedFrames> {
+    for (lazy_frame, source, prefix, suffix) in [
+        (
+            &mut first_file_lf,
+            first_source,
+            &args.strip_prefix1,
+            &args.strip_suffix1,
+        ),
+        (
+            &mut second_file_lf,
+            second_source,
+            &args.strip_prefix2,
+            &args.strip_suffix2,
+        ),
+    ] {
+        if prefix.is_none() && suffix.is_none() {
+            continue;
+        }
+        let names = get_column_names(lazy_frame).with_context(|| source.context())?;
+        let stripped_names = headers::strip_affixes(&names, prefix.as_deref(), suffix.as_deref());
+        if stripped_names != names {
+            debug!("Columns renamed as {:?}", stripped_names);
+            *lazy_frame = headers::rename_columns(lazy_frame.clone(), &names, &stripped_names);
+        }
+    }
+
+    if let Some(aliases_path) = &args.aliases {
+        let aliases = headers::Aliases::load(aliases_path)?;
+        let first_names =
+            get_column_names(&first_file_lf).with_context(|| first_source.context())?;
+        let second_names =
+            get_column_names(&second_file_lf).with_context(|| second_source.context())?;
+        let matched_names = aliases.match_names(&first_names, &second_names);
+        if matched_names != second_names {
+            printer.info(format!(
+                "Columns matched by their aliases: {}",
+                second_names
+                    .iter()
+                    .zip(&matched_names)
+                    .filter(|(second_name, matched_name)| second_name != matched_name)
+                    .map(|(second_name, matched_name)| format!(
+                        "{} <> {}",
+                        matched_name, second_name
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            second_file_lf =
+                headers::rename_columns(second_file_lf.clone(), &second_names, &matched_names);
+        }
+    }
+
+    if args.match_by_position {
+        let first_names =
+            get_column_names(&first_file_lf).with_context(|| first_source.context())?;
+        let second_names =
+            get_column_names(&second_file_lf).with_context(|| second_source.context())?;
+        if first_names.len() == second_names.len() {
+            report.columns_matched_by_position =
+                get_column_order_differences(&first_names, &second_names);
+            second_file_lf =
+                headers::rename_columns(second_file_lf.clone(), &second_names, &first_names);
+        }
+        if !report.columns_matched_by_position.is_empty() {
+            printer.info(format!(
+                "Columns matched by position: {}",
+                report
+                    .columns_matched_by_position
+                    .iter()
+                    .map(|pair| format!("{} <> {}", pair.first_column, pair.second_column))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    } else {
+        let first_names =
+            get_column_names(&first_file_lf).with_context(|| first_source.context())?;
+        let second_names =
+            get_column_names(&second_file_lf).with_context(|| second_source.context())?;
+        let matched_names =
+            headers::match_names(&first_names, &second_names, args.ignore_column_case);
+        if matched_names != second_names {
+            debug!(
+                "Columns of {} renamed as {:?}",
+                second_file_path, matched_names
+            );
+            second_file_lf =
+                headers::rename_columns(second_file_lf.clone(), &second_names, &matched_names);
+        }
+    }
+
+    if let Some(sort_by) = &args.sort_by {
+        let first_names =
+            get_column_names(&first_file_lf).with_context(|| first_source.context())?;
+        let sort_column = sort_by.resolve(&first_names).map_err(anyhow::Error::msg)?;
+        if sort_column != first_names[0] {
+            let second_names =
+                get_column_names(&second_file_lf).with_context(|| second_source.context())?;
+            if !second_names.contains(&sort_column) {
+                anyhow::bail!(
+                    "Column {} to sort by doesn't exist in file {}",
+                    sort_column,
+                    second_file_path
+                );
+            }
+            debug!("Sorting by column {} ({})", sort_column, sort_by);
+            first_file_lf = headers::move_to_front(first_file_lf, &first_names, &sort_column);
+            second_file_lf = headers::move_to_front(second_file_lf, &second_names, &sort_column);
+        }
+    }
+    let key =
+        get_column_names(&first_file_lf).with_context(|| first_source.context())?[0].to_string();
+    match args.key_type {
+        Some(key_type) => {
+            first_file_lf = key::cast_key(first_file_lf, &key, key_type);
+            second_file_lf = key::cast_key(second_file_lf, &key, key_type);
+        }
+        None => {
+            if key::is_numeric_text(&first_file_lf, &key).with_context(|| first_source.context())? {
+                printer.info(format!(
+                    "{}: column {} has numbers of different lengths sorted as text (\"10\" before \"9\"), \
+                     use {} to sort them as numbers",
+                    tr("Warning").warn_status(),
+                    key,
+                    "--key-type int".bold()
+                ));
+            }
+        }
+    }
+    let since = Checkpoint::from_args(args)?;
+    if !since.is_start() {
+        printer.info(format!("Comparing only the rows after {}", since));
+        first_file_lf = since.skip_compared(first_file_lf, &key)?;
+        second_file_lf = since.skip_compared(second_file_lf, &key)?;
+    }
+    if let Some(window) = &args.key_range {
+        printer.info(format!("Comparing only the rows with keys in {}", window));
+        first_file_lf = filters::keys_in_window(first_file_lf, &key, window)
+            .with_context(|| first_source.context())?;
+        second_file_lf = filters::keys_in_window(second_file_lf, &key, window)
+            .with_context(|| second_source.context())?;
+    }
+    let first_new_rows_lf = first_file_lf.clone();
+    let excluded_keys = match &args.exclude_keys {
+        Some(keys_file) => {
+            let keys = filters::read_keys(keys_file)?;
+            printer.info(format!(
+                "Excluding {} key(s) of column {} listed in {}",
+                keys.len(),
+                key,
+                keys_file
+            ));
+            keys
+        }
+        None => vec![],
+    };
+    for exclusion in &args.exclude_rows {
+        printer.info(format!("Excluding rows where {}", exclusion));
+    }
+    first_file_lf = apply_normalizations(first_file_lf, args, &key, &excluded_keys);
+    second_file_lf = apply_normalizations(second_file_lf, args, &key, &excluded_keys);
+    Ok(PreparedFrames {
+        first_lazy_frame: first_file_lf,
+        second_lazy_frame: second_file_lf,
+        key,
+        since,
+        first_new_rows_lf,
+    })
+}
+
+/// Result and summary line of files with the same values, whose assertions or column order can still be different
+fn identical_result(
+    report: &ComparisonReport,
+    row_num: u32,
+    sorting_column: &str,
+) -> (ComparisonResult, String) {
+    if !report.assertion_failures.is_empty() {
+        return (
+            ComparisonResult::FailedAssertions,
+            format!(
+                "FAILED ASSERTIONS: {} assertions, the values of {} rows sorted by {} are identical",
+                report.assertion_failures.len(),
+                row_num,
+                sorting_column
+            ),
+        );
+    }
+    let result = if report.column_order_differences.is_empty() {
+        ComparisonResult::Identical
+    } else {
+        ComparisonResult::DifferentColumnOrder
+    };
+    (
+        result,
+        format!("IDENTICAL: {} rows sorted by {}", row_num, sorting_column),
+    )
+}
+
+/// Checks that both files have the same columns (and data types in typed mode), warning about
+/// the ones in a different order. Returns the columns of the first file if the comparison can go on
+fn check_columns(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    first_file_lf: &LazyFrame,
+    first_source: &SourceFile,
+    second_file_lf: &LazyFrame,
+    second_source: &SourceFile,
+) -> Result<Option<Vec<String>>> {
+    let first_file_cols =
+        get_column_names(first_file_lf).with_context(|| first_source.context())?;
+    let second_file_cols =
+        get_column_names(second_file_lf).with_context(|| second_source.context())?;
+
+    if !assert_both_frames_are_comparable(
+        &first_file_cols,
+        &second_file_cols,
+        args.strict_column_order,
+        printer,
+    ) {
+        report.result = ComparisonResult::DifferentColumns;
+        return Ok(None);
+    }
+    printer.info(tr("Files have comparable columns").pass());
+
+    if args.typed {
+        report.column_type_differences =
+            get_column_type_differences(first_file_lf, second_file_lf, &first_file_cols)
+                .context("Couldn't infer the column types")?;
+        if !report.column_type_differences.is_empty() {
+            printer.result(format!(
+                "{}: {}\n{}",
+                tr("FILES ARE DIFFERENT").fail_status(),
+                tr("Columns have different data types").fail(),
+                report.types_table()
+            ));
+            printer.summary(format!(
+                "DIFFERENT TYPES: {}",
+                report
+                    .column_type_differences
+                    .iter()
+                    .map(|difference| format!(
+                        "{} ({} <> {})",
+                        difference.column, difference.first_type, difference.second_type
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            report.result = ComparisonResult::DifferentTypes;
+            return Ok(None);
+        }
+    }
+
+    report.column_order_differences =
+        get_column_order_differences(&first_file_cols, &second_file_cols);
+    if !report.column_order_differences.is_empty() {
+        printer.info(format!(
+            "{}: {}",
+            tr("Warning: columns are in a different order").warn_status(),
+            report
+                .column_order_differences
+                .iter()
+                .map(|difference| format!(
+                    "position {}: {} <> {}",
+                    difference.position, difference.first_column, difference.second_column
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    Ok(Some(first_file_cols))
+}
+
+/// Leaves out the excluded rows and normalizes the values of the columns as configured in the arguments
+pub(crate) fn apply_normalizations(
+    mut lazy_frame: LazyFrame,
+    args: &Args,
+    key: &str,
+    excluded_keys: &[String],
+) -> LazyFrame {
+    if !excluded_keys.is_empty() {
+        lazy_frame = filters::exclude_keys(lazy_frame, key, excluded_keys);
+    }
+    for exclusion in &args.exclude_rows {
+        lazy_frame = filters::exclude_rows(lazy_frame, exclusion);
+    }
+    for column in &args.nullish_columns {
+        lazy_frame = normalize::nullify_blanks(lazy_frame, column);
+    }
+    for column in &args.json_columns {
+        lazy_frame = normalize::canonicalize_json(lazy_frame, column, &args.ignore_json_paths);
+    }
+    for list_column in &args.list_columns {
+        lazy_frame = normalize::sort_lists(lazy_frame, list_column);
+    }
+    for binary_column in &args.binary_columns {
+        lazy_frame = normalize::decode_binary(lazy_frame, binary_column);
+    }
+    lazy_frame
+}
+
+/// Whether the file has no content at all (not even a header). Only small files are read to check it
+fn is_empty_file(file_path: &str) -> bool {
+    match std::fs::metadata(file_path) {
+        Ok(metadata) if metadata.len() == 0 => true,
+        Ok(metadata) if metadata.len() <= 1024 => std::fs::read(file_path)
+            .is_ok_and(|content| content.iter().all(u8::is_ascii_whitespace)),
+        _ => false,
+    }
+}
+
+/// Compares the number of rows and of nulls of every column (--nulls-only)
+fn compare_null_counts(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_lf, first_source, first_file_path): (&LazyFrame, &SourceFile, &str),
+    (second_file_lf, second_source, second_file_path): (&LazyFrame, &SourceFile, &str),
+) -> Result<()> {
+    let Some(columns) = check_columns(
+        report,
+        args,
+        printer,
+        first_file_lf,
+        first_source,
+        second_file_lf,
+        second_source,
+    )?
+    else {
+        return Ok(());
+    };
+    let (first_rows, first_nulls) = info_span!("null_counts")
+        .in_scope(|| compare::count_nulls(first_file_lf, &columns))
+        .with_context(|| first_source.context())?;
+    let (second_rows, second_nulls) = info_span!("null_counts")
+        .in_scope(|| compare::count_nulls(second_file_lf, &columns))
+        .with_context(|| second_source.context())?;
+    report.rows = first_rows;
+    if first_rows != second_rows {
+        printer.result(format!(
+            "{}: {} {} <> {}",
+            tr("FILES ARE DIFFERENT").fail_status(),
+            tr("Different number of rows").fail(),
+            first_rows,
+            second_rows
+        ));
+        printer.summary(format!("DIFFERENT ROWS: {} <> {}", first_rows, second_rows));
+        report.result = ComparisonResult::DifferentRows;
+        return Ok(());
+    }
+    report.null_count_differences = columns
+        .iter()
+        .zip(first_nulls.into_iter().zip(second_nulls))
+        .filter(|(_, (first_nulls, second_nulls))| first_nulls != second_nulls)
+        .map(
+            |(column, (first_nulls, second_nulls))| NullCountDifference {
+                column: column.to_string(),
+                first_nulls,
+                second_nulls,
+            },
+        )
+        .collect();
+    if report.null_count_differences.is_empty() {
+        printer.result(tr_format(
+            "Files {} and {} {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE THE SAME NUMBER OF NULLS IN EVERY COLUMN").pass_status(),
+            ],
+        ));
+        printer.summary(format!(
+            "SAME NULLS: {} rows, values were not compared (--nulls-only)",
+            first_rows
+        ));
+        return Ok(());
+    }
+    printer.result(format!(
+        "{}: {}\n{}",
+        tr("FILES ARE DIFFERENT").fail_status(),
+        tr("Columns have a different number of nulls").fail(),
+        report.null_counts_table()
+    ));
+    printer.summary(format!(
+        "DIFFERENT NULLS: {}",
+        report
+            .null_count_differences
+            .iter()
+            .map(|difference| difference.column.as_str())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    ));
+    report.result = ComparisonResult::DifferentValues;
+    Ok(())
+}
+
+/// Compares the number of distinct values of every column (--cardinality)
+fn compare_cardinality(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    (first_file_lf, first_source, first_file_path): (&LazyFrame, &SourceFile, &str),
+    (second_file_lf, second_source, second_file_path): (&LazyFrame, &SourceFile, &str),
+) -> Result<()> {
+    let Some(columns) = check_columns(
+        report,
+        args,
+        printer,
+        first_file_lf,
+        first_source,
+        second_file_lf,
+        second_source,
+    )?
+    else {
+        return Ok(());
+    };
+    let first_distinct = info_span!("distinct_counts")
+        .in_scope(|| stats::distinct_counts(first_file_lf, &columns))
+        .with_context(|| first_source.context())?;
+    let second_distinct = info_span!("distinct_counts")
+        .in_scope(|| stats::distinct_counts(second_file_lf, &columns))
+        .with_context(|| second_source.context())?;
+    report.cardinality_differences = columns
+        .iter()
+        .zip(first_distinct.into_iter().zip(second_distinct))
+        .filter(|(_, (first_distinct, second_distinct))| {
+            stats::percent_change(*first_distinct as f64, *second_distinct as f64)
+                > args.cardinality_tolerance
+        })
+        .map(
+            |(column, (first_distinct, second_distinct))| CardinalityDifference {
+                column: column.to_string(),
+                first_distinct,
+                second_distinct,
+            },
+        )
+        .collect();
+    if report.cardinality_differences.is_empty() {
+        printer.result(tr_format(
+            "Files {} and {} {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE THE SAME DISTINCT VALUES IN EVERY COLUMN").pass_status(),
+            ],
+        ));
+        printer.summary("SAME CARDINALITY: values were not compared (--cardinality)");
+        return Ok(());
+    }
+    printer.result(format!(
+        "{}: {}\n{}",
+        tr("FILES ARE DIFFERENT").fail_status(),
+        tr("Columns have a different number of distinct values").fail(),
+        report.cardinality_table()
+    ));
+    printer.summary(format!(
+        "DIFFERENT CARDINALITY: {}",
+        report
+            .cardinality_differences
+            .iter()
+            .map(|difference| difference.column.as_str())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    ));
+    report.result = ComparisonResult::DifferentValues;
+    Ok(())
+}
+
+/// Compares the statistics of the columns with the drift allowed by the rules (--drift-rules)
+fn compare_drift(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    drift_rules: &stats::DriftRules,
+    (first_file_lf, first_source, first_file_path): (&LazyFrame, &SourceFile, &str),
+    (second_file_lf, second_source, second_file_path): (&LazyFrame, &SourceFile, &str),
+) -> Result<()> {
+    let Some(columns) = check_columns(
+        report,
+        args,
+        printer,
+        first_file_lf,
+        first_source,
+        second_file_lf,
+        second_source,
+    )?
+    else {
+        return Ok(());
+    };
+    report.drift_violations = info_span!("drift")
+        .in_scope(|| drift_rules.check(first_file_lf, second_file_lf, &columns))
+        .with_context(|| format!("Couldn't compare {first_file_path} with {second_file_path}"))?;
+    let failed_columns: Vec<&str> = report
+        .drift_violations
+        .iter()
+        .filter(|violation| violation.severity == DriftSeverity::Fail)
+        .map(|violation| violation.column.as_str())
+        .collect();
+    if !report.drift_violations.is_empty() {
+        printer.result(format!(
+            "{}\n{}",
+            tr("Statistics that drifted more than allowed:").warn(),
+            report.drift_table()
+        ));
+    }
+    if failed_columns.is_empty() {
+        printer.result(tr_format(
+            "Files {} and {} {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr("HAVE STATISTICS WITHIN THE ALLOWED DRIFT").pass_status(),
+            ],
+        ));
+        printer.summary("SAME STATISTICS: values were not compared (--drift-rules)");
+        return Ok(());
+    }
+    printer.result(format!(
+        "{}: {}",
+        tr("FILES ARE DIFFERENT").fail_status(),
+        tr("Statistics drifted more than allowed").fail()
+    ));
+    printer.summary(format!(
+        "DIFFERENT STATISTICS: {}",
+        failed_columns.join(" | ")
+    ));
+    report.result = ComparisonResult::DifferentValues;
+    Ok(())
+}
+
+/// Compares the most frequent values of every column (--top-values). A column is different when its most
+/// frequent values aren't the same, whatever their number of rows
+fn compare_top_values(
+    report: &mut ComparisonReport,
+    args: &Args,
+    printer: &Printer,
+    top: usize,
+    (first_file_lf, first_source, first_file_path): (&LazyFrame, &SourceFile, &str),
+    (second_file_lf, second_source, second_file_path): (&LazyFrame, &SourceFile, &str),
+) -> Result<()> {
+    let Some(columns) = check_columns(
+        report,
+        args,
+        printer,
+        first_file_lf,
+        first_source,
+        second_file_lf,
+        second_source,
+    )?
+    else {
+        return Ok(());
+    };
+    for column in &columns[1..] {
+        let _span = info_span!("top_values", column = %column).entered();
+        let first_values = stats::top_values(first_file_lf, column, top)
+            .with_context(|| first_source.context())?;
+        let second_values = stats::top_values(second_file_lf, column, top)
+            .with_context(|| second_source.context())?;
+        let values = |value_counts: &[ValueCount]| {
+            value_counts
+                .iter()
+                .map(|value_count| value_count.value.clone())
+                .collect::<HashSet<_>>()
+        };
+        if values(&first_values) != values(&second_values) {
+            report.top_values_differences.push(TopValuesDifference {
+                column: column.to_string(),
+                first_values,
+                second_values,
+            });
+        }
+    }
+    if report.top_values_differences.is_empty() {
+        printer.result(tr_format(
+            "Files {} and {} {}",
+            &[
+                &first_file_path.bold(),
+                &second_file_path.bold(),
+                &tr_format(
+                    "HAVE THE SAME {} MOST FREQUENT VALUES IN EVERY COLUMN",
+                    &[&top],
+                )
+                .as_str()
+                .pass_status(),
+            ],
+        ));
+        printer.summary("SAME TOP VALUES: values were not compared (--top-values)");
+        return Ok(());
+    }
+    redact::redact_report(report, args, &columns[0])?;
+    printer.result(format!(
+        "{}: {}\n{}",
+        tr("FILES ARE DIFFERENT").fail_status(),
+        tr("Columns have different most frequent values").fail(),
+        report.top_values_table()
+    ));
+    printer.summary(format!(
+        "DIFFERENT TOP VALUES: {}",
+        report
+            .top_values_differences
+            .iter()
+            .map(|difference| difference.column.as_str())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    ));
+    report.result = ComparisonResult::DifferentValues;
+    Ok(())
+}
+
+/// Fills the number of rows missing and added in the second file, using the first column of the first file as key
+fn classify_rows(
+    report: &mut ComparisonReport,
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+) -> Result<()> {
+    let key = get_column_names(first_lazy_frame)?[0].to_string();
+    if !get_column_names(second_lazy_frame)?.contains(&key) {
+        return Ok(());
+    }
+    report.rows_missing = compare::count_missing_keys(first_lazy_frame, second_lazy_frame, &key)?;
+    report.rows_added = compare::count_missing_keys(second_lazy_frame, first_lazy_frame, &key)?;
+    Ok(())
+}
+
+/// When the number of rows of both files differs within the tolerance, reports the rows missing and added and
+/// leaves in both files only the rows with keys in both of them
+fn tolerate_row_counts(
+    report: &mut ComparisonReport,
+    printer: &Printer,
+    (tolerance, key): (RowCountTolerance, &str),
+    (first_lazy_frame, first_source): (LazyFrame, &SourceFile),
+    (second_lazy_frame, second_source): (LazyFrame, &SourceFile),
+) -> Result<(LazyFrame, LazyFrame)> {
+    let first_row_num = get_rows_num(&first_lazy_frame).with_context(|| first_source.context())?;
+    let second_row_num =
+        get_rows_num(&second_lazy_frame).with_context(|| second_source.context())?;
+    if first_row_num == second_row_num
+        || !tolerance.allows(first_row_num as u64, second_row_num as u64)
+    {
+        return Ok((first_lazy_frame, second_lazy_frame));
+    }
+    classify_rows(report, &first_lazy_frame, &second_lazy_frame)?;
+    printer.result(format!(
+        "{}: {} {} <> {} ({})",
+        tr("Warning").warn_status(),
+        tr("Different number of rows within the tolerance").warn(),
+        first_row_num,
+        second_row_num,
+        tolerance
+    ));
+    printer.result(report.categories_table());
+    printer.info(tr("Comparing only the rows with keys in both files"));
+    Ok(filters::keys_in_both(
+        first_lazy_frame,
+        second_lazy_frame,
+        key,
+    ))
+}
+
+fn assert_both_frames_have_same_row_num(
+    first_lazy_frame: &LazyFrame,
+    first_source: &SourceFile,
+    second_lazy_frame: &LazyFrame,
+    second_source: &SourceFile,
+    printer: &Printer,
+    observer: &dyn ComparisonObserver,
+) -> Result<Option<u32>> {
+    let first_row_num = get_rows_num(first_lazy_frame).with_context(|| first_source.context())?;
+    let second_row_num =
+        get_rows_num(second_lazy_frame).with_context(|| second_source.context())?;
+    observer.row_counts(first_row_num as u64, second_row_num as u64);
+
+    if first_row_num != second_row_num {
+        printer.result(format!(
+            "{}: {} {} <> {}",
+            tr("FILES ARE DIFFERENT").fail_status(),
+            tr("Different number of rows").fail(),
+            first_row_num.to_string(),
+            second_row_num.to_string()
+        ));
+        printer.summary(format!(
+            "DIFFERENT ROWS: {} <> {}",
+            first_row_num, second_row_num
+        ));
+
+        return Ok(None);
+    }
+
+    Ok(Some(first_row_num))
+}
+
+fn assert_both_frames_are_comparable(
+    first_file_cols: &[String],
+    second_file_cols: &[String],
+    is_strict_order: bool,
+    printer: &Printer,
+) -> bool {
+    let have_same_columns = if is_strict_order {
+        first_file_cols.eq(second_file_cols)
+    } else {
+        // Convert the vectors into sets to ignore the order
+        let set1: HashSet<_> = first_file_cols.iter().collect();
+        let set2: HashSet<_> = second_file_cols.iter().collect();
+        set1 == set2
+    };
+
+    if !have_same_columns {
+        printer.result(format!(
+            "{}: {}\n{}",
+            tr("FILES ARE DIFFERENT").fail_status(),
+            tr("Different columns").fail(),
+            report::schema_table(first_file_cols, second_file_cols)
+        ));
+        if is_strict_order {
+            printer.result(format!(
+                "{} {} {}",
+                "Hint:",
+                "--strict-order".bold(),
+                "flag is active"
+            ));
+        }
+        printer.summary(format!(
+            "DIFFERENT COLUMNS: [{}] != [{}]",
+            first_file_cols.join(","),
+            second_file_cols.join(",")
+        ));
+    }
+
+    have_same_columns
+}
+
+/// Positions (1-based) where the columns of both files have different names
+fn get_column_order_differences(
+    first_file_cols: &[String],
+    second_file_cols: &[String],
+) -> Vec<ColumnOrderDifference> {
+    first_file_cols
+        .iter()
+        .zip(second_file_cols)
+        .enumerate()
+        .filter(|(_, (first_column, second_column))| first_column != second_column)
+        .map(
+            |(index, (first_column, second_column))| ColumnOrderDifference {
+                position: index + 1,
+                first_column: first_column.to_string(),
+                second_column: second_column.to_string(),
+            },
+        )
+        .collect()
+}
+
+/// Columns of the first file whose inferred data type is different in the second file
+fn get_column_type_differences(
+    first_lazy_frame: &LazyFrame,
+    second_lazy_frame: &LazyFrame,
+    columns: &[String],
+) -> Result<Vec<ColumnTypeDifference>> {
+    let first_schema = first_lazy_frame.schema()?;
+    let second_schema = second_lazy_frame.schema()?;
+    Ok(columns
+        .iter()
+        .filter_map(|column| {
+            let first_type = first_schema.get(column)?;
+            let second_type = second_schema.get(column)?;
+            (first_type != second_type).then(|| ColumnTypeDifference {
+                column: column.to_string(),
+                first_type: first_type.to_string(),
+                second_type: second_type.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Opens the file lazily. All the values are read as text unless `typed`, where the data types
+/// are inferred from the first rows
+fn get_lazy_frame(
+    file_path: &str,
+    delimiter: char,
+    typed: bool,
+    low_memory: bool,
+) -> Result<LazyFrame> {
+    debug!(
+        "Opening file {} with separator {:?} (typed: {}, low memory: {})",
+        file_path, delimiter, typed, low_memory
+    );
+    LazyCsvReader::new(file_path)
+        .has_header(true)
+        .with_infer_schema_length(if typed { Some(100) } else { Some(0) })
+        .with_separator(delimiter as u8)
+        // Smaller read buffers, and no copy of the chunks into contiguous memory
+        .low_memory(low_memory)
+        .with_rechunk(!low_memory)
+        .finish()
+        .with_context(|| format!("Couldn't open file {file_path}"))
+}
+
+fn get_column_names(lazy_frame: &LazyFrame) -> Result<Vec<String>> {
+    let schema = lazy_frame
+        .clone()
+        .limit(1)
+        .collect()
+        .context("Couldn't parse the header")?
+        .schema();
+
+    Ok(schema.get_names().into_vec())
+}
+
+/// Compares a batch of columns of both files, range by range of the key (all the rows at once when there's
+/// a single range). Returns whether they are equal and the differences found
+fn compare_batch(
+    (first_lazy_frame, first_source): (&LazyFrame, &SourceFile),
+    (second_lazy_frame, second_source): (&LazyFrame, &SourceFile),
+    (sorting_column, sort_options): (&String, SortOptions),
+    columns: &Vec<&String>,
+    key_ranges: &[KeyRange],
+    max_samples: usize,
+) -> Result<(bool, Vec<ColumnDifference>)> {
+    let mut are_equal = true;
+    let mut differences = vec![];
+    for key_range in key_ranges {
+        let first_data_frame = get_sorted_data_frame_for_columns(
+            &key_range.filter(first_lazy_frame, sorting_column),
+            sorting_column,
+            columns,
+            sort_options,
+        )
+        .with_context(|| first_source.context())?;
+        let second_data_frame = get_sorted_data_frame_for_columns(
+            &key_range.filter(second_lazy_frame, sorting_column),
+            sorting_column,
+            columns,
+            sort_options,
+        )
+        .with_context(|| second_source.context())?;
+        let (first_data_frame, second_data_frame, extra_rows) =
+            partition::align(first_data_frame, second_data_frame);
+        if extra_rows == 0 && first_data_frame.equals_missing(&second_data_frame) {
+            continue;
+        }
+        are_equal = false;
+        let range_differences = compare::count_differences(
+            &first_data_frame,
+            &second_data_frame,
+            sorting_column,
+            columns,
+            max_samples,
+        )?;
+        partition::merge_differences(
+            &mut differences,
+            range_differences,
+            columns,
+            extra_rows,
+            max_samples,
+        );
+    }
+    Ok((are_equal, differences))
+}
+
+fn get_sorted_data_frame_for_columns(
+    lazy_frame: &LazyFrame,
+    sorting_by_column: &String,
+    columns: &Vec<&String>,
+    sort_options: SortOptions,
+) -> Result<DataFrame> {
+    let _span = info_span!("sort", column = %sorting_by_column).entered();
+    let mut all_columns = vec![col(sorting_by_column)];
+    for next_column in columns {
+        all_columns.push(col(next_column));
+    }
+
+    lazy_frame
+        .clone()
+        .select(all_columns)
+        .sort(sorting_by_column, sort_options)
+        .collect()
+        .with_context(|| {
+            format!(
+                "Couldn't sort by column {} reading column(s) {}",
+                sorting_by_column,
+                columns
+                    .iter()
+                    .map(|column| column.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+fn get_rows_num(lazy_frame: &LazyFrame) -> Result<u32> {
+    let first_column_name = get_column_names(&lazy_frame.clone())?[0].to_string();
+    trace!("Counting rows using column {}", first_column_name);
+    let rows = lazy_frame
+        .clone()
+        .select([col(first_column_name.as_str())])
+        .collect()
+        .with_context(|| format!("Couldn't count the rows using column {first_column_name}"))?
+        .shape()
+        .0;
+    Ok(rows as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::AssertionFailure;
+
+    #[test]
+    fn identical_files_with_failed_assertions_have_a_single_summary() {
+        let mut report = ComparisonReport {
+            assertion_failures: vec![AssertionFailure {
+                column: "age".to_string(),
+                assertion: "age >= 0".to_string(),
+                first_file: 1,
+                second_file: 1,
+            }],
+            ..Default::default()
+        };
+        report.column_order_differences = get_column_order_differences(
+            &["id".to_string(), "age".to_string()],
+            &["age".to_string(), "id".to_string()],
+        );
+        let (result, summary) = identical_result(&report, 10, "id");
+        assert_eq!(result, ComparisonResult::FailedAssertions);
+        assert_eq!(
+            summary,
+            "FAILED ASSERTIONS: 1 assertions, the values of 10 rows sorted by id are identical"
+        );
+    }
+
+    #[test]
+    fn identical_files_in_a_different_column_order() {
+        let mut report = ComparisonReport::default();
+        assert_eq!(
+            identical_result(&report, 10, "id"),
+            (
+                ComparisonResult::Identical,
+                "IDENTICAL: 10 rows sorted by id".to_string()
+            )
+        );
+        report.column_order_differences = get_column_order_differences(
+            &["id".to_string(), "age".to_string()],
+            &["age".to_string(), "id".to_string()],
+        );
+        assert_eq!(
+            identical_result(&report, 10, "id").0,
+            ComparisonResult::DifferentColumnOrder
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use clap::ValueEnum;
+use tracing::Level;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{Layer, Registry};
+
+/// Format of the diagnostic log lines written to stderr
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Sets up the global tracing subscriber. Logs always go to stderr, stdout is reserved for the result.
+/// Verbosity: 0 => warnings, 1 (-v) => debug, 2 or more (-vv) => trace.
+/// With an OpenTelemetry endpoint, the spans of the comparison are also exported with OTLP (HTTP)
+pub fn init(verbosity: u8, format: LogFormat, use_colors: bool, otel_endpoint: Option<&str>) {
+    let level = level(verbosity);
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_ansi(use_colors);
+    let fmt_layer: Box<dyn Layer<Registry> + Send + Sync> = match format {
+        LogFormat::Text => fmt_layer.boxed(),
+        LogFormat::Json => fmt_layer.json().boxed(),
+    };
+    let subscriber =
+        tracing_subscriber::registry().with(fmt_layer.with_filter(LevelFilter::from_level(level)));
+
+    match otel_endpoint {
+        #[cfg(feature = "otel")]
+        Some(endpoint) => match crate::otel::layer(endpoint) {
+            Ok(otel_layer) => subscriber
+                .with(otel_layer.with_filter(LevelFilter::INFO))
+                .init(),
+            Err(error) => {
+                subscriber.init();
+                tracing::warn!("{}", error);
+            }
+        },
+        #[cfg(not(feature = "otel"))]
+        Some(_) => {
+            subscriber.init();
+            tracing::warn!("OpenTelemetry support is not included in this build (feature otel)");
+        }
+        None => subscriber.init(),
+    }
+}
+
+fn level(verbosity: u8) -> Level {
+    match verbosity {
+        0 => Level::WARN,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Sends the spans not exported yet. Must be called before exiting the process
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    crate::otel::shutdown();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_raises_the_level() {
+        assert_eq!(level(0), Level::WARN);
+        assert_eq!(level(1), Level::DEBUG);
+        assert_eq!(level(2), Level::TRACE);
+        assert_eq!(level(5), Level::TRACE);
+    }
+}
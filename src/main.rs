@@ -1,238 +1,339 @@
-use clap::Parser;
+use clap::FromArgMatches;
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
-use polars::frame::DataFrame;
-use polars::prelude::{
-    col, IndexOfSchema, IntoVec, LazyCsvReader, LazyFileListReader, LazyFrame, SortOptions,
+use csv_compare::cli::{Args, Command};
+use csv_compare::i18n::{self, tr};
+use csv_compare::output::{ColorChoice, Printer, Themed};
+use csv_compare::{
+    baseline, bench, canonicalize, compare_files, config, dirs, git, hash, logging, merge, metrics,
+    notify, output, report, validate,
 };
-use std::collections::HashSet;
+use std::ffi::OsString;
+use std::panic::AssertUnwindSafe;
 use std::process::exit;
-
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// First file to compare
-    file1: String,
-
-    /// Second file to compare
-    file2: String,
-
-    /// Whether files are required to have the columns in the same order (default: allow unordered)
-    #[arg(default_value = "false", long, short)]
-    strict_column_order: bool,
-
-    /// How many columns to compare at the same time.
-    /// The bigger the number the faster, but will also increase the memory consumption
-    #[arg(default_value = "1", long, short)]
-    number_of_columns: usize,
-
-    /// Column separator character
-    #[arg(default_value = ",", long, short = 'p')]
-    separator: char,
-}
+use std::time::Instant;
+use tracing::debug;
 
 fn main() {
-    let args = Args::parse();
-
-    let first_file_path = args.file1.as_str();
-    let second_file_path = args.file2.as_str();
-
-    println!(
-        "Comparing file {} with file {}. {} column(s) at a time... {}",
-        first_file_path,
-        second_file_path,
-        args.number_of_columns,
-        if args.strict_column_order {
-            " Strict order of columns enforced".yellow()
-        } else {
-            "".white()
+    let cli_args: Vec<OsString> = std::env::args_os().collect();
+    let command = match config::command_with_config(&cli_args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}", error.fail_status());
+            exit(1);
         }
-    );
-
-    let separator = args.separator;
-    let first_file_lf = get_lazy_frame(first_file_path, separator);
-    let second_file_lf = get_lazy_frame(second_file_path, separator);
-
-    let row_num = assert_both_frames_have_same_row_num(&first_file_lf, &second_file_lf);
-    println!("{}: {}", "Files have same number of rows".green(), row_num);
-
-    let first_file_cols = get_column_names(&first_file_lf);
-    let second_file_cols = get_column_names(&second_file_lf);
-
-    assert_both_frames_are_comparable(
-        &first_file_cols,
-        &second_file_cols,
-        args.strict_column_order,
-    );
-    println!("{}", "Files have comparable columns".green());
-
-    let sorting_column = &first_file_cols[0];
-    let columns_to_iterate = (first_file_cols.len() - 1) as u64;
+    };
+    let matches = command.get_matches_from(cli_args);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    // A panic means the tool failed, not that the files are different
+    let exit_code_failed = args.exit_code_failed;
+    if std::panic::catch_unwind(AssertUnwindSafe(|| run(args))).is_err() {
+        exit(exit_code_failed);
+    }
+}
 
-    println!(
-        "Comparing content of columns in both files when sorted by column \"{}\"...",
-        sorting_column
+fn run(args: Args) {
+    let color_choice = if args.no_color {
+        ColorChoice::Never
+    } else {
+        args.color
+    };
+    let use_colors = output::setup_colors(color_choice);
+    output::set_theme(args.theme);
+    i18n::set_lang(args.lang.unwrap_or_else(i18n::lang_from_locale));
+    logging::init(
+        args.verbose,
+        args.log_format,
+        use_colors,
+        args.otel_endpoint.as_deref(),
     );
-    let progress_bar = ProgressBar::new(columns_to_iterate);
-    progress_bar.set_style(
-        ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
-            .expect("Error creating progress bar. Incorrect Style?. Please raise issue to developers of this tool"));
-
-    let number_of_columns_to_compare = args.number_of_columns;
-    let mut columns_to_compare = vec![];
-    for i in 1..first_file_cols.len() {
-        let column_name = &first_file_cols[i];
-        columns_to_compare.push(column_name);
-
-        if columns_to_compare.len() == number_of_columns_to_compare
-            || i == first_file_cols.len() - 1
-        {
-            let first_data_frame = get_sorted_data_frame_for_columns(
-                &first_file_lf,
-                sorting_column,
-                &columns_to_compare,
-            );
-
-            let second_data_frame = get_sorted_data_frame_for_columns(
-                &second_file_lf,
-                sorting_column,
-                &columns_to_compare,
-            );
-
-            if !first_data_frame.equals_missing(&second_data_frame) {
-                let column_names = columns_to_compare
-                    .iter()
-                    .copied()
-                    .map(String::as_str)
-                    .collect::<Vec<_>>()
-                    .join(" | ");
+    debug!("{:?}", args);
+
+    if let Some(Command::Serve {
+        host,
+        port,
+        root,
+        job_ttl,
+        workers,
+    }) = &args.command
+    {
+        let workers = workers.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, |parallelism| parallelism.get())
+        });
+        #[cfg(feature = "serve")]
+        let served = csv_compare::serve::run(
+            (host, *port),
+            workers,
+            root.as_deref(),
+            std::time::Duration::from_secs(*job_ttl),
+        );
+        #[cfg(not(feature = "serve"))]
+        let served: Result<(), String> = {
+            let _ = (root, job_ttl);
+            Err(format!(
+                "Can't listen on {host}:{port}: the HTTP service is not included in this build (feature serve)"
+            ))
+        };
+        if let Err(error) = served {
+            eprintln!("{}", error.fail_status());
+            exit(args.exit_code_failed);
+        }
+        exit(0);
+    }
 
-                println!(
-                    "{}: {} \n {} \n {}",
-                    "FILES ARE DIFFERENT".red(),
-                    "Values for column(s)".red(),
-                    column_names.red().bold(),
-                    "are different".red()
+    let printer = Printer::new(args.quiet, args.summary_only, !args.no_pager);
+    if let Some(Command::Canonicalize { file, output }) = &args.command {
+        match canonicalize::canonicalize_file(&args, file, output.as_deref()) {
+            Ok(rows) => {
+                if let Some(output) = output {
+                    printer.info(format!("{} rows written to {}", rows, output));
+                }
+                exit(0);
+            }
+            Err(error) => {
+                eprintln!("{}", format!("{:#}", error).fail_status());
+                exit(args.exit_code_failed);
+            }
+        }
+    }
+    if let Some(Command::Hash { file, key, json }) = &args.command {
+        match hash::hash_file(&args, file, key.as_deref()) {
+            Ok(content_hash) => {
+                if *json {
+                    printer.result(
+                        serde_json::to_string_pretty(&content_hash)
+                            .expect("Content hash is always serializable"),
+                    );
+                } else {
+                    printer.result(format!(
+                        "sha256:{}  {} ({} rows)",
+                        content_hash.hash, file, content_hash.rows
+                    ));
+                }
+                printer.summary(format!("sha256:{}", content_hash.hash));
+                printer.finish();
+                exit(0);
+            }
+            Err(error) => {
+                eprintln!("{}", format!("{:#}", error).fail_status());
+                exit(args.exit_code_failed);
+            }
+        }
+    }
+    if let Some(Command::Bench {
+        file1,
+        file2,
+        columns,
+        partitions,
+        repeat,
+    }) = &args.command
+    {
+        let grid = bench::settings_grid(columns, partitions);
+        let results = bench::run(&args, &printer, file1, file2, &grid, *repeat);
+        printer.result(bench::results_table(&results));
+        if let Some(fastest) = results.first() {
+            printer.summary(format!(
+                "FASTEST: {} ({:.3}s)",
+                fastest.settings,
+                fastest.fastest().as_secs_f64()
+            ));
+        }
+        printer.finish();
+        exit(0);
+    }
+    if let Some(Command::ReportSchema) = &args.command {
+        println!("{}", report::json_schema());
+        exit(0);
+    }
+    if let Some(Command::MergeReports { reports, output }) = &args.command {
+        let merged = merge::merge_reports(reports).unwrap_or_else(|error| {
+            eprintln!("{}", format!("{:#}", error).fail_status());
+            exit(args.exit_code_failed);
+        });
+        printer.result(merged.reports_table());
+        if let Some(output) = output {
+            let content = if output.ends_with(".html") {
+                merged.to_html()
+            } else {
+                serde_json::to_string_pretty(&merged).expect("Merged report is always serializable")
+            };
+            if let Err(error) = std::fs::write(output, content) {
+                eprintln!(
+                    "{}",
+                    format!("Couldn't write {}: {}", output, error).fail_status()
                 );
-
-                exit(3);
+                exit(args.exit_code_failed);
             }
-            progress_bar.inc(columns_to_compare.len() as u64);
-            columns_to_compare.clear();
         }
+        printer.summary(format!(
+            "{} reports, {} different rows",
+            merged.reports, merged.total_differing_rows
+        ));
+        printer.finish();
+        exit(if merged.all_identical() || args.exit_zero {
+            0
+        } else {
+            args.exit_code_values
+        });
     }
-    progress_bar.finish();
-
-    println!(
-        "Files {} and {} {} {}",
-        first_file_path.bold(),
-        second_file_path.bold(),
-        "ARE IDENTICAL WHEN SORTED BY COLUMN:".green(),
-        sorting_column.green()
-    );
-}
-
-fn assert_both_frames_have_same_row_num(
-    first_lazy_frame: &LazyFrame,
-    second_lazy_frame: &LazyFrame,
-) -> u32 {
-    let first_row_num = get_rows_num(first_lazy_frame);
-    let second_row_num = get_rows_num(second_lazy_frame);
-
-    if first_row_num != second_row_num {
-        println!(
-            "{}: {} {} <> {}",
-            "FILES ARE DIFFERENT".red(),
-            "Different number of rows".red(),
-            first_row_num.to_string(),
-            second_row_num.to_string()
-        );
-
-        exit(4);
+    if let Some(Command::Dirs {
+        first_dir,
+        second_dir,
+        pairs,
+        jobs,
+    }) = &args.command
+    {
+        let file_pairs = dirs::pair_files(first_dir, second_dir, pairs).unwrap_or_else(|error| {
+            eprintln!("{}", format!("{:#}", error).fail_status());
+            exit(args.exit_code_failed);
+        });
+        let results =
+            dirs::compare_pairs(&args, &printer, first_dir, second_dir, file_pairs, *jobs);
+        printer.result(dirs::pairs_table(&results));
+        let compared = results
+            .iter()
+            .filter(|result| result.report.is_some())
+            .count();
+        printer.summary(format!(
+            "{} pairs compared, {} files without pair",
+            compared,
+            results.len() - compared
+        ));
+        printer.finish();
+        exit(dirs::exit_code(&results, &args));
+    }
+    if let Some(Command::Validate {
+        file,
+        expected_columns,
+        json,
+    }) = &args.command
+    {
+        let validation = validate::validate_file(file, args.separator, expected_columns);
+        if *json {
+            printer.result(
+                serde_json::to_string_pretty(&validation)
+                    .expect("Validation report is always serializable"),
+            );
+        } else if validation.is_valid() {
+            printer.result(format!(
+                "{} {}: {} rows, {} columns",
+                file.bold(),
+                tr("IS VALID").pass_status(),
+                validation.rows,
+                validation.columns.len()
+            ));
+        } else {
+            printer.result(format!(
+                "{} {}: {} error(s)\n{}",
+                file.bold(),
+                tr("IS NOT VALID").fail_status(),
+                validation.error_count,
+                validation.errors_table()
+            ));
+        }
+        printer.summary(if validation.is_valid() {
+            "VALID".to_string()
+        } else {
+            format!("INVALID: {} error(s)", validation.error_count)
+        });
+        printer.finish();
+        let exit_code = if validation.is_valid() || args.exit_zero {
+            0
+        } else {
+            args.exit_code_invalid
+        };
+        exit(exit_code);
     }
 
-    return first_row_num;
-}
-
-fn assert_both_frames_are_comparable(
-    first_file_cols: &[String],
-    second_file_cols: &[String],
-    is_strict_order: bool,
-) {
-    let have_same_columns = if is_strict_order {
-        first_file_cols.eq(second_file_cols)
-    } else {
-        // Convert the vectors into sets to ignore the order
-        let set1: HashSet<_> = first_file_cols.iter().collect();
-        let set2: HashSet<_> = second_file_cols.iter().collect();
-        set1 == set2
+    let started = Instant::now();
+    let report = match &args.command {
+        None => match &args.baseline_report {
+            Some(baseline_report) => baseline::compare_with_baseline(
+                &args,
+                &printer,
+                baseline_report,
+                args.file1.as_deref().expect("First file is required"),
+            ),
+            None => compare_files(
+                &args,
+                &printer,
+                args.file1.as_deref().expect("First file is required"),
+                args.file2.as_deref().expect("Second file is required"),
+            ),
+        },
+        Some(Command::Git { path, rev }) => {
+            let (first_version, second_version) = git::get_file_versions(path, rev.as_deref())
+                .unwrap_or_else(|error| {
+                    eprintln!("{}", error.fail_status());
+                    exit(args.exit_code_failed);
+                });
+            compare_files(
+                &args,
+                &printer,
+                &first_version.path(),
+                &second_version.path(),
+            )
+        }
+        Some(Command::Difftool { paths }) => {
+            let (first_path, second_path) =
+                git::get_difftool_paths(paths).unwrap_or_else(|error| {
+                    eprintln!("{}", error.fail_status());
+                    exit(args.exit_code_failed);
+                });
+            compare_files(&args, &printer, &first_path, &second_path)
+        }
+        Some(Command::Serve { .. })
+        | Some(Command::Dirs { .. })
+        | Some(Command::Validate { .. })
+        | Some(Command::Canonicalize { .. })
+        | Some(Command::Hash { .. })
+        | Some(Command::MergeReports { .. })
+        | Some(Command::Bench { .. })
+        | Some(Command::ReportSchema) => {
+            unreachable!("Command already handled")
+        }
     };
-
-    if !have_same_columns {
-        println!(
-            "{}: {} => [{}] != [{}]",
-            "FILES ARE DIFFERENT".red(),
-            "Different columns".red(),
-            first_file_cols.join(",").bold().yellow(),
-            second_file_cols.join(",").bold().blue()
+    if let Some(metrics_path) = &args.metrics_file {
+        let metrics = metrics::comparison_metrics(
+            &report,
+            args.file1.as_deref().unwrap_or_default(),
+            args.file2.as_deref().unwrap_or_default(),
+            started.elapsed(),
         );
-        if is_strict_order {
-            println!(
-                "{} {} {}",
-                "Hint:",
-                "--strict-order".bold(),
-                "flag is active"
+        if let Err(error) = metrics::write_metrics_file(metrics_path, &metrics) {
+            eprintln!(
+                "{}",
+                format!("Couldn't write metrics {}: {}", metrics_path, error).fail()
             );
         }
-        exit(2);
     }
-}
-
-fn get_lazy_frame(file_path: &str, delimiter: char) -> LazyFrame {
-    LazyCsvReader::new(file_path)
-        .has_header(true)
-        .with_infer_schema_length(Some(0))
-        .with_separator(delimiter as u8)
-        .finish()
-        .expect(format!("Couldn't open file {file_path}").as_str())
-}
-
-fn get_column_names(lazy_frame: &LazyFrame) -> Vec<String> {
-    let schema = lazy_frame
-        .clone()
-        .limit(1)
-        .collect()
-        .expect("Couldn't parse first CSV file")
-        .schema();
-
-    schema.get_names().into_vec()
-}
-
-fn get_sorted_data_frame_for_columns(
-    lazy_frame: &LazyFrame,
-    sorting_by_column: &String,
-    columns: &Vec<&String>,
-) -> DataFrame {
-    let mut all_columns = vec![col(sorting_by_column)];
-    for next_column in columns {
-        all_columns.push(col(next_column));
+    if let Some(report_path) = &args.report_json {
+        let json = serde_json::to_string_pretty(&report).expect("Report is always serializable");
+        if let Err(error) = std::fs::write(report_path, json) {
+            eprintln!(
+                "{}",
+                format!("Couldn't write report {}: {}", report_path, error).fail()
+            );
+        }
     }
-
-    lazy_frame
-        .clone()
-        .select(all_columns)
-        .sort(sorting_by_column, SortOptions::default())
-        .collect()
-        .expect(format!("Couldn't sort by column {sorting_by_column}",).as_str())
-}
-
-fn get_rows_num(lazy_frame: &LazyFrame) -> u32 {
-    let first_column_name = get_column_names(&lazy_frame.clone())[0].to_string();
-    return lazy_frame
-        .clone()
-        .select([col(first_column_name.as_str())])
-        .collect()
-        .expect("Error when counting the rows of the CSV file")
-        .shape()
-        .0 as u32;
+    if args.timings {
+        if !report.batches.is_empty() {
+            printer.result(report.timings_table());
+        }
+        printer.result(report.resources_table());
+    }
+    printer.finish();
+    let exit_code = report.result.exit_code(&args);
+    if let Some(notify_url) = &args.notify_url {
+        let notification = notify::Notification::new(
+            &report,
+            args.file1.as_deref().unwrap_or_default(),
+            args.file2.as_deref().unwrap_or_default(),
+            exit_code,
+        );
+        if let Err(error) = notify::notify(notify_url, args.notify_format, &notification) {
+            eprintln!("{}", error.fail_status());
+        }
+    }
+    logging::shutdown();
+    exit(exit_code);
 }
@@ -0,0 +1,287 @@
+use crate::report::{ComparisonReport, ComparisonResult, REPORT_SCHEMA_VERSION};
+use crate::table;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Columns with more different rows listed in the roll-up
+const WORST_COLUMNS: usize = 10;
+
+/// Roll-up of many comparison reports
+#[derive(Debug, Serialize)]
+pub struct MergedReport {
+    pub reports: usize,
+    /// How many comparisons ended with each result
+    pub results: BTreeMap<ComparisonResult, usize>,
+    pub total_rows: u64,
+    pub total_differing_rows: u64,
+    /// One entry per report, the ones with more different rows first
+    pub entries: Vec<ReportEntry>,
+    /// Columns with more different rows across all the reports
+    pub worst_columns: Vec<ColumnEntry>,
+}
+
+/// Outcome of a single report
+#[derive(Debug, Serialize)]
+pub struct ReportEntry {
+    /// File name of the report
+    pub name: String,
+    pub result: ComparisonResult,
+    pub rows: u64,
+    pub differing_rows: u64,
+    pub different_columns: usize,
+    pub error: Option<String>,
+}
+
+/// A different column of one of the reports
+#[derive(Debug, Serialize)]
+pub struct ColumnEntry {
+    pub report: String,
+    pub column: String,
+    pub differing_rows: u64,
+}
+
+/// Reads the JSON reports (written with --report-json) and combines them
+pub fn merge_reports(report_paths: &[String]) -> Result<MergedReport> {
+    let mut merged = MergedReport {
+        reports: 0,
+        results: BTreeMap::new(),
+        total_rows: 0,
+        total_differing_rows: 0,
+        entries: vec![],
+        worst_columns: vec![],
+    };
+    for report_path in report_paths {
+        let file = File::open(report_path)
+            .with_context(|| format!("Couldn't open report {report_path}"))?;
+        let report: ComparisonReport = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Couldn't parse report {report_path}"))?;
+        if report.schema_version.0 > REPORT_SCHEMA_VERSION {
+            bail!(
+                "Report {report_path} has schema version {}, but only up to {REPORT_SCHEMA_VERSION} is supported",
+                report.schema_version.0
+            );
+        }
+        let name = Path::new(report_path)
+            .file_name()
+            .map_or(report_path.to_string(), |name| {
+                name.to_string_lossy().to_string()
+            });
+        let differing_rows: u64 = report
+            .column_differences
+            .iter()
+            .map(|difference| difference.differing_rows)
+            .sum();
+
+        merged.reports += 1;
+        *merged.results.entry(report.result).or_insert(0) += 1;
+        // Every shard of a comparison has the same rows
+        if report.shard.is_none() || report.shard.is_some_and(|shard| shard.is_first()) {
+            merged.total_rows += report.rows;
+        }
+        merged.total_differing_rows += differing_rows;
+        merged
+            .worst_columns
+            .extend(
+                report
+                    .column_differences
+                    .iter()
+                    .map(|difference| ColumnEntry {
+                        report: name.to_string(),
+                        column: difference.column.to_string(),
+                        differing_rows: difference.differing_rows,
+                    }),
+            );
+        merged.entries.push(ReportEntry {
+            name,
+            result: report.result,
+            rows: report.rows,
+            differing_rows,
+            different_columns: report.column_differences.len(),
+            error: report.error,
+        });
+    }
+    // Ties are sorted by name, so the roll-up doesn't depend on the order the reports were listed in
+    merged.entries.sort_by(|a, b| {
+        b.differing_rows
+            .cmp(&a.differing_rows)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    merged.worst_columns.sort_by(|a, b| {
+        b.differing_rows
+            .cmp(&a.differing_rows)
+            .then_with(|| a.report.cmp(&b.report))
+            .then_with(|| a.column.cmp(&b.column))
+    });
+    merged.worst_columns.truncate(WORST_COLUMNS);
+    Ok(merged)
+}
+
+impl MergedReport {
+    /// Whether all the comparisons found identical (or empty) files, maybe with the columns in a different order
+    pub fn all_identical(&self) -> bool {
+        self.results.keys().all(|result| {
+            matches!(
+                result,
+                ComparisonResult::Identical
+                    | ComparisonResult::Empty
+                    | ComparisonResult::DifferentColumnOrder
+            )
+        })
+    }
+
+    /// Table with the result of every report, the ones with more different rows first
+    pub fn reports_table(&self) -> String {
+        let mut table = table::new_table(vec![
+            "Report",
+            "Result",
+            "Rows",
+            "Different rows",
+            "Different columns",
+        ]);
+        for entry in &self.entries {
+            table.add_row(vec![
+                table::truncate(&entry.name),
+                entry.result.name().to_string(),
+                entry.rows.to_string(),
+                entry.differing_rows.to_string(),
+                entry.different_columns.to_string(),
+            ]);
+        }
+        table.add_row(vec![
+            "Total".to_string(),
+            "".to_string(),
+            self.total_rows.to_string(),
+            self.total_differing_rows.to_string(),
+            "".to_string(),
+        ]);
+        table::align_right(&mut table, &[2, 3, 4]);
+        table.to_string()
+    }
+
+    /// Page with the totals, the result of every report and the worst columns
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>csv-compare reports</title></head>\n<body>\n",
+        );
+        html.push_str(&format!(
+            "<h1>{} reports</h1>\n<p>{} rows compared, {} different rows</p>\n<ul>\n",
+            self.reports, self.total_rows, self.total_differing_rows
+        ));
+        for (result, count) in &self.results {
+            html.push_str(&format!("<li>{}: {}</li>\n", result.name(), count));
+        }
+        html.push_str("</ul>\n<h2>Reports</h2>\n<table>\n<tr><th>Report</th><th>Result</th><th>Rows</th><th>Different rows</th><th>Different columns</th><th>Error</th></tr>\n");
+        for entry in &self.entries {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&entry.name),
+                entry.result.name(),
+                entry.rows,
+                entry.differing_rows,
+                entry.different_columns,
+                escape_html(entry.error.as_deref().unwrap_or(""))
+            ));
+        }
+        html.push_str("</table>\n<h2>Worst columns</h2>\n<table>\n<tr><th>Report</th><th>Column</th><th>Different rows</th></tr>\n");
+        for column in &self.worst_columns {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&column.report),
+                escape_html(&column.column),
+                column.differing_rows
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{ColumnDifference, SchemaVersion};
+    use tempfile::TempDir;
+
+    fn difference(column: &str, differing_rows: u64) -> ColumnDifference {
+        ColumnDifference {
+            column: column.to_string(),
+            differing_rows,
+            values_changed: differing_rows,
+            null_on_one_side: 0,
+            whitespace_only: 0,
+            case_only: 0,
+            dtype_mismatch: false,
+            samples: vec![],
+        }
+    }
+
+    fn write(directory: &TempDir, name: &str, report: &ComparisonReport) -> String {
+        let path = directory.path().join(name);
+        std::fs::write(&path, serde_json::to_string(report).unwrap()).unwrap();
+        path.display().to_string()
+    }
+
+    #[test]
+    fn combines_the_reports() {
+        let directory = TempDir::new().unwrap();
+        let paths = [
+            write(
+                &directory,
+                "identical.json",
+                &ComparisonReport {
+                    rows: 10,
+                    ..Default::default()
+                },
+            ),
+            write(
+                &directory,
+                "different.json",
+                &ComparisonReport {
+                    result: ComparisonResult::DifferentValues,
+                    rows: 20,
+                    column_differences: vec![difference("name", 2), difference("age", 5)],
+                    ..Default::default()
+                },
+            ),
+        ];
+        let merged = merge_reports(&paths).unwrap();
+        assert_eq!(merged.reports, 2);
+        assert_eq!((merged.total_rows, merged.total_differing_rows), (30, 7));
+        assert_eq!(merged.entries[0].name, "different.json");
+        assert_eq!(merged.entries[0].different_columns, 2);
+        assert_eq!(merged.worst_columns[0].column, "age");
+        assert_eq!(merged.results[&ComparisonResult::Identical], 1);
+        assert!(!merged.all_identical());
+        assert!(merged.to_html().contains("<td>different.json</td>"));
+    }
+
+    #[test]
+    fn rejects_newer_reports() {
+        let directory = TempDir::new().unwrap();
+        let path = write(
+            &directory,
+            "future.json",
+            &ComparisonReport {
+                schema_version: SchemaVersion(REPORT_SCHEMA_VERSION + 1),
+                ..Default::default()
+            },
+        );
+        assert!(merge_reports(&[path]).is_err());
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(escape_html("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,243 @@
+use crate::report::{ComparisonReport, ComparisonResult};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Metrics of a single comparison in the Prometheus text format, for the textfile collector of the node exporter
+pub fn comparison_metrics(
+    report: &ComparisonReport,
+    first_file: &str,
+    second_file: &str,
+    elapsed: Duration,
+) -> String {
+    let files = format!(
+        "file1=\"{}\",file2=\"{}\"",
+        escape_label(first_file),
+        escape_label(second_file)
+    );
+    let mut metrics = String::new();
+    add_metric(
+        &mut metrics,
+        "csv_compare_rows_compared",
+        "gauge",
+        "Rows of each file in the last comparison",
+        &[(files.to_string(), report.rows as f64)],
+    );
+    add_metric(
+        &mut metrics,
+        "csv_compare_differing_rows",
+        "gauge",
+        "Rows with a different value, added up for all the columns",
+        &[(files.to_string(), differing_rows(report) as f64)],
+    );
+    add_metric(
+        &mut metrics,
+        "csv_compare_duration_seconds",
+        "gauge",
+        "Time spent in the last comparison",
+        &[(files.to_string(), elapsed.as_secs_f64())],
+    );
+    add_metric(
+        &mut metrics,
+        "csv_compare_result",
+        "gauge",
+        "Result of the last comparison (1 for the current result)",
+        &ComparisonResult::ALL
+            .iter()
+            .map(|result| {
+                (
+                    format!("{},result=\"{}\"", files, result.name()),
+                    if *result == report.result { 1.0 } else { 0.0 },
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    add_metric(
+        &mut metrics,
+        "csv_compare_column_differing_rows",
+        "gauge",
+        "Rows with a different value in each different column",
+        &report
+            .column_differences
+            .iter()
+            .map(|difference| {
+                (
+                    format!("{},column=\"{}\"", files, escape_label(&difference.column)),
+                    difference.differing_rows as f64,
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    metrics
+}
+
+/// Writes the metrics to a temporary file renamed at the end, so the collector never reads half a file
+pub fn write_metrics_file(file_path: &str, metrics: &str) -> std::io::Result<()> {
+    let temporary_path = Path::new(file_path).with_extension("prom.tmp");
+    std::fs::write(&temporary_path, metrics)?;
+    std::fs::rename(&temporary_path, file_path)
+}
+
+/// Totals of the comparisons run by the HTTP service, published in /metrics
+#[derive(Debug, Default)]
+pub struct ServiceMetrics {
+    comparisons: BTreeMap<ComparisonResult, u64>,
+    failed_comparisons: u64,
+    rows_compared: u64,
+    differing_rows: u64,
+    duration_seconds: f64,
+}
+
+impl ServiceMetrics {
+    pub fn record(&mut self, report: &ComparisonReport, elapsed: Duration) {
+        *self.comparisons.entry(report.result).or_insert(0) += 1;
+        self.rows_compared += report.rows;
+        self.differing_rows += differing_rows(report);
+        self.duration_seconds += elapsed.as_secs_f64();
+    }
+
+    pub fn record_failure(&mut self, elapsed: Duration) {
+        self.failed_comparisons += 1;
+        self.duration_seconds += elapsed.as_secs_f64();
+    }
+
+    /// Metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut metrics = String::new();
+        add_metric(
+            &mut metrics,
+            "csv_compare_comparisons_total",
+            "counter",
+            "Comparisons finished by result",
+            &self
+                .comparisons
+                .iter()
+                .map(|(result, count)| (format!("result=\"{}\"", result.name()), *count as f64))
+                .collect::<Vec<_>>(),
+        );
+        add_metric(
+            &mut metrics,
+            "csv_compare_failed_comparisons_total",
+            "counter",
+            "Comparisons that failed unexpectedly",
+            &[(String::new(), self.failed_comparisons as f64)],
+        );
+        add_metric(
+            &mut metrics,
+            "csv_compare_rows_compared_total",
+            "counter",
+            "Rows compared",
+            &[(String::new(), self.rows_compared as f64)],
+        );
+        add_metric(
+            &mut metrics,
+            "csv_compare_differing_rows_total",
+            "counter",
+            "Rows with a different value, added up for all the columns",
+            &[(String::new(), self.differing_rows as f64)],
+        );
+        add_metric(
+            &mut metrics,
+            "csv_compare_duration_seconds_total",
+            "counter",
+            "Time spent comparing files",
+            &[(String::new(), self.duration_seconds)],
+        );
+        metrics
+    }
+}
+
+fn add_metric(metrics: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(metrics, "# HELP {name} {help}");
+    let _ = writeln!(metrics, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(metrics, "{name} {value}");
+        } else {
+            let _ = writeln!(metrics, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+fn differing_rows(report: &ComparisonReport) -> u64 {
+    report
+        .column_differences
+        .iter()
+        .map(|difference| difference.differing_rows)
+        .sum()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ComparisonReport {
+        ComparisonReport {
+            result: ComparisonResult::DifferentValues,
+            rows: 10,
+            column_differences: vec![crate::report::ColumnDifference {
+                column: "na\"me".to_string(),
+                differing_rows: 3,
+                values_changed: 3,
+                null_on_one_side: 0,
+                whitespace_only: 0,
+                case_only: 0,
+                dtype_mismatch: false,
+                samples: vec![],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn writes_the_metrics_of_a_comparison() {
+        let metrics = comparison_metrics(&report(), "a.csv", "b.csv", Duration::from_millis(1500));
+        assert!(metrics.contains("# TYPE csv_compare_rows_compared gauge\n"));
+        assert!(metrics.contains("csv_compare_rows_compared{file1=\"a.csv\",file2=\"b.csv\"} 10\n"));
+        assert!(
+            metrics.contains("csv_compare_duration_seconds{file1=\"a.csv\",file2=\"b.csv\"} 1.5\n")
+        );
+        assert!(metrics.contains(
+            "csv_compare_result{file1=\"a.csv\",file2=\"b.csv\",result=\"different_values\"} 1\n"
+        ));
+        assert!(metrics.contains(
+            "csv_compare_result{file1=\"a.csv\",file2=\"b.csv\",result=\"identical\"} 0\n"
+        ));
+        assert!(metrics.contains(
+            "csv_compare_column_differing_rows{file1=\"a.csv\",file2=\"b.csv\",column=\"na\\\"me\"} 3\n"
+        ));
+    }
+
+    #[test]
+    fn adds_up_the_comparisons_of_the_service() {
+        let mut service_metrics = ServiceMetrics::default();
+        service_metrics.record(&report(), Duration::from_secs(1));
+        service_metrics.record(&report(), Duration::from_secs(2));
+        service_metrics.record_failure(Duration::from_secs(1));
+        let metrics = service_metrics.render();
+        assert!(metrics.contains("csv_compare_comparisons_total{result=\"different_values\"} 2\n"));
+        assert!(metrics.contains("csv_compare_failed_comparisons_total 1\n"));
+        assert!(metrics.contains("csv_compare_rows_compared_total 20\n"));
+        assert!(metrics.contains("csv_compare_differing_rows_total 6\n"));
+        assert!(metrics.contains("csv_compare_duration_seconds_total 4\n"));
+    }
+
+    #[test]
+    fn replaces_the_metrics_file() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let path = directory.path().join("csv_compare.prom");
+        let path = path.to_str().unwrap();
+        write_metrics_file(path, "first").unwrap();
+        write_metrics_file(path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "second");
+        assert_eq!(std::fs::read_dir(directory.path()).unwrap().count(), 1);
+    }
+}
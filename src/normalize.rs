@@ -0,0 +1,384 @@
+use polars::prelude::{col, DataType, GetOutput, IntoSeries, LazyFrame, Utf8Chunked};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Replaces the empty and whitespace-only values of the column with nulls, so null, "" and " "
+/// are all equal when comparing it
+pub fn nullify_blanks(lazy_frame: LazyFrame, column: &str) -> LazyFrame {
+    lazy_frame.with_column(col(column).map(
+        |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let values: Utf8Chunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.filter(|value| !value.trim().is_empty()))
+                .collect();
+            Ok(Some(values.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Utf8),
+    ))
+}
+
+/// Rewrites the JSON values of the column in a canonical form (keys sorted, no whitespace) without
+/// the ignored paths, so they are compared structurally. Values that aren't valid JSON are kept as they are
+pub fn canonicalize_json(
+    lazy_frame: LazyFrame,
+    column: &str,
+    ignored_paths: &[String],
+) -> LazyFrame {
+    let ignored_paths: Vec<Vec<String>> = ignored_paths
+        .iter()
+        .map(|path| {
+            path.trim_start_matches("$.")
+                .split('.')
+                .map(str::to_string)
+                .collect()
+        })
+        .collect();
+    lazy_frame.with_column(col(column).map(
+        move |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let values: Utf8Chunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.map(|value| canonical_json(value, &ignored_paths)))
+                .collect();
+            Ok(Some(values.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Utf8),
+    ))
+}
+
+fn canonical_json(text: &str, ignored_paths: &[Vec<String>]) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(mut value) => {
+            for path in ignored_paths {
+                remove_path(&mut value, path);
+            }
+            value.to_string()
+        }
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Removes the value at the path. A `*` matches every key of an object or element of an array
+fn remove_path(value: &mut Value, path: &[String]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(object) if rest.is_empty() => {
+            if first == "*" {
+                object.clear();
+            } else {
+                object.remove(first);
+            }
+        }
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                if first == "*" || first == key {
+                    remove_path(child, rest);
+                }
+            }
+        }
+        Value::Array(elements) if first == "*" => {
+            if rest.is_empty() {
+                elements.clear();
+            } else {
+                for element in elements {
+                    remove_path(element, rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How the elements of a list column are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListComparison {
+    /// Same elements in any order, repetitions included
+    Sorted,
+    /// Same distinct elements in any order
+    Set,
+}
+
+/// Column whose values are lists of elements joined by a delimiter, written as `column[:delimiter[:sorted|set]]`
+#[derive(Debug, Clone)]
+pub struct ListColumn {
+    pub column: String,
+    pub delimiter: String,
+    pub comparison: ListComparison,
+}
+
+impl FromStr for ListColumn {
+    type Err = String;
+
+    fn from_str(list_column: &str) -> Result<Self, Self::Err> {
+        let mut parts = list_column.splitn(3, ':');
+        let column = parts.next().unwrap_or_default().trim().to_string();
+        if column.is_empty() {
+            return Err(format!(
+                "Expected column[:delimiter[:sorted|set]], found {list_column}"
+            ));
+        }
+        let delimiter = match parts.next() {
+            Some(delimiter) if !delimiter.is_empty() => delimiter.to_string(),
+            _ => ";".to_string(),
+        };
+        let comparison = match parts.next() {
+            None | Some("sorted") => ListComparison::Sorted,
+            Some("set") => ListComparison::Set,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown list comparison {other}, expected sorted or set"
+                ))
+            }
+        };
+        Ok(ListColumn {
+            column,
+            delimiter,
+            comparison,
+        })
+    }
+}
+
+/// Sorts (and deduplicates when compared as sets) the elements of the lists of the column
+pub fn sort_lists(lazy_frame: LazyFrame, list_column: &ListColumn) -> LazyFrame {
+    let delimiter = list_column.delimiter.to_string();
+    let comparison = list_column.comparison;
+    lazy_frame.with_column(col(&list_column.column).map(
+        move |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let values: Utf8Chunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.map(|value| sorted_list(value, &delimiter, comparison)))
+                .collect();
+            Ok(Some(values.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Utf8),
+    ))
+}
+
+fn sorted_list(text: &str, delimiter: &str, comparison: ListComparison) -> String {
+    let mut elements: Vec<&str> = text
+        .split(delimiter)
+        .map(str::trim)
+        .filter(|element| !element.is_empty())
+        .collect();
+    elements.sort_unstable();
+    if comparison == ListComparison::Set {
+        elements.dedup();
+    }
+    elements.join(delimiter)
+}
+
+/// How the values of a binary column are encoded as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryEncoding {
+    /// Standard or URL-safe base64, with or without padding and line breaks
+    Base64,
+    /// Hexadecimal digits in any case
+    Hex,
+}
+
+/// What is compared of the decoded bytes of a binary column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryComparison {
+    /// Every byte
+    Bytes,
+    /// Only the number of bytes
+    Size,
+    /// Only the SHA-256 of the bytes, shorter to show for large payloads
+    Hash,
+}
+
+/// Column with binary values encoded as text, written as `column:base64|hex[:bytes|size|hash]`
+#[derive(Debug, Clone)]
+pub struct BinaryColumn {
+    pub column: String,
+    pub encoding: BinaryEncoding,
+    pub comparison: BinaryComparison,
+}
+
+impl FromStr for BinaryColumn {
+    type Err = String;
+
+    fn from_str(binary_column: &str) -> Result<Self, Self::Err> {
+        let mut parts = binary_column.splitn(3, ':');
+        let column = parts.next().unwrap_or_default().trim().to_string();
+        let encoding = match parts.next() {
+            Some("base64") => BinaryEncoding::Base64,
+            Some("hex") => BinaryEncoding::Hex,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown binary encoding {other}, expected base64 or hex"
+                ))
+            }
+            None => {
+                return Err(format!(
+                    "Expected column:base64|hex[:bytes|size|hash], found {binary_column}"
+                ))
+            }
+        };
+        if column.is_empty() {
+            return Err(format!(
+                "Expected column:base64|hex[:bytes|size|hash], found {binary_column}"
+            ));
+        }
+        let comparison = match parts.next() {
+            None | Some("bytes") => BinaryComparison::Bytes,
+            Some("size") => BinaryComparison::Size,
+            Some("hash") => BinaryComparison::Hash,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown binary comparison {other}, expected bytes, size or hash"
+                ))
+            }
+        };
+        Ok(BinaryColumn {
+            column,
+            encoding,
+            comparison,
+        })
+    }
+}
+
+/// Decodes the values of the column and rewrites them as lowercase hexadecimal, their size or their hash,
+/// so the same bytes encoded differently (line wrapping, padding, case) are equal. Values that can't be decoded
+/// are kept as they are
+pub fn decode_binary(lazy_frame: LazyFrame, binary_column: &BinaryColumn) -> LazyFrame {
+    let encoding = binary_column.encoding;
+    let comparison = binary_column.comparison;
+    lazy_frame.with_column(col(&binary_column.column).map(
+        move |series| {
+            let text = series.cast(&DataType::Utf8)?;
+            let values: Utf8Chunked = text
+                .utf8()?
+                .into_iter()
+                .map(|value| value.map(|value| decoded_value(value, encoding, comparison)))
+                .collect();
+            Ok(Some(values.with_name(series.name()).into_series()))
+        },
+        GetOutput::from_type(DataType::Utf8),
+    ))
+}
+
+fn decoded_value(text: &str, encoding: BinaryEncoding, comparison: BinaryComparison) -> String {
+    let bytes = match encoding {
+        BinaryEncoding::Base64 => decode_base64(text),
+        BinaryEncoding::Hex => decode_hex(text),
+    };
+    let Some(bytes) = bytes else {
+        return text.to_string();
+    };
+    match comparison {
+        BinaryComparison::Bytes => to_hex(&bytes),
+        BinaryComparison::Size => format!("{} bytes", bytes.len()),
+        BinaryComparison::Hash => format!("sha256:{}", to_hex(&Sha256::digest(bytes))),
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for character in text.bytes().filter(|byte| !byte.is_ascii_whitespace()) {
+        let sextet = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | sextet as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .trim()
+        .trim_start_matches("0x")
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if digits.len() % 2 != 0 || !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_binary_columns() {
+        let binary_column: BinaryColumn = "payload:base64".parse().unwrap();
+        assert_eq!(binary_column.column, "payload");
+        assert_eq!(binary_column.encoding, BinaryEncoding::Base64);
+        assert_eq!(binary_column.comparison, BinaryComparison::Bytes);
+        let binary_column: BinaryColumn = "thumbnail:hex:hash".parse().unwrap();
+        assert_eq!(binary_column.encoding, BinaryEncoding::Hex);
+        assert_eq!(binary_column.comparison, BinaryComparison::Hash);
+        let binary_column: BinaryColumn = "blob:hex:size".parse().unwrap();
+        assert_eq!(binary_column.comparison, BinaryComparison::Size);
+    }
+
+    #[test]
+    fn rejects_invalid_binary_columns() {
+        for text in ["payload", ":base64", "payload:base32", "payload:hex:md5"] {
+            assert!(
+                text.parse::<BinaryColumn>().is_err(),
+                "{text} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_base64_wrapped_or_unpadded() {
+        let decoded = |text| decoded_value(text, BinaryEncoding::Base64, BinaryComparison::Bytes);
+        assert_eq!(decoded("aGVsbG8="), "68656c6c6f");
+        assert_eq!(decoded("aGVs\nbG8="), "68656c6c6f");
+        assert_eq!(decoded("aGVsbG8"), "68656c6c6f");
+        assert_eq!(decoded(""), "");
+        assert_eq!(decoded("not base64!"), "not base64!");
+    }
+
+    #[test]
+    fn decodes_hex_in_any_case() {
+        let decoded = |text| decoded_value(text, BinaryEncoding::Hex, BinaryComparison::Bytes);
+        assert_eq!(decoded("0xDEADbeef"), "deadbeef");
+        assert_eq!(decoded("de ad"), "dead");
+        assert_eq!(decoded("abc"), "abc");
+        assert_eq!(decoded("+f"), "+f");
+    }
+
+    #[test]
+    fn compares_size_or_hash_of_large_payloads() {
+        assert_eq!(
+            decoded_value("aGVsbG8=", BinaryEncoding::Base64, BinaryComparison::Size),
+            "5 bytes"
+        );
+        assert_eq!(
+            decoded_value("68656c6c6f", BinaryEncoding::Hex, BinaryComparison::Hash),
+            decoded_value("aGVsbG8=", BinaryEncoding::Base64, BinaryComparison::Hash)
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::report::{ComparisonReport, ComparisonResult};
+use clap::ValueEnum;
+use serde::Serialize;
+#[cfg(feature = "http")]
+use std::time::Duration;
+
+/// How long to wait for the webhook before giving up
+#[cfg(feature = "http")]
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body of the request sent to the webhook
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyFormat {
+    /// The summary of the comparison as JSON
+    Json,
+    /// A message for Slack incoming webhooks ({"text": ...})
+    Slack,
+}
+
+/// Summary of a finished comparison sent to the webhook
+#[derive(Debug, Serialize)]
+pub struct Notification<'a> {
+    pub file1: &'a str,
+    pub file2: &'a str,
+    pub result: ComparisonResult,
+    pub exit_code: i32,
+    pub rows: u64,
+    pub different_columns: Vec<&'a str>,
+    pub error: Option<&'a str>,
+}
+
+impl<'a> Notification<'a> {
+    pub fn new(
+        report: &'a ComparisonReport,
+        file1: &'a str,
+        file2: &'a str,
+        exit_code: i32,
+    ) -> Self {
+        Notification {
+            file1,
+            file2,
+            result: report.result,
+            exit_code,
+            rows: report.rows,
+            different_columns: report
+                .column_differences
+                .iter()
+                .map(|difference| difference.column.as_str())
+                .collect(),
+            error: report.error.as_deref(),
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!(
+            "csv-compare {} <> {}: {} ({} rows)",
+            self.file1,
+            self.file2,
+            self.result.name(),
+            self.rows
+        );
+        if !self.different_columns.is_empty() {
+            text.push_str(&format!(
+                "\nDifferent columns: {}",
+                self.different_columns.join(", ")
+            ));
+        }
+        if let Some(error) = self.error {
+            text.push_str(&format!("\nError: {}", error));
+        }
+        text
+    }
+}
+
+/// POSTs the notification to the webhook
+#[cfg(feature = "http")]
+pub fn notify(url: &str, format: NotifyFormat, notification: &Notification) -> Result<(), String> {
+    let body = match format {
+        NotifyFormat::Json => serde_json::to_string(notification),
+        NotifyFormat::Slack => {
+            serde_json::to_string(&serde_json::json!({ "text": notification.text() }))
+        }
+    }
+    .map_err(|error| format!("Couldn't build the notification: {error}"))?;
+    ureq::post(url)
+        .timeout(NOTIFY_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(&body)
+        .map(|_| ())
+        .map_err(|error| format!("Couldn't notify {url}: {error}"))
+}
+
+#[cfg(not(feature = "http"))]
+pub fn notify(
+    url: &str,
+    _format: NotifyFormat,
+    _notification: &Notification,
+) -> Result<(), String> {
+    Err(format!(
+        "Couldn't notify {url}: HTTP support is not included in this build (feature http)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::ColumnDifference;
+
+    fn report() -> ComparisonReport {
+        ComparisonReport {
+            result: ComparisonResult::DifferentValues,
+            rows: 10,
+            column_differences: ["name", "age"]
+                .map(|column| ColumnDifference {
+                    column: column.to_string(),
+                    differing_rows: 1,
+                    values_changed: 1,
+                    null_on_one_side: 0,
+                    whitespace_only: 0,
+                    case_only: 0,
+                    dtype_mismatch: false,
+                    samples: vec![],
+                })
+                .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summarizes_the_comparison() {
+        let report = report();
+        let notification = Notification::new(&report, "a.csv", "b.csv", 1);
+        assert_eq!(
+            notification.text(),
+            "csv-compare a.csv <> b.csv: different_values (10 rows)\nDifferent columns: name, age"
+        );
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["result"], "different_values");
+        assert_eq!(json["exit_code"], 1);
+        assert_eq!(
+            json["different_columns"],
+            serde_json::json!(["name", "age"])
+        );
+    }
+
+    #[test]
+    fn includes_the_error() {
+        let report = ComparisonReport {
+            result: ComparisonResult::Failed,
+            error: Some("Couldn't read a.csv".to_string()),
+            ..Default::default()
+        };
+        let notification = Notification::new(&report, "a.csv", "b.csv", 70);
+        assert!(notification
+            .text()
+            .ends_with("\nError: Couldn't read a.csv"));
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn cant_notify_without_http_support() {
+        let report = report();
+        let notification = Notification::new(&report, "a.csv", "b.csv", 1);
+        assert!(notify("http://localhost", NotifyFormat::Json, &notification).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::headers::SortColumn;
+use crate::key::KeyType;
+use crate::Args;
+use std::ops::Deref;
+use std::time::Duration;
+
+/// Arguments of a comparison built from code, with the same defaults as the command line. It can be used
+/// wherever the library expects `&Args`:
+/// ```ignore
+/// let options = CompareOptions::builder().key("id").key_type(KeyType::Int).report_all().build()?;
+/// let report = compare_files(&options, &printer, "fileA.csv", "fileB.csv");
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    args: Args,
+}
+
+impl CompareOptions {
+    pub fn builder() -> CompareOptionsBuilder {
+        CompareOptionsBuilder::default()
+    }
+
+    pub fn into_args(self) -> Args {
+        self.args
+    }
+}
+
+impl Deref for CompareOptions {
+    type Target = Args;
+
+    fn deref(&self) -> &Args {
+        &self.args
+    }
+}
+
+/// Sets the options as typed fields of `Args`, starting from the defaults of the command line. The environment
+/// variables of the command line (CSV_COMPARE_*) aren't read. The comparison has no numeric tolerance nor a way to
+/// leave columns out, so there are no setters for them
+#[derive(Debug, Clone)]
+pub struct CompareOptionsBuilder {
+    args: Args,
+    /// First invalid value given, reported by `build`
+    error: Option<String>,
+}
+
+impl Default for CompareOptionsBuilder {
+    fn default() -> Self {
+        CompareOptionsBuilder {
+            args: Args::defaults(),
+            error: None,
+        }
+    }
+}
+
+impl CompareOptionsBuilder {
+    /// Column used to sort the rows of both files (--sort-by), by name or by position as @3
+    pub fn key(mut self, column: &str) -> Self {
+        match column.parse::<SortColumn>() {
+            Ok(column) => self.args.sort_by = Some(column),
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+        self
+    }
+
+    /// Sort the key as text, integers or decimal numbers (--key-type)
+    pub fn key_type(mut self, key_type: KeyType) -> Self {
+        self.args.key_type = Some(key_type);
+        self
+    }
+
+    pub fn separator(mut self, separator: char) -> Self {
+        self.args.separator = separator;
+        self
+    }
+
+    /// Infer the data types of the columns and compare them (--typed)
+    pub fn typed(mut self) -> Self {
+        self.args.typed = true;
+        self
+    }
+
+    /// How many columns to compare at the same time (--number-of-columns)
+    pub fn columns_at_once(mut self, number_of_columns: usize) -> Self {
+        self.args.number_of_columns = number_of_columns;
+        self
+    }
+
+    pub fn all_columns(mut self) -> Self {
+        self.args.all_columns = true;
+        self
+    }
+
+    pub fn strict_column_order(mut self) -> Self {
+        self.args.strict_column_order = true;
+        self
+    }
+
+    pub fn ignore_column_case(mut self) -> Self {
+        self.args.ignore_column_case = true;
+        self
+    }
+
+    pub fn match_by_position(mut self) -> Self {
+        self.args.match_by_position = true;
+        self
+    }
+
+    /// Keys of the rows to leave out of the comparison, one per line
+    pub fn exclude_keys(mut self, keys_file: &str) -> Self {
+        self.args.exclude_keys = Some(keys_file.to_string());
+        self
+    }
+
+    /// Keep comparing the columns after the first difference (--report-all)
+    pub fn report_all(mut self) -> Self {
+        self.args.report_all = true;
+        self
+    }
+
+    /// Different values kept as samples of every column (--samples)
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.args.samples = samples;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.args.timeout = Some(timeout);
+        self
+    }
+
+    pub fn low_memory(mut self) -> Self {
+        self.args.low_memory = true;
+        self
+    }
+
+    /// Any other option, set in its field of `Args`, e.g. `configure(|args| args.dedupe_headers = true)`. The
+    /// conflicts between options of the command line aren't checked
+    pub fn configure(mut self, configure: impl FnOnce(&mut Args)) -> Self {
+        configure(&mut self.args);
+        self
+    }
+
+    /// Fails with the first invalid value given
+    pub fn build(self) -> Result<CompareOptions, String> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(CompareOptions { args: self.args }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_defaults_of_the_command_line() {
+        let options = CompareOptions::builder().build().unwrap();
+        assert_eq!(options.separator, ',');
+        assert_eq!(options.number_of_columns, 1);
+        assert_eq!(options.samples, 5);
+        assert!(options.file1.is_none() && options.file2.is_none());
+    }
+
+    #[test]
+    fn sets_the_typed_options() {
+        let options = CompareOptions::builder()
+            .key("@2")
+            .key_type(KeyType::Int)
+            .separator(';')
+            .report_all()
+            .samples(10)
+            .timeout(Duration::from_secs(30))
+            .configure(|args| args.dedupe_headers = true)
+            .build()
+            .unwrap();
+        assert_eq!(options.sort_by, Some(SortColumn::Position(2)));
+        assert_eq!(options.key_type, Some(KeyType::Int));
+        assert_eq!(options.separator, ';');
+        assert!(options.report_all && options.dedupe_headers);
+        assert_eq!(options.samples, 10);
+        assert_eq!(options.timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(CompareOptions::builder().key("@0").build().is_err());
+    }
+}
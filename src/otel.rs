@@ -0,0 +1,33 @@
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{trace, Resource};
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Layer exporting the spans to the OTLP (HTTP) endpoint, e.g. http://localhost:4318/v1/traces
+pub fn layer<S>(endpoint: &str) -> Result<impl Layer<S>, String>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                env!("CARGO_PKG_NAME"),
+            )])),
+        )
+        .install_simple()
+        .map_err(|error| format!("Couldn't set up OpenTelemetry with {endpoint}: {error}"))?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
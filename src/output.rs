@@ -0,0 +1,243 @@
+use crate::pager;
+use crate::progress::{Progress, ProgressFormat};
+use clap::ValueEnum;
+use colored::{ColoredString, Colorize};
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// When to use colors in the output
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colors only if NO_COLOR is not set and the output is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+/// Globally enables or disables the colored output. Returns whether colors are enabled
+pub fn setup_colors(choice: ColorChoice) -> bool {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !no_color && std::io::stdout().is_terminal()
+        }
+    };
+    colored::control::set_override(enabled);
+    enabled
+}
+
+/// Colors and markers of the passed, warning and failed checks
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Theme {
+    /// Green, yellow and red
+    Default,
+    /// Blue, yellow and magenta, that can be told apart with red-green color blindness
+    Colorblind,
+    /// No colors, with [OK], [WARN] and [FAIL] markers
+    Ascii,
+}
+
+static THEME: AtomicU8 = AtomicU8::new(Theme::Default as u8);
+
+/// Globally sets the theme of the output. The ascii theme also disables the colors
+pub fn set_theme(theme: Theme) {
+    THEME.store(theme as u8, Ordering::Relaxed);
+    if theme == Theme::Ascii {
+        colored::control::set_override(false);
+    }
+}
+
+fn theme() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        value if value == Theme::Colorblind as u8 => Theme::Colorblind,
+        value if value == Theme::Ascii as u8 => Theme::Ascii,
+        _ => Theme::Default,
+    }
+}
+
+/// Styles text by the severity of what it tells, with the colors of the theme. The `_status` variants are for
+/// the words that give the outcome (e.g. "FILES ARE DIFFERENT"), that also get the marker of the ascii theme
+pub trait Themed {
+    fn pass(self) -> ColoredString;
+    fn warn(self) -> ColoredString;
+    fn fail(self) -> ColoredString;
+    fn pass_status(self) -> ColoredString;
+    fn warn_status(self) -> ColoredString;
+    fn fail_status(self) -> ColoredString;
+}
+
+impl Themed for &str {
+    fn pass(self) -> ColoredString {
+        match theme() {
+            Theme::Default => self.green(),
+            Theme::Colorblind => self.bright_blue(),
+            Theme::Ascii => self.normal(),
+        }
+    }
+
+    fn warn(self) -> ColoredString {
+        match theme() {
+            Theme::Default | Theme::Colorblind => self.yellow(),
+            Theme::Ascii => self.normal(),
+        }
+    }
+
+    fn fail(self) -> ColoredString {
+        match theme() {
+            Theme::Default => self.red(),
+            Theme::Colorblind => self.bright_magenta(),
+            Theme::Ascii => self.normal(),
+        }
+    }
+
+    fn pass_status(self) -> ColoredString {
+        with_marker(self, "[OK]").as_str().pass()
+    }
+
+    fn warn_status(self) -> ColoredString {
+        with_marker(self, "[WARN]").as_str().warn()
+    }
+
+    fn fail_status(self) -> ColoredString {
+        with_marker(self, "[FAIL]").as_str().fail()
+    }
+}
+
+fn with_marker(text: &str, marker: &str) -> String {
+    if theme() == Theme::Ascii {
+        format!("{marker} {text}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// How much the tool prints while comparing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Every step, the progress bar and the final result
+    Normal,
+    /// Nothing at all, only the exit code
+    Quiet,
+    /// A single final line with the result
+    SummaryOnly,
+}
+
+pub struct Printer {
+    mode: OutputMode,
+    /// When the pager is used, the result is kept here until the comparison finishes
+    paged_result: Option<RefCell<String>>,
+}
+
+impl Printer {
+    pub fn new(quiet: bool, summary_only: bool, use_pager: bool) -> Self {
+        let mode = if quiet {
+            OutputMode::Quiet
+        } else if summary_only {
+            OutputMode::SummaryOnly
+        } else {
+            OutputMode::Normal
+        };
+        let paged_result =
+            if use_pager && mode == OutputMode::Normal && std::io::stdout().is_terminal() {
+                Some(RefCell::new(String::new()))
+            } else {
+                None
+            };
+        Printer { mode, paged_result }
+    }
+
+    pub fn mode(&self) -> OutputMode {
+        self.mode
+    }
+
+    /// Prints an intermediate step to stderr, so it doesn't get mixed with the result (only in normal mode)
+    pub fn info(&self, message: impl Display) {
+        if self.mode == OutputMode::Normal {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Prints the detailed result of the comparison to stdout (only in normal mode)
+    pub fn result(&self, message: impl Display) {
+        if self.mode == OutputMode::Normal {
+            match &self.paged_result {
+                Some(paged_result) => paged_result
+                    .borrow_mut()
+                    .push_str(&format!("{}\n", message)),
+                None => println!("{}", message),
+            }
+        }
+    }
+
+    /// Shows the result kept for the pager, through the pager if it doesn't fit in the terminal
+    pub fn finish(&self) {
+        if let Some(paged_result) = &self.paged_result {
+            let text = paged_result.borrow();
+            if pager::exceeds_terminal_height(&text) {
+                pager::page(&text);
+            } else {
+                print!("{}", text);
+            }
+        }
+    }
+
+    /// Prints the single line summary of the comparison (only in summary-only mode)
+    pub fn summary(&self, message: impl Display) {
+        if self.mode == OutputMode::SummaryOnly {
+            println!("{}", message);
+        }
+    }
+
+    /// Creates the progress of the comparison, that will only be shown in normal mode
+    pub fn progress(
+        &self,
+        len: u64,
+        total_bytes: u64,
+        enabled: bool,
+        format: ProgressFormat,
+    ) -> Progress {
+        Progress::new(
+            len,
+            total_bytes,
+            enabled && self.mode == OutputMode::Normal,
+            format,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_takes_precedence_over_summary_only() {
+        assert_eq!(Printer::new(true, true, false).mode(), OutputMode::Quiet);
+        assert_eq!(
+            Printer::new(false, true, false).mode(),
+            OutputMode::SummaryOnly
+        );
+        assert_eq!(Printer::new(false, false, false).mode(), OutputMode::Normal);
+    }
+
+    #[test]
+    fn the_result_is_only_kept_for_the_pager_in_normal_mode() {
+        assert!(Printer::new(true, false, true).paged_result.is_none());
+        assert!(Printer::new(false, true, true).paged_result.is_none());
+        assert!(Printer::new(false, false, false).paged_result.is_none());
+    }
+
+    #[test]
+    fn the_ascii_theme_adds_markers() {
+        set_theme(Theme::Ascii);
+        let (pass, fail) = ("SAME".pass_status(), "DIFFERENT".fail_status());
+        set_theme(Theme::Default);
+        assert_eq!(pass.to_string(), "[OK] SAME");
+        assert_eq!(fail.to_string(), "[FAIL] DIFFERENT");
+        assert_eq!(with_marker("SAME", "[OK]"), "SAME");
+    }
+}
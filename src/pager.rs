@@ -0,0 +1,39 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use terminal_size::{terminal_size, Height};
+
+/// Pager used when $PAGER is not set. -F quits if the content fits in one screen, -R keeps the colors
+const DEFAULT_PAGER: &str = "less -FRX";
+
+/// Whether the text is too long to fit in the terminal
+pub fn exceeds_terminal_height(text: &str) -> bool {
+    match terminal_size() {
+        Some((_, Height(height))) => text.lines().count() >= height as usize,
+        None => false,
+    }
+}
+
+/// Shows the text through $PAGER (or less). Falls back to stdout if the pager can't be started
+pub fn page(text: &str) {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        print!("{text}");
+        return;
+    };
+
+    let child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The user may quit the pager before reading everything
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+        }
+        Err(_) => print!("{text}"),
+    }
+}
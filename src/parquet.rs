@@ -0,0 +1,151 @@
+use crate::canonicalize::write_csv;
+use crate::inputs::{percent_decode, temporary_file};
+use anyhow::{bail, Context, Result};
+use polars::prelude::{concat, LazyFrame, ScanArgsParquet, UnionArgs};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Writes a Parquet file, a directory of Parquet files (with hive-style partitions like `day=2024-03-01/`)
+/// or a Delta table as a single CSV file, for the commands that read a CSV file (canonicalize, hash...).
+/// Comparisons scan them lazily instead, with `ParquetSource`
+pub fn convert(path: &str, separator: char) -> Result<NamedTempFile> {
+    let data_frame = scan(path)?
+        .collect()
+        .with_context(|| format!("Error reading {path}"))?;
+    let mut output = temporary_file()?;
+    write_csv(&data_frame, separator, &mut output)?;
+    Ok(output)
+}
+
+pub(crate) fn scan(path: &str) -> Result<LazyFrame> {
+    let scan_args = ScanArgsParquet {
+        hive_partitioning: true,
+        ..Default::default()
+    };
+    let directory = Path::new(path);
+    if directory.join("_delta_log").is_dir() {
+        let frames = delta_files(directory)?
+            .iter()
+            .map(|file| LazyFrame::scan_parquet(file, scan_args.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Error reading Delta table {path}"))?;
+        if frames.is_empty() {
+            bail!("The Delta table {path} has no files");
+        }
+        return concat(frames, UnionArgs::default())
+            .with_context(|| format!("Error reading Delta table {path}"));
+    }
+    let pattern = if directory.is_dir() {
+        directory.join("**").join("*.parquet")
+    } else {
+        directory.to_path_buf()
+    };
+    LazyFrame::scan_parquet(pattern, scan_args).with_context(|| format!("Error reading {path}"))
+}
+
+/// Data files of the current version of a Delta table, replaying the files added and removed by
+/// the commits of its log
+fn delta_files(table: &Path) -> Result<Vec<PathBuf>> {
+    let log = table.join("_delta_log");
+    let mut commits: Vec<(u64, PathBuf)> = fs::read_dir(&log)
+        .with_context(|| format!("Couldn't read {}", log.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let version = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".json")?
+                .parse()
+                .ok()?;
+            Some((version, path))
+        })
+        .collect();
+    commits.sort();
+    if commits.first().map(|(version, _)| *version) != Some(0) {
+        bail!(
+            "The log of the Delta table {} starts at a checkpoint, which is not supported",
+            table.display()
+        );
+    }
+
+    let mut files = BTreeSet::new();
+    for (_, commit) in commits {
+        let content = fs::read_to_string(&commit)
+            .with_context(|| format!("Couldn't read {}", commit.display()))?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let action: serde_json::Value = serde_json::from_str(line)
+                .with_context(|| format!("Invalid commit {}", commit.display()))?;
+            if let Some(file) = action.pointer("/add/path").and_then(|path| path.as_str()) {
+                files.insert(percent_decode(file));
+            }
+            if let Some(file) = action
+                .pointer("/remove/path")
+                .and_then(|path| path.as_str())
+            {
+                files.remove(&percent_decode(file));
+            }
+        }
+    }
+    Ok(files.into_iter().map(|file| table.join(file)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::ParquetWriter;
+    use tempfile::TempDir;
+
+    fn write_parquet(path: &Path, ids: &[&str]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut data_frame = df!("id" => ids).unwrap();
+        ParquetWriter::new(fs::File::create(path).unwrap())
+            .finish(&mut data_frame)
+            .unwrap();
+    }
+
+    #[test]
+    fn converts_parquet_files_to_csv() {
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("data.parquet");
+        write_parquet(&path, &["1", "2"]);
+        let csv_file = convert(path.to_str().unwrap(), ',').unwrap();
+        assert_eq!(fs::read_to_string(csv_file.path()).unwrap(), "id\n1\n2\n");
+    }
+
+    #[test]
+    fn replays_the_log_of_delta_tables() {
+        let table = TempDir::new().unwrap();
+        let log = table.path().join("_delta_log");
+        fs::create_dir(&log).unwrap();
+        fs::write(
+            log.join("00000000000000000000.json"),
+            "{\"add\":{\"path\":\"part-0.parquet\"}}\n{\"add\":{\"path\":\"day%3D1/part-1.parquet\"}}\n",
+        )
+        .unwrap();
+        fs::write(
+            log.join("00000000000000000001.json"),
+            "{\"remove\":{\"path\":\"part-0.parquet\"}}\n\n{\"add\":{\"path\":\"part-2.parquet\"}}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            delta_files(table.path()).unwrap(),
+            [
+                table.path().join("day=1/part-1.parquet"),
+                table.path().join("part-2.parquet")
+            ]
+        );
+    }
+
+    #[test]
+    fn delta_logs_must_start_at_the_first_commit() {
+        let table = TempDir::new().unwrap();
+        let log = table.path().join("_delta_log");
+        fs::create_dir(&log).unwrap();
+        fs::write(log.join("00000000000000000010.json"), "").unwrap();
+        assert!(delta_files(table.path()).is_err());
+    }
+}
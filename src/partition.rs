@@ -0,0 +1,221 @@
+use crate::report::ColumnDifference;
+use anyhow::{Context, Result};
+use polars::frame::DataFrame;
+use polars::prelude::{col, lit, DataType, LazyFrame, SortOptions};
+
+/// Rows whose key (compared as text) is between the lower bound (included) and the upper bound (excluded).
+/// Rows with a null key belong to the first range
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyRange {
+    lower: Option<String>,
+    upper: Option<String>,
+}
+
+impl KeyRange {
+    /// Only the rows of the range
+    pub(crate) fn filter(&self, lazy_frame: &LazyFrame, key: &str) -> LazyFrame {
+        let key_text = col(key).cast(DataType::Utf8);
+        let in_range = match (&self.lower, &self.upper) {
+            (None, None) => return lazy_frame.clone(),
+            (Some(lower), None) => key_text.gt_eq(lit(lower.as_str())),
+            (None, Some(upper)) => key_text
+                .clone()
+                .lt(lit(upper.as_str()))
+                .or(key_text.is_null()),
+            (Some(lower), Some(upper)) => key_text
+                .clone()
+                .gt_eq(lit(lower.as_str()))
+                .and(key_text.lt(lit(upper.as_str()))),
+        };
+        lazy_frame.clone().filter(in_range)
+    }
+}
+
+/// Splits the keys of the file in ranges with about the same number of rows. Only the key column is read
+pub(crate) fn key_ranges(
+    lazy_frame: &LazyFrame,
+    key: &str,
+    partitions: usize,
+) -> Result<Vec<KeyRange>> {
+    if partitions <= 1 {
+        return Ok(vec![KeyRange::default()]);
+    }
+    let keys = lazy_frame
+        .clone()
+        .select([col(key).cast(DataType::Utf8)])
+        .drop_nulls(None)
+        .sort(key, SortOptions::default())
+        .collect()
+        .with_context(|| {
+            format!("Couldn't read the keys of column {key} to partition the files")
+        })?;
+    let keys = keys.column(key)?.utf8()?.clone();
+    let mut boundaries: Vec<String> = (1..partitions)
+        .filter_map(|partition| keys.get(partition * keys.len() / partitions))
+        .map(str::to_string)
+        .collect();
+    boundaries.dedup();
+
+    let mut lower = None;
+    let mut ranges = vec![];
+    for boundary in boundaries {
+        ranges.push(KeyRange {
+            lower: lower.replace(boundary.clone()),
+            upper: Some(boundary),
+        });
+    }
+    ranges.push(KeyRange { lower, upper: None });
+    Ok(ranges)
+}
+
+/// Leaves both (sorted) partitions with the same number of rows, so they can be compared row by row.
+/// Returns how many rows were left out, which are different in every column
+pub(crate) fn align(first: DataFrame, second: DataFrame) -> (DataFrame, DataFrame, u64) {
+    let height = first.height().min(second.height());
+    let extra_rows = first.height().max(second.height()) - height;
+    if extra_rows == 0 {
+        return (first, second, 0);
+    }
+    (
+        first.slice(0, height),
+        second.slice(0, height),
+        extra_rows as u64,
+    )
+}
+
+/// Adds the differences found in a partition to the ones of the previous partitions
+pub(crate) fn merge_differences(
+    differences: &mut Vec<ColumnDifference>,
+    partition_differences: Vec<ColumnDifference>,
+    columns: &[&String],
+    extra_rows: u64,
+    max_samples: usize,
+) {
+    for partition_difference in partition_differences {
+        match differences
+            .iter_mut()
+            .find(|difference| difference.column == partition_difference.column)
+        {
+            Some(difference) => {
+                difference.differing_rows += partition_difference.differing_rows;
+                difference.values_changed += partition_difference.values_changed;
+                difference.null_on_one_side += partition_difference.null_on_one_side;
+                difference.whitespace_only += partition_difference.whitespace_only;
+                difference.case_only += partition_difference.case_only;
+                difference.dtype_mismatch |= partition_difference.dtype_mismatch;
+                let missing_samples = max_samples.saturating_sub(difference.samples.len());
+                difference.samples.extend(
+                    partition_difference
+                        .samples
+                        .into_iter()
+                        .take(missing_samples),
+                );
+            }
+            None => differences.push(partition_difference),
+        }
+    }
+    if extra_rows == 0 {
+        return;
+    }
+    for column in columns {
+        match differences
+            .iter_mut()
+            .find(|difference| difference.column == column.as_str())
+        {
+            Some(difference) => {
+                difference.differing_rows += extra_rows;
+                difference.values_changed += extra_rows;
+            }
+            None => differences.push(ColumnDifference {
+                column: column.to_string(),
+                differing_rows: extra_rows,
+                values_changed: extra_rows,
+                null_on_one_side: 0,
+                whitespace_only: 0,
+                case_only: 0,
+                dtype_mismatch: false,
+                samples: vec![],
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+
+    fn difference(column: &str, differing_rows: u64, samples: usize) -> ColumnDifference {
+        ColumnDifference {
+            column: column.to_string(),
+            differing_rows,
+            values_changed: differing_rows,
+            null_on_one_side: 0,
+            whitespace_only: 0,
+            case_only: 0,
+            dtype_mismatch: false,
+            samples: vec![
+                crate::report::DifferenceSample {
+                    key: None,
+                    first_value: None,
+                    second_value: None,
+                };
+                samples
+            ],
+        }
+    }
+
+    #[test]
+    fn splits_the_keys_in_ranges_with_the_same_rows() {
+        let lazy_frame = df!("id" => [Some("h"), Some("a"), None, Some("c"), Some("b"), Some("e"), Some("d"), Some("g"), Some("f")])
+            .unwrap()
+            .lazy();
+        let ranges = key_ranges(&lazy_frame, "id", 4).unwrap();
+        let rows: Vec<usize> = ranges
+            .iter()
+            .map(|range| range.filter(&lazy_frame, "id").collect().unwrap().height())
+            .collect();
+        assert_eq!(rows, [3, 2, 2, 2]);
+        assert_eq!(key_ranges(&lazy_frame, "id", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn repeated_boundaries_are_merged() {
+        let lazy_frame = df!("id" => ["a", "a", "a", "a", "b"]).unwrap().lazy();
+        let ranges = key_ranges(&lazy_frame, "id", 4).unwrap();
+        assert_eq!(ranges.len(), 2);
+    }
+
+    #[test]
+    fn aligns_partitions_with_different_rows() {
+        let first = df!("id" => ["a", "b", "c"]).unwrap();
+        let second = df!("id" => ["a"]).unwrap();
+        let (first, second, extra_rows) = align(first, second);
+        assert_eq!((first.height(), second.height(), extra_rows), (1, 1, 2));
+    }
+
+    #[test]
+    fn merges_the_differences_of_the_partitions() {
+        let (name, age) = ("name".to_string(), "age".to_string());
+        let mut differences = vec![difference("name", 2, 2)];
+        merge_differences(
+            &mut differences,
+            vec![difference("name", 3, 2), difference("city", 1, 1)],
+            &[&name, &age],
+            4,
+            3,
+        );
+        let summary: Vec<(&str, u64, usize)> = differences
+            .iter()
+            .map(|difference| {
+                (
+                    difference.column.as_str(),
+                    difference.differing_rows,
+                    difference.samples.len(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, [("name", 9, 3), ("city", 1, 1), ("age", 4, 0)]);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::resources;
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// Minimum time between two plain text progress lines
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Names of columns shown in the message of the progress bar, the rest are only counted
+const MESSAGE_COLUMNS: usize = 3;
+
+/// Time between two updates of the bytes read in the progress bar
+const BYTES_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the progress is reported
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// Progress bar in a terminal, plain text lines otherwise
+    Bar,
+    /// One JSON event per line in stderr, to be consumed by other programs
+    Jsonl,
+}
+
+/// Event emitted in jsonl progress format
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ProgressEvent<'a> {
+    Started {
+        total_columns: u64,
+        total_bytes: u64,
+    },
+    BatchDone {
+        columns_done: u64,
+        total_columns: u64,
+        columns: &'a [&'a String],
+        rows_processed: u64,
+        bytes_read: u64,
+        total_bytes: u64,
+        elapsed_ms: u128,
+    },
+    Finished {
+        columns_done: u64,
+        total_columns: u64,
+        elapsed_ms: u128,
+    },
+    Abandoned {
+        columns_done: u64,
+        total_columns: u64,
+        elapsed_ms: u128,
+    },
+}
+
+impl ProgressEvent<'_> {
+    fn emit(&self) {
+        eprintln!(
+            "{}",
+            serde_json::to_string(self).expect("Progress events are always serializable")
+        );
+    }
+}
+
+/// Columns and bytes compared so far. The bytes to read are the size of both files times the times they are read
+/// (once per batch of columns and range of keys). The bytes read are measured (in Linux) or estimated from the
+/// columns done
+pub struct Counts {
+    total: u64,
+    done: u64,
+    total_bytes: u64,
+    started_bytes: Option<u64>,
+    started: Instant,
+}
+
+impl Counts {
+    fn new(total: u64, total_bytes: u64) -> Self {
+        Counts {
+            total,
+            done: 0,
+            total_bytes,
+            started_bytes: resources::bytes_read(),
+            started: Instant::now(),
+        }
+    }
+
+    fn bytes_read(&self) -> u64 {
+        let estimated = if self.total == 0 {
+            self.total_bytes
+        } else {
+            self.total_bytes * self.done / self.total
+        };
+        bytes_read_since(self.started_bytes, self.total_bytes)
+            .map_or(estimated, |read| read.max(estimated))
+    }
+
+    /// Seconds left at the current throughput, if anything has been read yet
+    fn eta_seconds(&self) -> Option<u64> {
+        let bytes_read = self.bytes_read();
+        if bytes_read == 0 {
+            return None;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let left = self.total_bytes.saturating_sub(bytes_read) as f64;
+        Some((left * elapsed / bytes_read as f64) as u64)
+    }
+}
+
+fn bytes_read_since(started_bytes: Option<u64>, total_bytes: u64) -> Option<u64> {
+    let started_bytes = started_bytes?;
+    let bytes_read = resources::bytes_read()?;
+    Some(bytes_read.saturating_sub(started_bytes).min(total_bytes))
+}
+
+/// Progress of the column comparison. Drawn as a bar in a terminal, as periodic
+/// plain text lines in stderr when it's redirected (CI logs) or not shown at all
+pub enum Progress {
+    Bar(ProgressBar, Counts),
+    Plain {
+        counts: Counts,
+        last_printed: Instant,
+    },
+    Jsonl(Counts),
+    Hidden,
+}
+
+impl Progress {
+    pub fn new(total: u64, total_bytes: u64, enabled: bool, format: ProgressFormat) -> Self {
+        if !enabled {
+            return Progress::Hidden;
+        }
+        let counts = Counts::new(total, total_bytes);
+        if format == ProgressFormat::Jsonl {
+            ProgressEvent::Started {
+                total_columns: total,
+                total_bytes,
+            }
+            .emit();
+            return Progress::Jsonl(counts);
+        }
+        if !std::io::stderr().is_terminal() {
+            return Progress::Plain {
+                counts,
+                last_printed: Instant::now(),
+            };
+        }
+
+        let progress_bar = ProgressBar::new(total_bytes);
+        progress_bar.set_style(
+            ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {bytes:>10}/{total_bytes:10} ({binary_bytes_per_sec}, ETA {eta}) {msg}")
+                .expect("Error creating progress bar. Incorrect Style?. Please raise issue to developers of this tool"));
+        progress_bar.set_message(format!("0/{} columns", total));
+        refresh_bytes_read(&progress_bar, counts.started_bytes, total_bytes);
+        Progress::Bar(progress_bar, counts)
+    }
+
+    /// A batch of columns is going to be compared, shown in the message of the progress bar
+    pub fn batch_started(&self, columns: &[&String], rows: u64) {
+        if let Progress::Bar(progress_bar, counts) = self {
+            progress_bar.set_message(format!(
+                "{}/{} columns | comparing {} ({} rows)",
+                counts.done,
+                counts.total,
+                describe_columns(columns),
+                rows
+            ));
+        }
+    }
+
+    /// A batch of columns has been compared
+    pub fn batch_done(&mut self, columns: &[&String], rows_processed: u64) {
+        let delta = columns.len() as u64;
+        match self {
+            Progress::Bar(progress_bar, counts) => {
+                counts.done += delta;
+                progress_bar.set_message(format!("{}/{} columns", counts.done, counts.total));
+                progress_bar.set_position(counts.bytes_read().max(progress_bar.position()));
+            }
+            Progress::Plain {
+                counts,
+                last_printed,
+            } => {
+                counts.done += delta;
+                if last_printed.elapsed() >= PLAIN_PROGRESS_INTERVAL {
+                    *last_printed = Instant::now();
+                    print_plain_progress(counts);
+                }
+            }
+            Progress::Jsonl(counts) => {
+                counts.done += delta;
+                ProgressEvent::BatchDone {
+                    columns_done: counts.done,
+                    total_columns: counts.total,
+                    columns,
+                    rows_processed,
+                    bytes_read: counts.bytes_read(),
+                    total_bytes: counts.total_bytes,
+                    elapsed_ms: counts.started.elapsed().as_millis(),
+                }
+                .emit();
+            }
+            Progress::Hidden => {}
+        }
+    }
+
+    /// All the columns have been compared
+    pub fn finish(&self) {
+        match self {
+            Progress::Bar(progress_bar, _) => progress_bar.finish(),
+            Progress::Plain { counts, .. } => print_plain_progress(counts),
+            Progress::Jsonl(counts) => ProgressEvent::Finished {
+                columns_done: counts.done,
+                total_columns: counts.total,
+                elapsed_ms: counts.started.elapsed().as_millis(),
+            }
+            .emit(),
+            Progress::Hidden => {}
+        }
+    }
+
+    /// The comparison stopped before comparing all the columns
+    pub fn abandon(&self) {
+        match self {
+            Progress::Bar(progress_bar, _) => progress_bar.abandon(),
+            Progress::Jsonl(counts) => ProgressEvent::Abandoned {
+                columns_done: counts.done,
+                total_columns: counts.total,
+                elapsed_ms: counts.started.elapsed().as_millis(),
+            }
+            .emit(),
+            Progress::Plain { .. } | Progress::Hidden => {}
+        }
+    }
+}
+
+/// Moves the bar with the bytes read by this process while it's being compared, as a single batch of a big file
+/// can take a long time. Stops when the bar is finished or dropped
+fn refresh_bytes_read(progress_bar: &ProgressBar, started_bytes: Option<u64>, total_bytes: u64) {
+    if started_bytes.is_none() {
+        return;
+    }
+    let weak_progress_bar = progress_bar.downgrade();
+    std::thread::spawn(move || {
+        while let Some(progress_bar) = weak_progress_bar.upgrade() {
+            if progress_bar.is_finished() {
+                break;
+            }
+            if let Some(bytes_read) = bytes_read_since(started_bytes, total_bytes) {
+                progress_bar.set_position(bytes_read.max(progress_bar.position()));
+            }
+            drop(progress_bar);
+            std::thread::sleep(BYTES_REFRESH_INTERVAL);
+        }
+    });
+}
+
+fn describe_columns(columns: &[&String]) -> String {
+    let names = columns
+        .iter()
+        .take(MESSAGE_COLUMNS)
+        .map(|column| column.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if columns.len() > MESSAGE_COLUMNS {
+        format!("{} and {} more", names, columns.len() - MESSAGE_COLUMNS)
+    } else {
+        names
+    }
+}
+
+fn print_plain_progress(counts: &Counts) {
+    let megabytes = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    let bytes_read = counts.bytes_read();
+    let elapsed = counts.started.elapsed();
+    eprintln!(
+        "Compared {}/{} columns, read {:.1}/{:.1} MB ({:.1} MB/s, {}s elapsed{})",
+        counts.done,
+        counts.total,
+        megabytes(bytes_read),
+        megabytes(counts.total_bytes),
+        megabytes(bytes_read) / elapsed.as_secs_f64().max(0.001),
+        elapsed.as_secs(),
+        counts
+            .eta_seconds()
+            .map_or(String::new(), |eta| format!(", ETA {}s", eta))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(total: u64, done: u64, total_bytes: u64) -> Counts {
+        Counts {
+            total,
+            done,
+            total_bytes,
+            started_bytes: None,
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn describes_the_first_columns_of_a_batch() {
+        let names: Vec<String> = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+        let columns: Vec<&String> = names.iter().collect();
+        assert_eq!(describe_columns(&columns[..2]), "a, b");
+        assert_eq!(describe_columns(&columns), "a, b, c and 2 more");
+    }
+
+    #[test]
+    fn estimates_the_bytes_read_from_the_columns_done() {
+        assert_eq!(counts(4, 1, 1000).bytes_read(), 250);
+        assert_eq!(counts(0, 0, 1000).bytes_read(), 1000);
+        assert_eq!(counts(4, 0, 1000).eta_seconds(), None);
+    }
+
+    #[test]
+    fn events_are_tagged_by_their_name() {
+        let event = ProgressEvent::Finished {
+            columns_done: 2,
+            total_columns: 3,
+            elapsed_ms: 10,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"finished","columns_done":2,"total_columns":3,"elapsed_ms":10}"#
+        );
+    }
+
+    #[test]
+    fn disabled_progress_is_hidden() {
+        let progress = Progress::new(10, 1000, false, ProgressFormat::Jsonl);
+        assert!(matches!(progress, Progress::Hidden));
+    }
+}
@@ -0,0 +1,166 @@
+use clap::ValueEnum;
+use tempfile::NamedTempFile;
+
+/// What to do with rows that have more or fewer fields than the header
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaggedHandling {
+    /// Don't look for ragged rows (fastest, the file is read only once)
+    Ignore,
+    /// Stop the comparison if any ragged row is found
+    Error,
+    /// Leave the ragged rows out of the comparison
+    Skip,
+    /// Fill the missing fields with nulls and drop the extra ones
+    Pad,
+}
+
+/// File ready to be read by the comparison, fixed into a temporary file when needed
+pub struct PreparedFile {
+    path: String,
+    fixed_file: Option<NamedTempFile>,
+    pub ragged_rows: u64,
+}
+
+impl PreparedFile {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Size of the fixed copy of the file in the temporary directory (0 if the file isn't copied)
+    pub fn temporary_bytes(&self) -> u64 {
+        self.fixed_file
+            .as_ref()
+            .and_then(|fixed_file| fixed_file.as_file().metadata().ok())
+            .map_or(0, |metadata| metadata.len())
+    }
+}
+
+/// Looks for ragged rows in the file and, when skipping or padding them, writes a fixed copy of the file
+pub fn prepare_file(
+    file_path: &str,
+    separator: char,
+    handling: RaggedHandling,
+) -> Result<PreparedFile, String> {
+    if handling == RaggedHandling::Ignore {
+        return Ok(PreparedFile {
+            path: file_path.to_string(),
+            fixed_file: None,
+            ragged_rows: 0,
+        });
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(file_path)
+        .map_err(|error| format!("Couldn't open file {file_path}: {error}"))?;
+
+    let fixed_file = if handling == RaggedHandling::Error {
+        None
+    } else {
+        Some(
+            tempfile::Builder::new()
+                .prefix("csv-compare-")
+                .suffix(".csv")
+                .tempfile()
+                .map_err(|error| format!("Couldn't create temporary file: {error}"))?,
+        )
+    };
+    let mut writer = match &fixed_file {
+        Some(fixed_file) => Some(
+            csv::WriterBuilder::new()
+                .delimiter(separator as u8)
+                .from_path(fixed_file.path())
+                .map_err(|error| format!("Couldn't write temporary file: {error}"))?,
+        ),
+        None => None,
+    };
+
+    let mut header_len = None;
+    let mut ragged_rows = 0;
+    for record in reader.records() {
+        let mut record =
+            record.map_err(|error| format!("Couldn't parse file {file_path}: {error}"))?;
+        let expected_len = *header_len.get_or_insert(record.len());
+        if record.len() != expected_len {
+            ragged_rows += 1;
+            match handling {
+                RaggedHandling::Skip => continue,
+                RaggedHandling::Pad => {
+                    record.truncate(expected_len);
+                    while record.len() < expected_len {
+                        record.push_field("");
+                    }
+                }
+                RaggedHandling::Ignore | RaggedHandling::Error => {}
+            }
+        }
+        if let Some(writer) = &mut writer {
+            writer
+                .write_record(&record)
+                .map_err(|error| format!("Couldn't write temporary file: {error}"))?;
+        }
+    }
+    if let Some(writer) = &mut writer {
+        writer
+            .flush()
+            .map_err(|error| format!("Couldn't write temporary file: {error}"))?;
+    }
+
+    let path = match &fixed_file {
+        Some(fixed_file) => fixed_file.path().display().to_string(),
+        None => file_path.to_string(),
+    };
+    Ok(PreparedFile {
+        path,
+        fixed_file,
+        ragged_rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const RAGGED: &str = "id,name\n1,Ann\n2\n3,Carl,x\n";
+
+    fn prepare(content: &str, handling: RaggedHandling) -> (NamedTempFile, PreparedFile) {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let prepared = prepare_file(file.path().to_str().unwrap(), ',', handling).unwrap();
+        (file, prepared)
+    }
+
+    #[test]
+    fn ignoring_or_erroring_doesnt_copy_the_file() {
+        for handling in [RaggedHandling::Ignore, RaggedHandling::Error] {
+            let (file, prepared) = prepare(RAGGED, handling);
+            assert_eq!(prepared.path(), file.path().to_str().unwrap());
+            assert_eq!(prepared.temporary_bytes(), 0);
+        }
+        assert_eq!(prepare(RAGGED, RaggedHandling::Ignore).1.ragged_rows, 0);
+        assert_eq!(prepare(RAGGED, RaggedHandling::Error).1.ragged_rows, 2);
+    }
+
+    #[test]
+    fn skips_the_ragged_rows() {
+        let (_file, prepared) = prepare(RAGGED, RaggedHandling::Skip);
+        assert_eq!(prepared.ragged_rows, 2);
+        assert_eq!(
+            std::fs::read_to_string(prepared.path()).unwrap(),
+            "id,name\n1,Ann\n"
+        );
+        assert!(prepared.temporary_bytes() > 0);
+    }
+
+    #[test]
+    fn pads_and_truncates_the_ragged_rows() {
+        let (_file, prepared) = prepare(RAGGED, RaggedHandling::Pad);
+        assert_eq!(
+            std::fs::read_to_string(prepared.path()).unwrap(),
+            "id,name\n1,Ann\n2,\n3,Carl\n"
+        );
+    }
+}
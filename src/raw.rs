@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader};
+
+/// Result of comparing the lines of two files as text, without parsing them
+#[derive(Debug, Default)]
+pub(crate) struct LineComparison {
+    pub first_lines: u64,
+    pub second_lines: u64,
+    /// Lines of the first file not found in the second one (in the same position, unless sorted)
+    pub lines_missing: u64,
+    /// Lines of the second file not found in the first one (in the same position, unless sorted)
+    pub lines_added: u64,
+    /// Number of the first line that is different in both files (only when not sorted)
+    pub first_different_line: Option<u64>,
+}
+
+impl LineComparison {
+    pub fn is_identical(&self) -> bool {
+        self.lines_missing == 0 && self.lines_added == 0
+    }
+}
+
+/// Compares the hashes of the lines of both files, line by line or sorted (ignoring the order of the lines).
+/// Only the hashes are kept in memory, 8 bytes per line
+pub(crate) fn compare_lines(
+    first_file_path: &str,
+    second_file_path: &str,
+    sorted: bool,
+) -> Result<LineComparison> {
+    let mut first_hashes = line_hashes(first_file_path)?;
+    let mut second_hashes = line_hashes(second_file_path)?;
+    let mut comparison = LineComparison {
+        first_lines: first_hashes.len() as u64,
+        second_lines: second_hashes.len() as u64,
+        ..LineComparison::default()
+    };
+    if !sorted {
+        for line in 0..first_hashes.len().max(second_hashes.len()) {
+            let (first_hash, second_hash) = (first_hashes.get(line), second_hashes.get(line));
+            if first_hash == second_hash {
+                continue;
+            }
+            comparison
+                .first_different_line
+                .get_or_insert(line as u64 + 1);
+            comparison.lines_missing += first_hash.is_some() as u64;
+            comparison.lines_added += second_hash.is_some() as u64;
+        }
+        return Ok(comparison);
+    }
+
+    first_hashes.sort_unstable();
+    second_hashes.sort_unstable();
+    let (mut first_line, mut second_line) = (0, 0);
+    while first_line < first_hashes.len() || second_line < second_hashes.len() {
+        match (first_hashes.get(first_line), second_hashes.get(second_line)) {
+            (Some(first_hash), Some(second_hash)) if first_hash == second_hash => {
+                first_line += 1;
+                second_line += 1;
+            }
+            (Some(first_hash), Some(second_hash)) if first_hash > second_hash => {
+                comparison.lines_added += 1;
+                second_line += 1;
+            }
+            (Some(_), _) => {
+                comparison.lines_missing += 1;
+                first_line += 1;
+            }
+            (None, _) => {
+                comparison.lines_added += 1;
+                second_line += 1;
+            }
+        }
+    }
+    Ok(comparison)
+}
+
+/// Hashes of the lines of the file, without their line terminators
+fn line_hashes(file_path: &str) -> Result<Vec<u64>> {
+    let file = File::open(file_path).with_context(|| format!("Couldn't open file {file_path}"))?;
+    BufReader::new(file)
+        .split(b'\n')
+        .map(|line| {
+            let line = line.with_context(|| format!("Couldn't read file {file_path}"))?;
+            let mut hasher = DefaultHasher::new();
+            line.strip_suffix(b"\r").unwrap_or(&line).hash(&mut hasher);
+            Ok(hasher.finish())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn compare(first: &str, second: &str, sorted: bool) -> LineComparison {
+        let (first, second) = (file(first), file(second));
+        compare_lines(
+            first.path().to_str().unwrap(),
+            second.path().to_str().unwrap(),
+            sorted,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn identical_files_ignoring_line_terminators() {
+        let comparison = compare("a,b\n1,2\n", "a,b\r\n1,2", false);
+        assert!(comparison.is_identical());
+        assert_eq!(comparison.first_lines, 2);
+        assert_eq!(comparison.first_different_line, None);
+    }
+
+    #[test]
+    fn finds_the_first_different_line() {
+        let comparison = compare("a\n1\n2\n3\n", "a\n1\nx\n3\n4\n", false);
+        assert_eq!(comparison.first_different_line, Some(3));
+        assert_eq!(comparison.lines_missing, 1);
+        assert_eq!(comparison.lines_added, 2);
+    }
+
+    #[test]
+    fn sorted_lines_ignore_the_order() {
+        let comparison = compare("a\n1\n2\n3\n", "a\n3\n1\n2\n", true);
+        assert!(comparison.is_identical());
+        let comparison = compare("a\n1\n1\n2\n", "a\n2\n1\n3\n", true);
+        assert_eq!(comparison.lines_missing, 1);
+        assert_eq!(comparison.lines_added, 1);
+        assert_eq!(comparison.first_different_line, None);
+    }
+
+    #[test]
+    fn empty_files() {
+        assert!(compare("", "", false).is_identical());
+        let comparison = compare("", "a\n", true);
+        assert_eq!(comparison.lines_missing, 0);
+        assert_eq!(comparison.lines_added, 1);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::report::ComparisonReport;
+use crate::Args;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+/// How the values of sensitive columns are hidden in the reports
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactMode {
+    /// Replace the value with asterisks
+    Mask,
+    /// Replace the value with a short keyed hash (HMAC-SHA256), so equal values can still be matched but the
+    /// values can't be guessed from it without the key
+    Hash,
+}
+
+/// Hides the values of the given columns in every part of the report that shows them: the samples of differences
+/// and the most frequent values. If the key column is redacted, the keys of the samples, of the keys with a
+/// different number of rows and of the duplicated keys are hidden too. It must be called once, before printing the
+/// report. The encoding differences aren't redacted, --encoding-differences can't be used with --redact
+pub fn redact_report(report: &mut ComparisonReport, args: &Args, key: &str) -> Result<()> {
+    let columns = &args.redact;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let mode = match args.redact_mode {
+        RedactMode::Mask => Redaction::Mask,
+        RedactMode::Hash => Redaction::Hash(hash_key(args.redact_key_file.as_deref())?),
+    };
+    let mode = &mode;
+    let redact_key = columns.iter().any(|column| column == key);
+    if redact_key {
+        for difference in &mut report.key_count_differences {
+            difference.key = redact(&difference.key, mode);
+        }
+        for duplicated_key in report
+            .duplicated_keys_first
+            .iter_mut()
+            .chain(report.duplicated_keys_second.iter_mut())
+        {
+            duplicated_key.key = redact(&duplicated_key.key, mode);
+        }
+    }
+    for difference in &mut report.top_values_differences {
+        if columns.contains(&difference.column) {
+            for value_count in difference
+                .first_values
+                .iter_mut()
+                .chain(difference.second_values.iter_mut())
+            {
+                value_count.value = redact(&value_count.value, mode);
+            }
+        }
+    }
+    for difference in &mut report.column_differences {
+        let redact_values = columns.contains(&difference.column);
+        for sample in &mut difference.samples {
+            if redact_key {
+                sample.key = redact(&sample.key, mode);
+            }
+            if redact_values {
+                sample.first_value = redact(&sample.first_value, mode);
+                sample.second_value = redact(&sample.second_value, mode);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How the values are hidden, with the key of the hashes
+enum Redaction {
+    Mask,
+    Hash(Vec<u8>),
+}
+
+/// Key of the hashes: the content of the key file, so the hashes of different runs can be matched, or a random key
+/// of this run, whose hashes only match within its reports
+fn hash_key(key_file: Option<&str>) -> Result<Vec<u8>> {
+    static RUN_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+    match key_file {
+        Some(key_file) => {
+            let key = std::fs::read_to_string(key_file)
+                .with_context(|| format!("Couldn't read the key of the hashes {key_file}"))?;
+            if key.trim().is_empty() {
+                anyhow::bail!("The key of the hashes {key_file} is empty");
+            }
+            Ok(key.trim().as_bytes().to_vec())
+        }
+        None => Ok(RUN_KEY
+            .get_or_init(|| {
+                let mut key = [0; 32];
+                getrandom::getrandom(&mut key).expect("Couldn't generate the key of the hashes");
+                key
+            })
+            .to_vec()),
+    }
+}
+
+fn redact(value: &Option<String>, mode: &Redaction) -> Option<String> {
+    value.as_ref().map(|value| match mode {
+        Redaction::Mask => "****".to_string(),
+        Redaction::Hash(key) => {
+            let mut hmac =
+                Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+            hmac.update(value.as_bytes());
+            let digest = hmac.finalize().into_bytes();
+            let hex: String = digest[..8]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            format!("#{hex}")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{
+        ColumnDifference, DifferenceSample, DuplicatedKey, KeyCountDifference, TopValuesDifference,
+        ValueCount,
+    };
+    use sha2::Digest;
+    use std::io::Write;
+
+    const SECRET: &str = "alice@example.com";
+
+    fn args(options: &[&str]) -> Args {
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        Args::from_options("a.csv", "b.csv", &options).unwrap()
+    }
+
+    fn secret() -> Option<String> {
+        Some(SECRET.to_string())
+    }
+
+    /// Report with the secret value as key and as value of the email column everywhere it can be shown
+    fn report() -> ComparisonReport {
+        ComparisonReport {
+            column_differences: vec![ColumnDifference {
+                column: "email".to_string(),
+                differing_rows: 1,
+                values_changed: 1,
+                null_on_one_side: 0,
+                whitespace_only: 0,
+                case_only: 0,
+                dtype_mismatch: false,
+                samples: vec![DifferenceSample {
+                    key: secret(),
+                    first_value: secret(),
+                    second_value: Some("bob@example.com".to_string()),
+                }],
+            }],
+            top_values_differences: vec![TopValuesDifference {
+                column: "email".to_string(),
+                first_values: vec![ValueCount {
+                    value: secret(),
+                    count: 2,
+                }],
+                second_values: vec![],
+            }],
+            key_count_differences: vec![KeyCountDifference {
+                key: secret(),
+                first_count: 1,
+                second_count: 2,
+            }],
+            duplicated_keys_first: vec![DuplicatedKey {
+                key: secret(),
+                rows: 2,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn redacted_json(args: &Args) -> String {
+        let mut report = report();
+        redact_report(&mut report, args, "email").unwrap();
+        serde_json::to_string(&report).unwrap()
+    }
+
+    #[test]
+    fn masks_the_values_and_keys() {
+        let json = redacted_json(&args(&["--redact", "email"]));
+        assert!(!json.contains(SECRET));
+        assert!(json.contains("****"));
+    }
+
+    #[test]
+    fn hashes_never_show_the_values_or_their_plain_hash() {
+        let json = redacted_json(&args(&["--redact", "email", "--redact-mode", "hash"]));
+        assert!(!json.contains(SECRET));
+        let plain_hash: String = Sha256::digest(SECRET.as_bytes())[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert!(!json.contains(&plain_hash));
+        assert!(!json.contains(&plain_hash[..12]));
+    }
+
+    #[test]
+    fn equal_values_have_the_same_hash() {
+        let mut report = report();
+        redact_report(
+            &mut report,
+            &args(&["--redact", "email", "--redact-mode", "hash"]),
+            "email",
+        )
+        .unwrap();
+        let sample = &report.column_differences[0].samples[0];
+        assert_eq!(sample.key, sample.first_value);
+        assert_ne!(sample.first_value, sample.second_value);
+        assert_eq!(report.key_count_differences[0].key, sample.key);
+        assert_eq!(report.duplicated_keys_first[0].key, sample.key);
+        assert_eq!(
+            report.top_values_differences[0].first_values[0].value,
+            sample.key
+        );
+    }
+
+    #[test]
+    fn hashes_depend_on_the_key_file() {
+        let key_file = |key: &str| {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(key.as_bytes()).unwrap();
+            file
+        };
+        let (first_key, second_key) = (key_file("first secret\n"), key_file("second secret"));
+        let hashed_with = |key_file: &tempfile::NamedTempFile| {
+            redacted_json(&args(&[
+                "--redact",
+                "email",
+                "--redact-mode",
+                "hash",
+                "--redact-key-file",
+                key_file.path().to_str().unwrap(),
+            ]))
+        };
+        assert_eq!(hashed_with(&first_key), hashed_with(&first_key));
+        assert_ne!(hashed_with(&first_key), hashed_with(&second_key));
+        assert!(!hashed_with(&first_key).contains(SECRET));
+
+        let empty_key = key_file(" \n");
+        let mut report = report();
+        let args = args(&[
+            "--redact",
+            "email",
+            "--redact-mode",
+            "hash",
+            "--redact-key-file",
+            empty_key.path().to_str().unwrap(),
+        ]);
+        assert!(redact_report(&mut report, &args, "email").is_err());
+    }
+}
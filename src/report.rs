@@ -0,0 +1,726 @@
+use crate::shard::Shard;
+use crate::table;
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+/// Version of the JSON report. Fields may be added without changing it, but it's increased
+/// whenever a field is removed, renamed or changes its type
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Version of the schema of a report. Reports written before versioning them have version 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct SchemaVersion(pub u32);
+
+impl SchemaVersion {
+    fn unversioned() -> Self {
+        SchemaVersion(0)
+    }
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        SchemaVersion(REPORT_SCHEMA_VERSION)
+    }
+}
+
+/// Final outcome of comparing the two files
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonResult {
+    #[default]
+    Identical,
+    DifferentColumns,
+    DifferentValues,
+    DifferentRows,
+    /// Some column has a different data type in each file (only in typed mode)
+    DifferentTypes,
+    /// The comparison was cancelled before comparing all the columns
+    Aborted,
+    /// The comparison took longer than the timeout and stopped before comparing all the columns
+    TimedOut,
+    /// Some of the files couldn't be compared because it's malformed (or couldn't be read)
+    InvalidFile,
+    /// Both files are empty, or have the same columns but no rows
+    Empty,
+    /// The files are identical, but their columns are in a different order
+    DifferentColumnOrder,
+    /// The comparison couldn't run: a file couldn't be read or parsed, there wasn't enough memory...
+    Failed,
+    /// The key has duplicated values in some of the files (only with --assert-unique-key)
+    DuplicatedKeys,
+    /// The files are identical, but some of their values don't follow the assertions (--assertions)
+    FailedAssertions,
+}
+
+impl ComparisonResult {
+    pub const ALL: [ComparisonResult; 13] = [
+        ComparisonResult::Identical,
+        ComparisonResult::DifferentColumns,
+        ComparisonResult::DifferentValues,
+        ComparisonResult::DifferentRows,
+        ComparisonResult::DifferentTypes,
+        ComparisonResult::Aborted,
+        ComparisonResult::TimedOut,
+        ComparisonResult::InvalidFile,
+        ComparisonResult::Empty,
+        ComparisonResult::DifferentColumnOrder,
+        ComparisonResult::Failed,
+        ComparisonResult::DuplicatedKeys,
+        ComparisonResult::FailedAssertions,
+    ];
+
+    /// Name of the result, as written in the JSON report
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComparisonResult::Identical => "identical",
+            ComparisonResult::DifferentColumns => "different_columns",
+            ComparisonResult::DifferentValues => "different_values",
+            ComparisonResult::DifferentRows => "different_rows",
+            ComparisonResult::DifferentTypes => "different_types",
+            ComparisonResult::Aborted => "aborted",
+            ComparisonResult::TimedOut => "timed_out",
+            ComparisonResult::InvalidFile => "invalid_file",
+            ComparisonResult::Empty => "empty",
+            ComparisonResult::DifferentColumnOrder => "different_column_order",
+            ComparisonResult::Failed => "failed",
+            ComparisonResult::DuplicatedKeys => "duplicated_keys",
+            ComparisonResult::FailedAssertions => "failed_assertions",
+        }
+    }
+}
+
+/// Everything that was found while comparing the two files
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ComparisonReport {
+    /// Version of the structure of the report, see REPORT_SCHEMA_VERSION
+    #[serde(default = "SchemaVersion::unversioned")]
+    pub schema_version: SchemaVersion,
+    pub result: ComparisonResult,
+    pub batches: Vec<BatchTiming>,
+    pub rows: u64,
+    pub column_differences: Vec<ColumnDifference>,
+    /// Rows whose key is in the first file but not in the second one
+    pub rows_missing: u64,
+    /// Rows whose key is in the second file but not in the first one
+    pub rows_added: u64,
+    /// Positions where the columns are in a different order (only possible without strict column order)
+    pub column_order_differences: Vec<ColumnOrderDifference>,
+    /// Columns with a different name in each file compared anyway because of their position
+    pub columns_matched_by_position: Vec<ColumnOrderDifference>,
+    /// Columns whose values have been compared (with or without differences)
+    pub columns_compared: Vec<String>,
+    /// Rows whose text differs in both files only in how the values are encoded, e.g. quoted or unquoted (only
+    /// with --encoding-differences)
+    pub encoding_only_rows: u64,
+    /// Some of the rows whose text differs only in how the values are encoded
+    pub encoding_differences: Vec<EncodingDifference>,
+    /// Rows with more or fewer fields than the header in each file (only when looking for them)
+    pub ragged_rows_first: u64,
+    pub ragged_rows_second: u64,
+    /// Column names that appear more than once in each file
+    pub duplicate_headers_first: Vec<String>,
+    pub duplicate_headers_second: Vec<String>,
+    /// Columns whose inferred data type is different in each file (only in typed mode)
+    pub column_type_differences: Vec<ColumnTypeDifference>,
+    /// Keys with a different number of rows in each file (only with --key-counts)
+    pub keys_with_different_count: u64,
+    /// Some of the keys with a different number of rows, in order
+    pub key_count_differences: Vec<KeyCountDifference>,
+    /// Keys that appear in more than one row of each file (only with --assert-unique-key), the most repeated first
+    pub duplicated_keys_first: Vec<DuplicatedKey>,
+    pub duplicated_keys_second: Vec<DuplicatedKey>,
+    /// Columns with a different number of nulls in each file (only with --nulls-only)
+    pub null_count_differences: Vec<NullCountDifference>,
+    /// Columns whose number of distinct values changed more than the tolerance (only with --cardinality)
+    pub cardinality_differences: Vec<CardinalityDifference>,
+    /// Columns whose most frequent values changed (only with --top-values)
+    pub top_values_differences: Vec<TopValuesDifference>,
+    /// Statistics of the columns that drifted more than allowed by the rules (only with --drift-rules)
+    pub drift_violations: Vec<DriftViolation>,
+    /// Data quality assertions (--assertions) that some values of the files don't follow
+    pub assertion_failures: Vec<AssertionFailure>,
+    /// Fingerprints of the columns of the first file (only with --fingerprints), to compare other files
+    /// with it later with --baseline-report
+    pub fingerprints: Vec<ColumnFingerprint>,
+    /// Part of the columns compared when the comparison is split in shards
+    pub shard: Option<Shard>,
+    /// Resources used by the comparison
+    pub resources: ResourceUsage,
+    /// Why the comparison couldn't finish, with the file, column and line where it failed
+    pub error: Option<String>,
+}
+
+/// Memory, disk and time used by a comparison. Memory and bytes read are measured for the whole process
+/// (only in Linux), so they include other comparisons running at the same time in the HTTP service
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ResourceUsage {
+    #[serde(
+        rename = "elapsed_seconds",
+        serialize_with = "serialize_seconds",
+        deserialize_with = "deserialize_seconds"
+    )]
+    #[schemars(with = "f64")]
+    pub elapsed: Duration,
+    pub peak_memory_bytes: Option<u64>,
+    pub bytes_read: Option<u64>,
+    /// Size of the two files compared
+    pub input_bytes: u64,
+    /// Size of the temporary copies of the files (e.g. with ragged rows fixed)
+    pub temporary_disk_bytes: u64,
+}
+
+/// Column names found at the same position in each file
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ColumnOrderDifference {
+    pub position: usize,
+    pub first_column: String,
+    pub second_column: String,
+}
+
+/// Data types inferred for a column in each file
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ColumnTypeDifference {
+    pub column: String,
+    pub first_type: String,
+    pub second_type: String,
+}
+
+/// Number of rows with a different value in a column
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ColumnDifference {
+    pub column: String,
+    pub differing_rows: u64,
+    /// Rows where both values are present but different
+    pub values_changed: u64,
+    /// Rows where the value is null in only one of the files
+    pub null_on_one_side: u64,
+    /// Changed values that are the same once trimmed (cosmetic differences)
+    #[serde(default)]
+    pub whitespace_only: u64,
+    /// Changed values that are the same once trimmed and lowercased, but not only trimmed (cosmetic differences)
+    #[serde(default)]
+    pub case_only: u64,
+    /// The column has a different data type in each file
+    pub dtype_mismatch: bool,
+    /// Some examples of rows with different values
+    pub samples: Vec<DifferenceSample>,
+}
+
+/// Hash of the values of a column that doesn't depend on the order of the rows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ColumnFingerprint {
+    pub column: String,
+    /// Hexadecimal SHA-256
+    pub hash: String,
+    pub nulls: u64,
+}
+
+/// Number of rows of a key in each file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyCountDifference {
+    pub key: Option<String>,
+    pub first_count: u64,
+    pub second_count: u64,
+}
+
+/// Text of a row with the same values in both files, encoded differently
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EncodingDifference {
+    pub key: Option<String>,
+    pub first_line: String,
+    pub second_line: String,
+}
+
+/// Number of nulls of a column in each file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NullCountDifference {
+    pub column: String,
+    pub first_nulls: u64,
+    pub second_nulls: u64,
+}
+
+/// Number of distinct values of a column in each file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CardinalityDifference {
+    pub column: String,
+    pub first_distinct: u64,
+    pub second_distinct: u64,
+}
+
+/// Most frequent values of a column in each file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TopValuesDifference {
+    pub column: String,
+    pub first_values: Vec<ValueCount>,
+    pub second_values: Vec<ValueCount>,
+}
+
+/// A value and its number of rows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ValueCount {
+    pub value: Option<String>,
+    pub count: u64,
+}
+
+/// What happens when a statistic drifts more than allowed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftSeverity {
+    /// Only show it
+    Warn,
+    /// The files are different
+    #[default]
+    Fail,
+}
+
+/// Statistic of a column that drifted more than allowed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DriftViolation {
+    pub column: String,
+    /// mean, null_rate (percentage of nulls) or distinct (number of distinct values)
+    pub metric: String,
+    pub first_value: Option<f64>,
+    pub second_value: Option<f64>,
+    /// Change in percentage (percentage points for the null rate)
+    pub drift: f64,
+    pub max_drift: f64,
+    pub severity: DriftSeverity,
+}
+
+/// Assertion of a column with values that don't follow it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AssertionFailure {
+    pub column: String,
+    pub assertion: String,
+    /// Values of each file that don't follow the assertion
+    pub first_file: u64,
+    pub second_file: u64,
+}
+
+/// Key that appears in more than one row of a file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DuplicatedKey {
+    pub key: Option<String>,
+    pub rows: u64,
+}
+
+/// Values of a column in both files for a row with a difference
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DifferenceSample {
+    pub key: Option<String>,
+    pub first_value: Option<String>,
+    pub second_value: Option<String>,
+}
+
+/// How long it took to compare a batch of columns
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BatchTiming {
+    pub columns: Vec<String>,
+    #[serde(
+        rename = "elapsed_seconds",
+        serialize_with = "serialize_seconds",
+        deserialize_with = "deserialize_seconds"
+    )]
+    #[schemars(with = "f64")]
+    pub elapsed: Duration,
+    pub peak_memory_bytes: Option<u64>,
+}
+
+fn serialize_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn deserialize_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Duration::try_from_secs_f64(f64::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+impl ComparisonReport {
+    /// Table with the time spent comparing each batch of columns, slowest first
+    pub fn timings_table(&self) -> String {
+        let mut batches: Vec<&BatchTiming> = self.batches.iter().collect();
+        batches.sort_by(|a, b| b.elapsed.cmp(&a.elapsed));
+
+        let mut table = table::new_table(vec!["Column(s)", "Seconds", "Peak memory (MB)"]);
+        for batch in batches {
+            table.add_row(vec![
+                table::truncate(&batch.columns.join(" | ")),
+                format!("{:.3}", batch.elapsed.as_secs_f64()),
+                batch
+                    .peak_memory_bytes
+                    .map(|bytes| format!("{:.1}", bytes as f64 / (1024.0 * 1024.0)))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+        let total: f64 = self.batches.iter().map(|b| b.elapsed.as_secs_f64()).sum();
+        table.add_row(vec![
+            "Total".to_string(),
+            format!("{:.3}", total),
+            "".to_string(),
+        ]);
+        table::align_right(&mut table, &[1, 2]);
+        table.to_string()
+    }
+
+    /// Table with the columns that have different values, the ones with more different rows first
+    pub fn differences_table(&self) -> String {
+        let mut differences: Vec<&ColumnDifference> = self.column_differences.iter().collect();
+        differences.sort_by(|a, b| b.differing_rows.cmp(&a.differing_rows));
+
+        let mut table = table::new_table(vec!["Column", "Different rows", "Mismatch %"]);
+        for difference in differences {
+            let percent = if self.rows == 0 {
+                0.0
+            } else {
+                difference.differing_rows as f64 * 100.0 / self.rows as f64
+            };
+            table.add_row(vec![
+                table::truncate(&self.column_label(&difference.column)),
+                difference.differing_rows.to_string(),
+                format!("{:.2}", percent),
+            ]);
+        }
+        table::align_right(&mut table, &[1, 2]);
+        table.to_string()
+    }
+
+    /// Counts of the differences aggregated by category
+    pub fn categories_table(&self) -> String {
+        let values_changed: u64 = self
+            .column_differences
+            .iter()
+            .map(|difference| difference.values_changed)
+            .sum();
+        let null_on_one_side: u64 = self
+            .column_differences
+            .iter()
+            .map(|difference| difference.null_on_one_side)
+            .sum();
+        let dtype_mismatches = self
+            .column_differences
+            .iter()
+            .filter(|difference| difference.dtype_mismatch)
+            .count() as u64;
+        let (whitespace_only, case_only) = self.cosmetic_differences();
+
+        let mut table = table::new_table(vec!["Category", "Count"]);
+        for (category, count) in [
+            ("Rows missing", self.rows_missing),
+            ("Rows added", self.rows_added),
+            ("Values changed", values_changed),
+            ("Null on one side", null_on_one_side),
+            ("Whitespace only", whitespace_only),
+            ("Case only", case_only),
+            ("Dtype mismatches", dtype_mismatches),
+        ] {
+            table.add_row(vec![category.to_string(), count.to_string()]);
+        }
+        table::align_right(&mut table, &[1]);
+        table.to_string()
+    }
+
+    /// Changed values that only differ in whitespace around them, and in case
+    pub fn cosmetic_differences(&self) -> (u64, u64) {
+        self.column_differences
+            .iter()
+            .fold((0, 0), |(whitespace_only, case_only), difference| {
+                (
+                    whitespace_only + difference.whitespace_only,
+                    case_only + difference.case_only,
+                )
+            })
+    }
+
+    /// Name of the column, with its name in the second file when it was matched by position
+    fn column_label(&self, column: &str) -> String {
+        match self
+            .columns_matched_by_position
+            .iter()
+            .find(|pair| pair.first_column == column)
+        {
+            Some(pair) => format!("{} <> {}", pair.first_column, pair.second_column),
+            None => column.to_string(),
+        }
+    }
+
+    /// Table with the time, memory and disk used by the comparison
+    pub fn resources_table(&self) -> String {
+        let megabytes = |bytes: u64| format!("{:.1}", bytes as f64 / (1024.0 * 1024.0));
+        let resources = &self.resources;
+        let mut table = table::new_table(vec!["Resource", "Usage"]);
+        for (resource, usage) in [
+            ("Seconds", format!("{:.3}", resources.elapsed.as_secs_f64())),
+            (
+                "Peak memory (MB)",
+                resources
+                    .peak_memory_bytes
+                    .map_or("-".to_string(), megabytes),
+            ),
+            (
+                "Read (MB)",
+                resources.bytes_read.map_or("-".to_string(), megabytes),
+            ),
+            ("Input files (MB)", megabytes(resources.input_bytes)),
+            (
+                "Temporary disk (MB)",
+                megabytes(resources.temporary_disk_bytes),
+            ),
+        ] {
+            table.add_row(vec![resource.to_string(), usage]);
+        }
+        table::align_right(&mut table, &[1]);
+        table.to_string()
+    }
+
+    /// Columns with a different data type in each file
+    pub fn types_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "First file", "Second file"]);
+        for difference in &self.column_type_differences {
+            table.add_row(vec![
+                table::truncate(&difference.column),
+                difference.first_type.to_string(),
+                difference.second_type.to_string(),
+            ]);
+        }
+        table.to_string()
+    }
+
+    /// Keys with a different number of rows in each file
+    pub fn key_counts_table(&self) -> String {
+        let mut table = table::new_table(vec!["Key", "First file", "Second file"]);
+        for difference in &self.key_count_differences {
+            table.add_row(vec![
+                table::truncate(display_value(&difference.key)),
+                difference.first_count.to_string(),
+                difference.second_count.to_string(),
+            ]);
+        }
+        table::align_right(&mut table, &[1, 2]);
+        table.to_string()
+    }
+
+    /// Text of the rows with the same values encoded differently in each file
+    pub fn encoding_table(&self) -> String {
+        let mut table = table::new_table(vec!["Key", "First file", "Second file"]);
+        for difference in &self.encoding_differences {
+            table.add_row(vec![
+                table::truncate(display_value(&difference.key)),
+                table::truncate(&difference.first_line),
+                table::truncate(&difference.second_line),
+            ]);
+        }
+        table.to_string()
+    }
+
+    /// Columns with a different number of nulls in each file
+    pub fn null_counts_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "First file", "Second file"]);
+        for difference in &self.null_count_differences {
+            table.add_row(vec![
+                table::truncate(&self.column_label(&difference.column)),
+                difference.first_nulls.to_string(),
+                difference.second_nulls.to_string(),
+            ]);
+        }
+        table::align_right(&mut table, &[1, 2]);
+        table.to_string()
+    }
+
+    /// Columns whose number of distinct values changed
+    pub fn cardinality_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "First file", "Second file"]);
+        for difference in &self.cardinality_differences {
+            table.add_row(vec![
+                table::truncate(&self.column_label(&difference.column)),
+                difference.first_distinct.to_string(),
+                difference.second_distinct.to_string(),
+            ]);
+        }
+        table::align_right(&mut table, &[1, 2]);
+        table.to_string()
+    }
+
+    /// Most frequent values of the columns where they changed, side by side
+    pub fn top_values_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "Rank", "First file", "Second file"]);
+        let describe = |value_count: Option<&ValueCount>| {
+            value_count.map_or(String::new(), |value_count| {
+                format!(
+                    "{} ({})",
+                    table::truncate(display_value(&value_count.value)),
+                    value_count.count
+                )
+            })
+        };
+        for difference in &self.top_values_differences {
+            let ranks = difference
+                .first_values
+                .len()
+                .max(difference.second_values.len());
+            for rank in 0..ranks {
+                table.add_row(vec![
+                    table::truncate(&self.column_label(&difference.column)),
+                    (rank + 1).to_string(),
+                    describe(difference.first_values.get(rank)),
+                    describe(difference.second_values.get(rank)),
+                ]);
+            }
+        }
+        table::align_right(&mut table, &[1]);
+        table.to_string()
+    }
+
+    /// Statistics that drifted more than allowed
+    pub fn drift_table(&self) -> String {
+        let mut table = table::new_table(vec![
+            "Column",
+            "Metric",
+            "First file",
+            "Second file",
+            "Drift",
+            "Allowed",
+            "Severity",
+        ]);
+        let describe =
+            |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:.4}", value));
+        for violation in &self.drift_violations {
+            table.add_row(vec![
+                table::truncate(&self.column_label(&violation.column)),
+                violation.metric.to_string(),
+                describe(violation.first_value),
+                describe(violation.second_value),
+                format!("{:.2}", violation.drift),
+                format!("{:.2}", violation.max_drift),
+                match violation.severity {
+                    DriftSeverity::Warn => "warn".to_string(),
+                    DriftSeverity::Fail => "fail".to_string(),
+                },
+            ]);
+        }
+        table::align_right(&mut table, &[2, 3, 4, 5]);
+        table.to_string()
+    }
+
+    /// Assertions that some values don't follow, with how many of them in each file
+    pub fn assertions_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "Assertion", "First file", "Second file"]);
+        for failure in &self.assertion_failures {
+            table.add_row(vec![
+                table::truncate(&failure.column),
+                table::truncate(&failure.assertion),
+                failure.first_file.to_string(),
+                failure.second_file.to_string(),
+            ]);
+        }
+        table::align_right(&mut table, &[2, 3]);
+        table.to_string()
+    }
+
+    /// Keys repeated in some of the files, the most repeated first
+    pub fn duplicated_keys_table(&self) -> String {
+        let mut table = table::new_table(vec!["File", "Key", "Rows"]);
+        for (file, duplicated_keys) in [
+            ("First", &self.duplicated_keys_first),
+            ("Second", &self.duplicated_keys_second),
+        ] {
+            for duplicated_key in duplicated_keys {
+                table.add_row(vec![
+                    file.to_string(),
+                    table::truncate(display_value(&duplicated_key.key)),
+                    duplicated_key.rows.to_string(),
+                ]);
+            }
+        }
+        table::align_right(&mut table, &[2]);
+        table.to_string()
+    }
+
+    /// Examples of different values for each different column
+    pub fn samples_table(&self) -> String {
+        let mut table = table::new_table(vec!["Column", "Key", "First file", "Second file"]);
+        for difference in &self.column_differences {
+            for sample in &difference.samples {
+                table.add_row(vec![
+                    table::truncate(&self.column_label(&difference.column)),
+                    table::truncate(display_value(&sample.key)),
+                    table::truncate(display_value(&sample.first_value)),
+                    table::truncate(display_value(&sample.second_value)),
+                ]);
+            }
+        }
+        table.to_string()
+    }
+}
+
+/// Table comparing the columns of both files position by position
+pub fn schema_table(first_file_cols: &[String], second_file_cols: &[String]) -> String {
+    let mut table = table::new_table(vec!["Position", "First file", "Second file"]);
+    for position in 0..first_file_cols.len().max(second_file_cols.len()) {
+        table.add_row(vec![
+            (position + 1).to_string(),
+            table::truncate(first_file_cols.get(position).map_or("", String::as_str)),
+            table::truncate(second_file_cols.get(position).map_or("", String::as_str)),
+        ]);
+    }
+    table::align_right(&mut table, &[0]);
+    table.to_string()
+}
+
+fn display_value(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("<null>")
+}
+
+/// JSON schema of the report, generated from its types
+pub fn json_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(ComparisonReport))
+        .expect("Schema is always serializable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(column: &str, millis: u64) -> BatchTiming {
+        BatchTiming {
+            columns: vec![column.to_string()],
+            elapsed: Duration::from_millis(millis),
+            peak_memory_bytes: None,
+        }
+    }
+
+    #[test]
+    fn timings_are_written_in_seconds() {
+        let json = serde_json::to_value(batch("name", 1500)).unwrap();
+        assert_eq!(json["elapsed_seconds"], 1.5);
+        let timing: BatchTiming = serde_json::from_value(json).unwrap();
+        assert_eq!(timing.elapsed, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn the_slowest_batches_are_shown_first() {
+        let report = ComparisonReport {
+            batches: vec![batch("fast", 100), batch("slow", 2000)],
+            ..Default::default()
+        };
+        let table = report.timings_table();
+        assert!(table.find("slow").unwrap() < table.find("fast").unwrap());
+        assert!(table.contains("2.100"));
+    }
+}
@@ -0,0 +1,48 @@
+/// Peak resident memory (high water mark) of this process in bytes.
+/// Only available in Linux, where it's read from /proc/self/status
+pub fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// Bytes read by this process so far (from disk or cache), in Linux read from /proc/self/io
+pub fn bytes_read() -> Option<u64> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    let line = io.lines().find(|line| line.starts_with("rchar:"))?;
+    line.trim_start_matches("rchar:").trim().parse().ok()
+}
+
+/// Size of the file in bytes, 0 if it can't be read
+pub fn file_size(file_path: &str) -> u64 {
+    std::fs::metadata(file_path).map_or(0, |metadata| metadata.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn measures_the_size_of_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"id,name\n1,Ann\n").unwrap();
+        assert_eq!(file_size(file.path().to_str().unwrap()), 14);
+        assert_eq!(file_size("missing.csv"), 0);
+    }
+
+    #[test]
+    fn reads_the_memory_and_bytes_read_in_linux() {
+        if cfg!(target_os = "linux") {
+            assert!(peak_memory_bytes().unwrap() > 0);
+            assert!(bytes_read().is_some());
+        }
+    }
+}
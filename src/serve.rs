@@ -0,0 +1,666 @@
+use crate::metrics::ServiceMetrics;
+use crate::output::Printer;
+use crate::report::{ColumnDifference, ComparisonReport};
+use crate::{compare_files_observed, Args, CancellationToken, ComparisonObserver};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{error, info};
+
+/// Threads accepting HTTP requests. Comparisons run in the worker pool, not in these threads
+const HTTP_THREADS: usize = 4;
+
+/// Finished jobs kept at most, the oldest are removed first even before their time to live
+const MAX_FINISHED_JOBS: usize = 1000;
+
+/// Options of the comparison that the requests can use. The rest aren't accepted: they write files, read other files
+/// (rules, keys, credentials, config), send requests to other hosts or change the output of the service
+const REQUEST_OPTIONS: &[&str] = &[
+    "--strict-column-order",
+    "--number-of-columns",
+    "--all-columns",
+    "--likely-different-first",
+    "--sort-by",
+    "--key-type",
+    "--nulls-first",
+    "--nulls-last",
+    "--descending",
+    "--start-at-column",
+    "--categorical-threshold",
+    "--low-memory",
+    "--key-partitions",
+    "--separator",
+    "--on-ragged",
+    "--typed",
+    "--headers-only",
+    "--count-only",
+    "--raw",
+    "--raw-sort",
+    "--assert-unique-key",
+    "--nulls-only",
+    "--cardinality",
+    "--top-values",
+    "--cardinality-tolerance",
+    "--encoding-differences",
+    "--key-counts",
+    "--strip-prefix1",
+    "--strip-suffix1",
+    "--strip-prefix2",
+    "--strip-suffix2",
+    "--match-by-position",
+    "--ignore-column-case",
+    "--exclude-rows",
+    "--key-range",
+    "--row-count-tolerance",
+    "--nullish-columns",
+    "--json-columns",
+    "--ignore-json-paths",
+    "--list-columns",
+    "--binary-columns",
+    "--dedupe-headers",
+    "--fingerprints",
+    "--since-offset",
+    "--since-key",
+    "--shard",
+    "--archive-member",
+    "--timeout",
+    "--report-all",
+    "--samples",
+    "--redact",
+    "--redact-mode",
+];
+
+/// Body of the requests to compare two files
+#[derive(Deserialize, Debug)]
+struct CompareRequest {
+    file1: String,
+    file2: String,
+    /// Command line options for the comparison, e.g. ["--separator", ";", "--report-all"]. Only the long
+    /// options of `REQUEST_OPTIONS` are accepted
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum JobStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// How far the comparison of a job has gone
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+struct JobProgress {
+    /// Size of both files
+    input_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_file_rows: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    second_file_rows: Option<u64>,
+    columns_compared: u64,
+    different_columns: u64,
+}
+
+#[derive(Serialize, Debug)]
+struct JobState {
+    id: u64,
+    status: JobStatus,
+    progress: JobProgress,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<Arc<ComparisonReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip)]
+    finished_at: Option<Instant>,
+}
+
+impl JobState {
+    fn new(id: u64) -> Self {
+        JobState {
+            id,
+            status: JobStatus::Queued,
+            progress: JobProgress::default(),
+            report: None,
+            error: None,
+            finished_at: None,
+        }
+    }
+}
+
+/// Updates the progress of a job as its comparison goes on
+struct JobObserver {
+    jobs: Jobs,
+    id: u64,
+}
+
+impl JobObserver {
+    fn update(&self, update: impl FnOnce(&mut JobProgress)) {
+        if let Some(job) = self
+            .jobs
+            .lock()
+            .expect("Jobs lock poisoned")
+            .get_mut(&self.id)
+        {
+            update(&mut job.progress);
+        }
+    }
+}
+
+impl ComparisonObserver for JobObserver {
+    fn input_size(&self, bytes: u64) {
+        self.update(|progress| progress.input_bytes = bytes);
+    }
+
+    fn row_counts(&self, first_file_rows: u64, second_file_rows: u64) {
+        self.update(|progress| {
+            progress.first_file_rows = Some(first_file_rows);
+            progress.second_file_rows = Some(second_file_rows);
+        });
+    }
+
+    fn columns_finished(&self, columns: &[&String], _are_equal: bool) {
+        self.update(|progress| progress.columns_compared += columns.len() as u64);
+    }
+
+    fn difference_found(&self, _difference: &ColumnDifference) {
+        self.update(|progress| progress.different_columns += 1);
+    }
+}
+
+struct Job {
+    id: u64,
+    args: Args,
+    /// Notified when the job finishes, for synchronous requests
+    done: Option<Sender<()>>,
+}
+
+type Jobs = Arc<Mutex<HashMap<u64, JobState>>>;
+type Metrics = Arc<Mutex<ServiceMetrics>>;
+
+/// Runs the HTTP service until the process is killed:
+/// - POST /compare compares two files and returns the JSON report
+/// - POST /jobs queues a comparison and returns its id
+/// - GET /jobs/{id} returns the status and progress of a job, and its report when finished. Finished jobs are
+///   removed after `job_ttl`
+/// - GET /metrics returns the totals of the comparisons in the Prometheus text format
+///
+/// With a root directory, the files of the requests are paths relative to it, and can't be outside of it
+pub fn run(
+    (host, port): (&str, u16),
+    workers: usize,
+    root: Option<&str>,
+    job_ttl: Duration,
+) -> Result<(), String> {
+    let root = root
+        .map(|root| {
+            Path::new(root)
+                .canonicalize()
+                .map_err(|error| format!("Couldn't open root directory {root}: {error}"))
+        })
+        .transpose()?;
+    let server = Server::http((host, port))
+        .map_err(|error| format!("Couldn't listen on {host}:{port}: {error}"))?;
+    let server = Arc::new(server);
+    let jobs: Jobs = Arc::new(Mutex::new(HashMap::new()));
+    let metrics: Metrics = Arc::new(Mutex::new(ServiceMetrics::default()));
+    let next_id = Arc::new(AtomicU64::new(1));
+    let (job_sender, job_receiver) = channel::<Job>();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+    info!("Listening on {}:{} with {} workers", host, port, workers);
+    for _ in 0..workers.max(1) {
+        let job_receiver = Arc::clone(&job_receiver);
+        let jobs = Arc::clone(&jobs);
+        let metrics = Arc::clone(&metrics);
+        thread::spawn(move || run_worker(job_receiver, jobs, metrics));
+    }
+
+    let handlers: Vec<_> = (0..HTTP_THREADS)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let jobs = Arc::clone(&jobs);
+            let metrics = Arc::clone(&metrics);
+            let next_id = Arc::clone(&next_id);
+            let job_sender = job_sender.clone();
+            let root = root.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(
+                        request,
+                        root.as_deref(),
+                        job_ttl,
+                        &jobs,
+                        &metrics,
+                        &next_id,
+                        &job_sender,
+                    );
+                }
+            })
+        })
+        .collect();
+    for handler in handlers {
+        let _ = handler.join();
+    }
+    Ok(())
+}
+
+fn run_worker(job_receiver: Arc<Mutex<Receiver<Job>>>, jobs: Jobs, metrics: Metrics) {
+    loop {
+        let job = match job_receiver.lock().expect("Job queue lock poisoned").recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        set_status(&jobs, job.id, JobStatus::Running, None, None);
+
+        let printer = Printer::new(true, false, false);
+        let (file1, file2) = (
+            job.args.file1.clone().unwrap_or_default(),
+            job.args.file2.clone().unwrap_or_default(),
+        );
+        let started = Instant::now();
+        let observer = JobObserver {
+            jobs: Arc::clone(&jobs),
+            id: job.id,
+        };
+        match catch_unwind(AssertUnwindSafe(|| {
+            compare_files_observed(
+                &job.args,
+                &printer,
+                &file1,
+                &file2,
+                &CancellationToken::new(),
+                &observer,
+            )
+        })) {
+            Ok(report) => {
+                metrics
+                    .lock()
+                    .expect("Metrics lock poisoned")
+                    .record(&report, started.elapsed());
+                set_status(
+                    &jobs,
+                    job.id,
+                    JobStatus::Finished,
+                    Some(Arc::new(report)),
+                    None,
+                )
+            }
+            Err(panic) => {
+                metrics
+                    .lock()
+                    .expect("Metrics lock poisoned")
+                    .record_failure(started.elapsed());
+                let message = panic
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| panic.downcast_ref::<&str>().map(|text| text.to_string()))
+                    .unwrap_or_else(|| "Comparison failed".to_string());
+                error!("Job {} failed: {}", job.id, message);
+                set_status(&jobs, job.id, JobStatus::Failed, None, Some(message));
+            }
+        }
+        if let Some(done) = job.done {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn set_status(
+    jobs: &Jobs,
+    id: u64,
+    status: JobStatus,
+    report: Option<Arc<ComparisonReport>>,
+    error: Option<String>,
+) {
+    let mut jobs = jobs.lock().expect("Jobs lock poisoned");
+    let job = jobs.entry(id).or_insert_with(|| JobState::new(id));
+    job.status = status;
+    job.report = report;
+    job.error = error;
+    if matches!(status, JobStatus::Finished | JobStatus::Failed) {
+        job.finished_at = Some(Instant::now());
+    }
+}
+
+/// Removes the jobs finished more than `ttl` ago, and the oldest finished ones beyond `MAX_FINISHED_JOBS`
+fn expire_jobs(jobs: &mut HashMap<u64, JobState>, ttl: Duration) {
+    jobs.retain(|_, job| {
+        job.finished_at
+            .map_or(true, |finished_at| finished_at.elapsed() < ttl)
+    });
+    let mut finished: Vec<_> = jobs
+        .values()
+        .filter_map(|job| job.finished_at.map(|finished_at| (finished_at, job.id)))
+        .collect();
+    if finished.len() > MAX_FINISHED_JOBS {
+        finished.sort_unstable();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            jobs.remove(id);
+        }
+    }
+}
+
+fn handle_request(
+    mut request: Request,
+    root: Option<&Path>,
+    job_ttl: Duration,
+    jobs: &Jobs,
+    metrics: &Metrics,
+    next_id: &AtomicU64,
+    job_sender: &Sender<Job>,
+) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let response = match (method, url.as_str()) {
+        (Method::Post, "/compare") | (Method::Post, "/jobs") => {
+            match parse_compare_request(&mut request, root) {
+                Ok(args) => {
+                    let id = next_id.fetch_add(1, Ordering::SeqCst);
+                    expire_jobs(&mut jobs.lock().expect("Jobs lock poisoned"), job_ttl);
+                    set_status(jobs, id, JobStatus::Queued, None, None);
+                    let wait = url == "/compare";
+                    let (done_sender, done_receiver) = channel();
+                    let job = Job {
+                        id,
+                        args,
+                        done: wait.then_some(done_sender),
+                    };
+                    if job_sender.send(job).is_err() {
+                        json_response(503, &ErrorBody::new("Workers are not running"))
+                    } else if wait {
+                        let _ = done_receiver.recv();
+                        let response = job_response(jobs, id);
+                        // Nobody knows the id of a synchronous job to ask for it again
+                        jobs.lock().expect("Jobs lock poisoned").remove(&id);
+                        response
+                    } else {
+                        json_response(202, &IdBody { id })
+                    }
+                }
+                Err(message) => json_response(400, &ErrorBody::new(&message)),
+            }
+        }
+        (Method::Get, path) if path.starts_with("/jobs/") => {
+            match path.trim_start_matches("/jobs/").parse::<u64>() {
+                Ok(id) => job_response(jobs, id),
+                Err(_) => json_response(400, &ErrorBody::new("Invalid job id")),
+            }
+        }
+        (Method::Get, "/metrics") => {
+            let metrics = metrics.lock().expect("Metrics lock poisoned").render();
+            Response::from_string(metrics).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+                    .expect("Valid header"),
+            )
+        }
+        _ => json_response(404, &ErrorBody::new("Not found")),
+    };
+    if let Err(error) = request.respond(response) {
+        error!("Couldn't send response: {}", error);
+    }
+}
+
+/// Reads the body of the request and builds the arguments of the comparison
+fn parse_compare_request(request: &mut Request, root: Option<&Path>) -> Result<Args, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|error| format!("Couldn't read request: {error}"))?;
+    let compare_request: CompareRequest =
+        serde_json::from_str(&body).map_err(|error| format!("Invalid request: {error}"))?;
+    compare_args(compare_request, root)
+}
+
+/// Arguments of the comparison of a request, with its files inside the root directory (if any)
+fn compare_args(compare_request: CompareRequest, root: Option<&Path>) -> Result<Args, String> {
+    if let Some(option) = compare_request
+        .options
+        .iter()
+        .filter(|option| option.starts_with('-'))
+        .map(|option| option.split('=').next().unwrap_or_default())
+        .find(|option| !REQUEST_OPTIONS.contains(option))
+    {
+        return Err(format!("Option {option} can't be used in requests"));
+    }
+    let (file1, file2) = match root {
+        Some(root) => (
+            inside_root(root, &compare_request.file1)?,
+            inside_root(root, &compare_request.file2)?,
+        ),
+        None => (compare_request.file1, compare_request.file2),
+    };
+    let args = Args::from_options(&file1, &file2, &compare_request.options)?;
+    if args.command.is_some()
+        || args.file1.as_deref() != Some(file1.as_str())
+        || args.file2.as_deref() != Some(file2.as_str())
+    {
+        return Err("The options can't have other files or commands".to_string());
+    }
+    Ok(args)
+}
+
+/// Path of a file of a request in the root directory. Absolute paths, URLs, parent directories and symbolic links
+/// that leave the root are rejected
+fn inside_root(root: &Path, file: &str) -> Result<String, String> {
+    let relative = Path::new(file);
+    let outside = || format!("File {file} is not inside the root directory");
+    if file.contains("://")
+        || relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(outside());
+    }
+    let path: PathBuf = root.join(relative);
+    // Only existing files can be resolved, the rest (globs, archive members) stay within the root by their name
+    if let Ok(canonical) = path.canonicalize() {
+        if !canonical.starts_with(root) {
+            return Err(outside());
+        }
+    }
+    Ok(path.display().to_string())
+}
+
+fn job_response(jobs: &Jobs, id: u64) -> Response<std::io::Cursor<Vec<u8>>> {
+    let jobs = jobs.lock().expect("Jobs lock poisoned");
+    match jobs.get(&id) {
+        Some(job) => json_response(200, job),
+        None => json_response(404, &ErrorBody::new("Job not found")),
+    }
+}
+
+#[derive(Serialize)]
+struct IdBody {
+    id: u64,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl ErrorBody {
+    fn new(message: &str) -> Self {
+        ErrorBody {
+            error: message.to_string(),
+        }
+    }
+}
+
+fn json_response<T: Serialize + ?Sized>(
+    status_code: u16,
+    body: &T,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let json = serde_json::to_string(body).expect("Responses are always serializable");
+    Response::from_string(json)
+        .with_status_code(status_code)
+        .with_header(
+            Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                .expect("Valid header"),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(file1: &str, file2: &str, options: &[&str]) -> CompareRequest {
+        CompareRequest {
+            file1: file1.to_string(),
+            file2: file2.to_string(),
+            options: options.iter().map(|option| option.to_string()).collect(),
+        }
+    }
+
+    fn finished_job(id: u64, finished_at: Instant) -> JobState {
+        JobState {
+            status: JobStatus::Finished,
+            finished_at: Some(finished_at),
+            ..JobState::new(id)
+        }
+    }
+
+    #[test]
+    fn expires_the_jobs_finished_before_their_time_to_live() {
+        let mut jobs = HashMap::new();
+        jobs.insert(
+            1,
+            finished_job(1, Instant::now() - Duration::from_secs(120)),
+        );
+        jobs.insert(2, finished_job(2, Instant::now()));
+        jobs.insert(3, JobState::new(3));
+        expire_jobs(&mut jobs, Duration::from_secs(60));
+        let mut ids: Vec<_> = jobs.keys().copied().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn keeps_only_the_last_finished_jobs() {
+        let started = Instant::now();
+        let mut jobs: HashMap<_, _> = (0..MAX_FINISHED_JOBS as u64 + 2)
+            .map(|id| (id, finished_job(id, started + Duration::from_millis(id))))
+            .collect();
+        jobs.insert(u64::MAX, JobState::new(u64::MAX));
+        expire_jobs(&mut jobs, Duration::from_secs(3600));
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS + 1);
+        assert!(!jobs.contains_key(&0) && !jobs.contains_key(&1));
+        assert!(jobs.contains_key(&u64::MAX));
+    }
+
+    #[test]
+    fn reports_the_progress_of_the_comparison() {
+        let jobs: Jobs = Arc::new(Mutex::new(HashMap::new()));
+        set_status(&jobs, 1, JobStatus::Queued, None, None);
+        let observer = JobObserver {
+            jobs: Arc::clone(&jobs),
+            id: 1,
+        };
+        let (a, b) = ("a".to_string(), "b".to_string());
+        observer.input_size(100);
+        observer.row_counts(3, 4);
+        observer.columns_finished(&[&a, &b], false);
+        set_status(&jobs, 1, JobStatus::Running, None, None);
+
+        let jobs = jobs.lock().unwrap();
+        let job = &jobs[&1];
+        assert_eq!(
+            job.progress,
+            JobProgress {
+                input_bytes: 100,
+                first_file_rows: Some(3),
+                second_file_rows: Some(4),
+                columns_compared: 2,
+                different_columns: 0,
+            }
+        );
+        assert!(job.finished_at.is_none());
+    }
+
+    #[test]
+    fn accepts_comparison_options() {
+        let args = compare_args(
+            request("a.csv", "b.csv", &["--report-all", "--separator", ";"]),
+            None,
+        )
+        .unwrap();
+        assert!(args.report_all);
+        assert_eq!(args.separator, ';');
+        assert_eq!(args.file1.as_deref(), Some("a.csv"));
+    }
+
+    #[test]
+    fn rejects_options_that_write_files_or_reach_other_hosts() {
+        for options in [
+            &["--report-json", "/tmp/report.json"][..],
+            &["--annotate=/tmp/annotated.csv"],
+            &["--metrics-file", "/tmp/metrics"],
+            &["--checkpoint", "/tmp/checkpoint"],
+            &["--notify-url", "http://169.254.169.254/"],
+            &["--proxy", "http://proxy:3128"],
+            &["--config", "/etc/passwd"],
+            &["-s"],
+            &["--", "--report-all"],
+        ] {
+            assert!(
+                compare_args(request("a.csv", "b.csv", options), None).is_err(),
+                "{options:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_files_and_commands_in_the_options() {
+        assert!(compare_args(request("a.csv", "b.csv", &["c.csv"]), None).is_err());
+        assert!(compare_args(request("a.csv", "b.csv", &["serve"]), None).is_err());
+    }
+
+    #[test]
+    fn keeps_the_files_inside_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("exports")).unwrap();
+        std::fs::write(root.join("exports/a.csv"), "id\n1\n").unwrap();
+
+        let args = compare_args(request("exports/a.csv", "b.csv", &[]), Some(&root)).unwrap();
+        assert_eq!(
+            args.file1,
+            Some(root.join("exports/a.csv").display().to_string())
+        );
+        for file in [
+            "../a.csv",
+            "exports/../../a.csv",
+            "/etc/passwd",
+            "https://example.com/a.csv",
+            "s3://bucket/a.csv",
+        ] {
+            assert!(
+                inside_root(&root, file).is_err(),
+                "{file} should be rejected"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_links_out_of_the_root() {
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.csv"), "id\n1\n").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path().canonicalize().unwrap();
+        std::os::unix::fs::symlink(outside.path().join("secret.csv"), root.join("link.csv"))
+            .unwrap();
+        assert!(inside_root(&root, "link.csv").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Part of the work done by one of several runs, written as `index/count` (from 1/count to count/count).
+/// The columns are dealt between the shards, so every run compares different columns of the same files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (index, count) = text
+            .split_once('/')
+            .ok_or_else(|| format!("Expected index/count, e.g. 1/4, found {text}"))?;
+        let index: usize = index
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid shard index in {text}"))?;
+        let count: usize = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid shard count in {text}"))?;
+        if count == 0 {
+            return Err(format!("The shard count must be at least 1 in {text}"));
+        }
+        if index == 0 || index > count {
+            return Err(format!("The shard index must be between 1 and {count}"));
+        }
+        Ok(Shard { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+impl Shard {
+    /// Columns compared by this shard: the key (first column) and every count-th of the rest
+    pub fn columns(&self, columns: Vec<String>) -> Vec<String> {
+        columns
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| *position == 0 || (position - 1) % self.count == self.index - 1)
+            .map(|(_, column)| column)
+            .collect()
+    }
+
+    /// The first shard counts the rows of the files, so they are counted once when the reports are merged
+    pub fn is_first(&self) -> bool {
+        self.index == 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|column| column.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_shards() {
+        assert_eq!("2/4".parse(), Ok(Shard { index: 2, count: 4 }));
+        assert_eq!(" 1 / 1 ".parse(), Ok(Shard { index: 1, count: 1 }));
+        assert_eq!("3/4".parse::<Shard>().unwrap().to_string(), "3/4");
+    }
+
+    #[test]
+    fn rejects_invalid_shards() {
+        for text in ["1/0", "0/0", "0/4", "5/4", "1", "a/4", "1/b", "-1/4"] {
+            assert!(text.parse::<Shard>().is_err(), "{text} should be invalid");
+        }
+    }
+
+    #[test]
+    fn deals_the_columns_between_shards_keeping_the_key() {
+        let columns = names(&["id", "a", "b", "c", "d", "e"]);
+        let first: Shard = "1/2".parse().unwrap();
+        let second: Shard = "2/2".parse().unwrap();
+        assert_eq!(
+            first.columns(columns.clone()),
+            names(&["id", "a", "c", "e"])
+        );
+        assert_eq!(second.columns(columns), names(&["id", "b", "d"]));
+    }
+
+    #[test]
+    fn shards_without_columns_only_keep_the_key() {
+        let shard: Shard = "3/3".parse().unwrap();
+        assert_eq!(shard.columns(names(&["id", "a"])), names(&["id"]));
+        assert_eq!(shard.columns(vec![]), Vec::<String>::new());
+        assert!(!shard.is_first());
+    }
+}
@@ -0,0 +1,234 @@
+#[cfg(any(feature = "parquet", feature = "sqlite"))]
+use crate::inputs::LocalFile;
+use crate::{get_lazy_frame, Args};
+use anyhow::Result;
+use polars::prelude::LazyFrame;
+
+/// An input of the comparison that can be read as a polars lazy frame. Implement it to compare other formats
+/// with `compare_sources`
+pub trait DataSource {
+    /// Name of the input in the messages and errors
+    fn name(&self) -> &str;
+
+    fn to_lazyframe(&self) -> Result<LazyFrame>;
+}
+
+/// A CSV file, read with the separator and types of the arguments
+pub struct CsvSource {
+    path: String,
+    separator: char,
+    typed: bool,
+    low_memory: bool,
+}
+
+impl CsvSource {
+    pub fn new(path: &str, args: &Args) -> Self {
+        CsvSource {
+            path: path.to_string(),
+            separator: args.separator,
+            typed: args.typed,
+            low_memory: args.low_memory,
+        }
+    }
+}
+
+impl DataSource for CsvSource {
+    fn name(&self) -> &str {
+        &self.path
+    }
+
+    fn to_lazyframe(&self) -> Result<LazyFrame> {
+        get_lazy_frame(&self.path, self.separator, self.typed, self.low_memory)
+    }
+}
+
+/// A Parquet file, a directory of Parquet files or a Delta table, scanned lazily
+#[cfg(feature = "parquet")]
+pub struct ParquetSource {
+    name: String,
+    path: String,
+    _file: Option<LocalFile>,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetSource {
+    pub fn new(path: &str) -> Self {
+        ParquetSource {
+            name: path.to_string(),
+            path: path.to_string(),
+            _file: None,
+        }
+    }
+
+    /// A Parquet file downloaded or extracted from an archive, kept until the source is dropped
+    pub(crate) fn fetched(name: &str, file: LocalFile) -> Self {
+        ParquetSource {
+            name: name.to_string(),
+            path: file.path().to_string(),
+            _file: Some(file),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl DataSource for ParquetSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn to_lazyframe(&self) -> Result<LazyFrame> {
+        crate::parquet::scan(&self.path)
+    }
+}
+
+/// A table of a SQLite database, or the rows of a query, read into memory. Values are read as text, as in CSV files
+#[cfg(feature = "sqlite")]
+pub struct DbSource {
+    name: String,
+    database_path: String,
+    table: Option<String>,
+    query: Option<String>,
+    _file: Option<LocalFile>,
+}
+
+#[cfg(feature = "sqlite")]
+impl DbSource {
+    pub fn table(database_path: &str, table: &str) -> Self {
+        DbSource {
+            name: database_path.to_string(),
+            database_path: database_path.to_string(),
+            table: Some(table.to_string()),
+            query: None,
+            _file: None,
+        }
+    }
+
+    pub fn query(database_path: &str, query: &str) -> Self {
+        DbSource {
+            name: database_path.to_string(),
+            database_path: database_path.to_string(),
+            table: None,
+            query: Some(query.to_string()),
+            _file: None,
+        }
+    }
+
+    /// A database downloaded or extracted from an archive, kept until the source is dropped
+    pub(crate) fn fetched(
+        name: &str,
+        file: LocalFile,
+        table: Option<&str>,
+        query: Option<&str>,
+    ) -> Self {
+        DbSource {
+            name: name.to_string(),
+            database_path: file.path().to_string(),
+            table: table.map(str::to_string),
+            query: query.map(str::to_string),
+            _file: Some(file),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl DataSource for DbSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn to_lazyframe(&self) -> Result<LazyFrame> {
+        use polars::prelude::IntoLazy;
+
+        crate::sqlite::read(
+            &self.database_path,
+            self.table.as_deref(),
+            self.query.as_deref(),
+        )
+        .map(|data_frame| data_frame.lazy())
+    }
+}
+
+/// A lazy frame already in memory
+pub struct FrameSource {
+    name: String,
+    lazy_frame: LazyFrame,
+}
+
+impl FrameSource {
+    pub fn new(name: &str, lazy_frame: LazyFrame) -> Self {
+        FrameSource {
+            name: name.to_string(),
+            lazy_frame,
+        }
+    }
+}
+
+impl DataSource for FrameSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn to_lazyframe(&self) -> Result<LazyFrame> {
+        Ok(self.lazy_frame.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare_sources;
+    use crate::output::Printer;
+    use crate::report::ComparisonResult;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+    use std::io::Write;
+
+    fn compare(first: &dyn DataSource, second: &dyn DataSource) -> ComparisonResult {
+        let args = Args::from_options(first.name(), second.name(), &[]).unwrap();
+        compare_sources(&args, &Printer::new(true, false, false), first, second).result
+    }
+
+    #[test]
+    fn compares_csv_files_with_frames() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"id,name\n1,Ann\n2,Bob\n").unwrap();
+        let path = file.path().to_str().unwrap();
+        let args = Args::from_options(path, path, &[]).unwrap();
+        let csv_source = CsvSource::new(path, &args);
+        assert_eq!(csv_source.name(), path);
+
+        let same = FrameSource::new(
+            "same",
+            df!("id" => ["2", "1"], "name" => ["Bob", "Ann"])
+                .unwrap()
+                .lazy(),
+        );
+        assert_eq!(compare(&csv_source, &same), ComparisonResult::Identical);
+        let different = FrameSource::new(
+            "different",
+            df!("id" => ["1", "2"], "name" => ["Ann", "Bobby"])
+                .unwrap()
+                .lazy(),
+        );
+        assert_eq!(
+            compare(&csv_source, &different),
+            ComparisonResult::DifferentValues
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn reads_tables_and_queries_of_databases() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let database_path = directory.path().join("data.db").display().to_string();
+        rusqlite::Connection::open(&database_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE people (id TEXT, name TEXT); INSERT INTO people VALUES ('1', 'Ann');",
+            )
+            .unwrap();
+        let table = DbSource::table(&database_path, "people");
+        let query = DbSource::query(&database_path, "SELECT * FROM people ORDER BY id");
+        assert_eq!(compare(&table, &query), ComparisonResult::Identical);
+    }
+}
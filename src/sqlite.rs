@@ -0,0 +1,122 @@
+use crate::canonicalize::write_csv;
+use crate::inputs::temporary_file;
+use anyhow::{Context, Result};
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Writes a table of the database, or the rows of a query, as a CSV file, for the commands that read a CSV file
+/// (canonicalize, hash...). Nulls are written as empty fields and blobs in hexadecimal
+pub fn export(
+    database_path: &str,
+    table: Option<&str>,
+    query: Option<&str>,
+    separator: char,
+) -> Result<NamedTempFile> {
+    let data_frame = read(database_path, table, query)?;
+    let mut output = temporary_file()?;
+    write_csv(&data_frame, separator, &mut output)?;
+    output.flush()?;
+    Ok(output)
+}
+
+/// Reads a table of the database, or the rows of a query, as a data frame with the values as text (blobs in
+/// hexadecimal), as they would be read from a CSV file
+pub fn read(database_path: &str, table: Option<&str>, query: Option<&str>) -> Result<DataFrame> {
+    let connection = Connection::open_with_flags(database_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Couldn't open database {database_path}"))?;
+    let query = match (table, query) {
+        (_, Some(query)) => query.to_string(),
+        (Some(table), None) => format!("SELECT * FROM \"{}\"", table.replace('"', "\"\"")),
+        (None, None) => anyhow::bail!(
+            "Missing the table of {database_path}: sqlite://file?table=name or sqlite://file?query=SELECT..."
+        ),
+    };
+    let mut statement = connection
+        .prepare(&query)
+        .with_context(|| format!("Invalid query for {database_path}: {query}"))?;
+    let column_names: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut columns: Vec<Vec<Option<String>>> = vec![vec![]; column_names.len()];
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows
+        .next()
+        .with_context(|| format!("Couldn't read {database_path}"))?
+    {
+        for (index, values) in columns.iter_mut().enumerate() {
+            values.push(as_text(row.get_ref(index)?));
+        }
+    }
+    DataFrame::new(
+        column_names
+            .iter()
+            .zip(columns)
+            .map(|(name, values)| Series::new(name, values))
+            .collect(),
+    )
+    .with_context(|| format!("Couldn't read {database_path}"))
+}
+
+fn as_text(value: ValueRef) -> Option<String> {
+    match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(integer) => Some(integer.to_string()),
+        ValueRef::Real(real) => Some(real.to_string()),
+        ValueRef::Text(text) => Some(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(blob) => Some(blob.iter().map(|byte| format!("{byte:02x}")).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn database() -> (TempDir, String) {
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("data.db").display().to_string();
+        let connection = Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE \"my table\" (id INTEGER, amount REAL, name TEXT, data BLOB);
+                INSERT INTO \"my table\" VALUES (1, 1.5, 'Ann', x'0aff'), (2, NULL, 'Bob', NULL);",
+            )
+            .unwrap();
+        (directory, path)
+    }
+
+    #[test]
+    fn exports_tables_as_csv() {
+        let (_directory, path) = database();
+        let csv_file = export(&path, Some("my table"), None, ',').unwrap();
+        assert_eq!(
+            std::fs::read_to_string(csv_file.path()).unwrap(),
+            "id,amount,name,data\n1,1.5,Ann,0aff\n2,,Bob,\n"
+        );
+    }
+
+    #[test]
+    fn reads_the_rows_of_queries() {
+        let (_directory, path) = database();
+        let data_frame = read(
+            &path,
+            None,
+            Some("SELECT name FROM \"my table\" WHERE id = 2"),
+        )
+        .unwrap();
+        assert_eq!(data_frame.get_column_names(), ["name"]);
+        assert_eq!(data_frame.height(), 1);
+    }
+
+    #[test]
+    fn needs_a_table_or_a_query() {
+        let (_directory, path) = database();
+        assert!(read(&path, None, None).is_err());
+        assert!(read(&path, Some("missing"), None).is_err());
+    }
+}
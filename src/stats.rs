@@ -0,0 +1,305 @@
+use crate::compare::{as_text, get_text};
+use crate::report::{DriftSeverity, DriftViolation, ValueCount};
+use anyhow::{bail, Context, Result};
+use polars::prelude::{col, count, DataType, Expr, LazyFrame};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Drift of a metric allowed between the files, and what happens when it's bigger
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriftLimit {
+    /// Maximum change: percentage of the first file value for the mean and the distinct values, percentage
+    /// points for the null rate
+    pub max: f64,
+    #[serde(default)]
+    pub severity: DriftSeverity,
+}
+
+/// Drift allowed for the metrics of a column
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnDriftLimits {
+    pub mean: Option<DriftLimit>,
+    pub null_rate: Option<DriftLimit>,
+    pub distinct: Option<DriftLimit>,
+}
+
+/// Drift allowed for the statistics of every column, read from a YAML file:
+/// ```yaml
+/// amount:
+///   mean: { max: 5, severity: fail }
+///   null_rate: { max: 1, severity: warn }
+/// customer_id:
+///   distinct: { max: 10 }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DriftRules {
+    columns: BTreeMap<String, ColumnDriftLimits>,
+}
+
+/// Statistics of a column compared in statistical mode
+struct ColumnMetrics {
+    /// Mean of the numeric values (none when no value is a number)
+    mean: Option<f64>,
+    /// Percentage of null values
+    null_rate: f64,
+    distinct: u64,
+}
+
+impl DriftRules {
+    pub fn load(path: &str) -> Result<DriftRules> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read drift rules {path}"))?;
+        let columns = serde_yaml::from_str(&content)
+            .with_context(|| format!("Couldn't parse drift rules {path}"))?;
+        Ok(DriftRules { columns })
+    }
+
+    /// Metrics of the columns with rules whose drift is bigger than allowed. Every file is read once
+    pub fn check(
+        &self,
+        first_lazy_frame: &LazyFrame,
+        second_lazy_frame: &LazyFrame,
+        columns: &[String],
+    ) -> Result<Vec<DriftViolation>> {
+        if let Some(column) = self.columns.keys().find(|column| !columns.contains(column)) {
+            bail!("Column {column} of the drift rules doesn't exist");
+        }
+        let columns: Vec<&String> = self.columns.keys().collect();
+        let first_metrics = column_metrics(first_lazy_frame, &columns)?;
+        let second_metrics = column_metrics(second_lazy_frame, &columns)?;
+        let mut violations = vec![];
+        for ((column, limits), (first, second)) in self
+            .columns
+            .iter()
+            .zip(first_metrics.iter().zip(&second_metrics))
+        {
+            let metrics = [
+                ("mean", limits.mean, first.mean, second.mean, true),
+                (
+                    "null_rate",
+                    limits.null_rate,
+                    Some(first.null_rate),
+                    Some(second.null_rate),
+                    false,
+                ),
+                (
+                    "distinct",
+                    limits.distinct,
+                    Some(first.distinct as f64),
+                    Some(second.distinct as f64),
+                    true,
+                ),
+            ];
+            for (metric, limit, first_value, second_value, relative) in metrics {
+                let Some(limit) = limit else {
+                    continue;
+                };
+                let drift = match (first_value, second_value) {
+                    (Some(first_value), Some(second_value)) if relative => {
+                        percent_change(first_value, second_value)
+                    }
+                    (Some(first_value), Some(second_value)) => (second_value - first_value).abs(),
+                    (None, None) => 0.0,
+                    _ => f64::INFINITY,
+                };
+                if drift > limit.max {
+                    violations.push(DriftViolation {
+                        column: column.to_string(),
+                        metric: metric.to_string(),
+                        first_value,
+                        second_value,
+                        drift,
+                        max_drift: limit.max,
+                        severity: limit.severity,
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+fn column_metrics(lazy_frame: &LazyFrame, columns: &[&String]) -> Result<Vec<ColumnMetrics>> {
+    let mut expressions: Vec<Expr> = vec![count().cast(DataType::Float64).alias("\u{0}rows")];
+    for (index, column) in columns.iter().enumerate() {
+        expressions.push(
+            col(column)
+                .cast(DataType::Float64)
+                .mean()
+                .alias(&format!("\u{0}mean_{index}")),
+        );
+        expressions.push(
+            col(column)
+                .null_count()
+                .cast(DataType::Float64)
+                .alias(&format!("\u{0}nulls_{index}")),
+        );
+        expressions.push(
+            col(column)
+                .n_unique()
+                .cast(DataType::UInt64)
+                .alias(&format!("\u{0}distinct_{index}")),
+        );
+    }
+    let metrics = lazy_frame
+        .clone()
+        .select(expressions)
+        .collect()
+        .context("Couldn't calculate the statistics of the columns")?;
+    let float = |name: &str| -> Result<Option<f64>> { Ok(metrics.column(name)?.f64()?.get(0)) };
+    let rows = float("\u{0}rows")?.unwrap_or_default();
+    (0..columns.len())
+        .map(|index| {
+            let nulls = float(&format!("\u{0}nulls_{index}"))?.unwrap_or_default();
+            Ok(ColumnMetrics {
+                mean: float(&format!("\u{0}mean_{index}"))?,
+                null_rate: if rows > 0.0 {
+                    nulls * 100.0 / rows
+                } else {
+                    0.0
+                },
+                distinct: metrics
+                    .column(&format!("\u{0}distinct_{index}"))?
+                    .u64()?
+                    .get(0)
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Number of distinct values of every column (nulls count as a value), in a single pass
+pub(crate) fn distinct_counts(lazy_frame: &LazyFrame, columns: &[String]) -> Result<Vec<u64>> {
+    let counts = lazy_frame
+        .clone()
+        .select(
+            columns
+                .iter()
+                .map(|column| col(column).n_unique().cast(DataType::UInt64))
+                .collect::<Vec<_>>(),
+        )
+        .collect()
+        .context("Couldn't count the distinct values of the columns")?;
+    counts
+        .get_columns()
+        .iter()
+        .map(|count| Ok(count.u64()?.get(0).unwrap_or_default()))
+        .collect()
+}
+
+/// The most frequent values of the column with their number of rows, the most frequent first (and in
+/// alphabetical order when they have the same rows)
+pub(crate) fn top_values(
+    lazy_frame: &LazyFrame,
+    column: &str,
+    top: usize,
+) -> Result<Vec<ValueCount>> {
+    let counts = lazy_frame
+        .clone()
+        .group_by([col(column)])
+        .agg([count().cast(DataType::UInt64).alias("\u{0}count")])
+        .sort_by_exprs(
+            [col("\u{0}count"), col(column).cast(DataType::Utf8)],
+            [true, false],
+            false,
+            false,
+        )
+        .limit(top as u32)
+        .collect()
+        .with_context(|| format!("Couldn't count the values of column {column}"))?;
+    let values = as_text(counts.column(column)?);
+    let rows = counts.column("\u{0}count")?.u64()?;
+    Ok((0..counts.height())
+        .map(|index| ValueCount {
+            value: get_text(&values, index),
+            count: rows.get(index).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Change from the first value to the second one, as a percentage of the first one
+pub(crate) fn percent_change(first: f64, second: f64) -> f64 {
+    if first == second {
+        0.0
+    } else if first == 0.0 {
+        100.0
+    } else {
+        (second - first).abs() * 100.0 / first.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+    use polars::prelude::IntoLazy;
+    use std::io::Write;
+
+    #[test]
+    fn percent_changes_are_relative_to_the_first_value() {
+        assert_eq!(percent_change(20.0, 30.0), 50.0);
+        assert_eq!(percent_change(-20.0, -10.0), 50.0);
+        assert_eq!(percent_change(0.0, 5.0), 100.0);
+        assert_eq!(percent_change(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn counts_distinct_and_top_values() {
+        let lazy_frame = df!(
+            "status" => ["b", "a", "b", "c", "a", "b"],
+            "name" => [Some("Ann"), None, Some("Ann"), None, Some("Bob"), Some("Ann")]
+        )
+        .unwrap()
+        .lazy();
+        let columns = ["status", "name"].map(String::from);
+        assert_eq!(distinct_counts(&lazy_frame, &columns).unwrap(), [3, 3]);
+        let value_count = |value: &str, count| ValueCount {
+            value: Some(value.to_string()),
+            count,
+        };
+        assert_eq!(
+            top_values(&lazy_frame, "status", 2).unwrap(),
+            [value_count("b", 3), value_count("a", 2)]
+        );
+    }
+
+    #[test]
+    fn finds_the_metrics_that_drifted_more_than_allowed() {
+        let mut rules_file = tempfile::NamedTempFile::new().unwrap();
+        rules_file
+            .write_all(
+                b"amount:\n  mean: { max: 5 }\n  null_rate: { max: 30, severity: warn }\n  distinct: { max: 10, severity: warn }\n",
+            )
+            .unwrap();
+        let rules = DriftRules::load(rules_file.path().to_str().unwrap()).unwrap();
+        let first = df!("amount" => [Some("10"), Some("20"), None, Some("30")])
+            .unwrap()
+            .lazy();
+        let second = df!("amount" => ["10", "40", "40", "30"]).unwrap().lazy();
+        let violations = rules
+            .check(&first, &second, &["amount".to_string()])
+            .unwrap();
+        let summary: Vec<(&str, f64, DriftSeverity)> = violations
+            .iter()
+            .map(|violation| {
+                (
+                    violation.metric.as_str(),
+                    violation.drift,
+                    violation.severity,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("mean", 50.0, DriftSeverity::Fail),
+                ("distinct", 25.0, DriftSeverity::Warn)
+            ]
+        );
+        assert!(rules
+            .check(&first, &second, &["other".to_string()])
+            .is_err());
+    }
+}
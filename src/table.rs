@@ -0,0 +1,62 @@
+use crate::i18n::tr;
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{CellAlignment, ContentArrangement, Table};
+
+/// Longest value shown in a cell before it's truncated
+const MAX_CELL_WIDTH: usize = 60;
+
+/// Creates a table that adapts its columns to the width of the terminal
+pub fn new_table(header: Vec<&'static str>) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL_CONDENSED)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header.into_iter().map(tr));
+    table
+}
+
+/// Aligns to the right the given columns of the table (for numbers)
+pub fn align_right(table: &mut Table, column_indexes: &[usize]) {
+    for &index in column_indexes {
+        if let Some(column) = table.column_mut(index) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+}
+
+/// Shortens long values so a single cell can't take the whole table
+pub fn truncate(value: &str) -> String {
+    if value.chars().count() <= MAX_CELL_WIDTH {
+        return value.to_string();
+    }
+    let truncated: String = value.chars().take(MAX_CELL_WIDTH - 1).collect();
+    format!("{truncated}…")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_long_values() {
+        assert_eq!(truncate("short"), "short");
+        let long = "é".repeat(MAX_CELL_WIDTH + 1);
+        let truncated = truncate(&long);
+        assert_eq!(truncated.chars().count(), MAX_CELL_WIDTH);
+        assert!(truncated.ends_with('…'));
+        assert_eq!(
+            truncate(&long[..MAX_CELL_WIDTH * 2]),
+            long[..MAX_CELL_WIDTH * 2]
+        );
+    }
+
+    #[test]
+    fn aligns_numbers_to_the_right() {
+        let mut table = new_table(vec!["Column", "Count"]);
+        table.add_row(vec!["name", "1"]);
+        align_right(&mut table, &[1, 5]);
+        let rendered = table.to_string();
+        assert!(rendered.contains("name   "));
+        assert!(rendered.contains("    1 "));
+    }
+}
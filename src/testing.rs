@@ -0,0 +1,187 @@
+use crate::canonicalize::write_csv;
+use crate::inputs::temporary_file;
+use crate::output::Printer;
+use crate::report::{ComparisonReport, ComparisonResult};
+use crate::{compare_files, Args};
+use polars::frame::DataFrame;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Something that can be compared by `assert_csv_eq!`: the path of a file, or a data frame (written to a
+/// temporary CSV file)
+pub trait TestInput {
+    /// Path of the file to compare, with the temporary file that must live while it's compared
+    fn csv_path(&self, separator: char) -> (String, Option<NamedTempFile>);
+}
+
+impl<T: TestInput + ?Sized> TestInput for &T {
+    fn csv_path(&self, separator: char) -> (String, Option<NamedTempFile>) {
+        (**self).csv_path(separator)
+    }
+}
+
+impl TestInput for str {
+    fn csv_path(&self, _separator: char) -> (String, Option<NamedTempFile>) {
+        (self.to_string(), None)
+    }
+}
+
+impl TestInput for String {
+    fn csv_path(&self, _separator: char) -> (String, Option<NamedTempFile>) {
+        (self.to_string(), None)
+    }
+}
+
+impl TestInput for Path {
+    fn csv_path(&self, _separator: char) -> (String, Option<NamedTempFile>) {
+        (self.display().to_string(), None)
+    }
+}
+
+impl TestInput for PathBuf {
+    fn csv_path(&self, _separator: char) -> (String, Option<NamedTempFile>) {
+        (self.display().to_string(), None)
+    }
+}
+
+impl TestInput for DataFrame {
+    fn csv_path(&self, separator: char) -> (String, Option<NamedTempFile>) {
+        let file = temporary_file().expect("Couldn't create a temporary file for the data frame");
+        write_csv(self, separator, file.as_file()).expect("Couldn't write the data frame");
+        (file.path().display().to_string(), Some(file))
+    }
+}
+
+/// Compares both inputs with the options of the command line (e.g. `["--typed"]`) and panics with the
+/// differences found unless the result exits with code 0 (identical files, or as configured by the options).
+/// Used by `assert_csv_eq!`
+pub fn assert_csv_eq<F: TestInput + ?Sized, S: TestInput + ?Sized>(
+    first: &F,
+    second: &S,
+    options: &[&str],
+) {
+    let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+    let args = Args::from_options("first", "second", &options)
+        .unwrap_or_else(|error| panic!("Invalid options {:?}: {}", options, error));
+    let (first_path, _first_file) = first.csv_path(args.separator);
+    let (second_path, _second_file) = second.csv_path(args.separator);
+    let report = compare_files(
+        &args,
+        &Printer::new(true, false, false),
+        &first_path,
+        &second_path,
+    );
+    if report.result.exit_code(&args) != 0 {
+        panic!(
+            "CSV files are different: {} <> {}\n{}",
+            first_path,
+            second_path,
+            render_report(&report)
+        );
+    }
+}
+
+/// The result of the comparison with the tables of the differences found
+fn render_report(report: &ComparisonReport) -> String {
+    let mut rendered = vec![format!("Result: {}", report.result.name())];
+    if let Some(error) = &report.error {
+        rendered.push(format!("Error: {}", error));
+    }
+    if !report.column_differences.is_empty() {
+        rendered.push(report.differences_table());
+        rendered.push(report.categories_table());
+        rendered.push(report.samples_table());
+    } else if report.result == ComparisonResult::DifferentRows {
+        rendered.push(format!("Rows: {}", report.rows));
+        rendered.push(report.categories_table());
+    }
+    if !report.column_type_differences.is_empty() {
+        rendered.push(report.types_table());
+    }
+    if !report.key_count_differences.is_empty() {
+        rendered.push(report.key_counts_table());
+    }
+    if !report.encoding_differences.is_empty() {
+        rendered.push(report.encoding_table());
+    }
+    if !report.duplicated_keys_first.is_empty() || !report.duplicated_keys_second.is_empty() {
+        rendered.push(report.duplicated_keys_table());
+    }
+    if !report.assertion_failures.is_empty() {
+        rendered.push(report.assertions_table());
+    }
+    if !report.column_order_differences.is_empty() {
+        rendered.push(format!(
+            "Columns in a different order: {}",
+            report
+                .column_order_differences
+                .iter()
+                .map(|difference| format!(
+                    "{} <> {}",
+                    difference.first_column, difference.second_column
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    rendered.join("\n")
+}
+
+/// Panics when the two CSV files (paths or polars data frames) aren't identical, showing the differences.
+/// The options of the command line can be given as a third argument:
+/// ```ignore
+/// assert_csv_eq!("expected.csv", output_data_frame, ["--typed", "--sort-by", "id"]);
+/// ```
+#[macro_export]
+macro_rules! assert_csv_eq {
+    ($first:expr, $second:expr $(,)?) => {
+        $crate::testing::assert_csv_eq(&$first, &$second, &[])
+    };
+    ($first:expr, $second:expr, $options:expr $(,)?) => {
+        $crate::testing::assert_csv_eq(&$first, &$second, &$options)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use polars::df;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn compares_files_and_data_frames() {
+        let expected = file("id,name\n1,Ann\n2,Bob\n");
+        let data_frame = df!("id" => ["2", "1"], "name" => ["Bob", "Ann"]).unwrap();
+        crate::assert_csv_eq!(expected.path(), data_frame);
+        crate::assert_csv_eq!(
+            expected.path().to_str().unwrap(),
+            expected.path(),
+            ["--typed"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CSV files are different")]
+    fn panics_with_the_differences() {
+        let expected = file("id,name\n1,Ann\n");
+        let data_frame = df!("id" => ["1"], "name" => ["Anna"]).unwrap();
+        crate::assert_csv_eq!(expected.path(), data_frame);
+    }
+
+    #[test]
+    fn options_change_what_is_equal() {
+        let first = file("id,name,age\n1,Ann,30\n");
+        let second = file("id,age,name\n1,30,Ann\n");
+        crate::assert_csv_eq!(first.path(), second.path());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            crate::assert_csv_eq!(first.path(), second.path(), ["--exit-code-order", "9"]);
+        }));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::table;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Errors kept in the report. The rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationErrorKind {
+    Unreadable,
+    Empty,
+    DuplicateHeader,
+    MissingColumn,
+    UnexpectedColumn,
+    WrongFieldCount,
+    ParseError,
+}
+
+/// A problem found in the file, with the line where it was found when known
+#[derive(Serialize, Debug, Clone)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    pub line: Option<u64>,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ValidationReport {
+    pub rows: u64,
+    pub columns: Vec<String>,
+    /// Total number of errors, even the ones not included in `errors`
+    pub error_count: u64,
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.error_count == 0
+    }
+
+    fn add_error(&mut self, kind: ValidationErrorKind, line: Option<u64>, message: String) {
+        self.error_count += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ValidationError {
+                kind,
+                line,
+                message,
+            });
+        }
+    }
+
+    pub fn errors_table(&self) -> String {
+        let mut table = table::new_table(vec!["Line", "Error", "Message"]);
+        for error in &self.errors {
+            table.add_row(vec![
+                error.line.map(|line| line.to_string()).unwrap_or_default(),
+                format!("{:?}", error.kind),
+                table::truncate(&error.message),
+            ]);
+        }
+        table.to_string()
+    }
+}
+
+/// Checks that the file can be parsed, every row has as many fields as the header, there are no
+/// duplicate column names and, if given, that the columns are the expected ones
+pub fn validate_file(
+    file_path: &str,
+    separator: char,
+    expected_columns: &[String],
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut reader = match csv::ReaderBuilder::new()
+        .delimiter(separator as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_path(file_path)
+    {
+        Ok(reader) => reader,
+        Err(error) => {
+            report.add_error(
+                ValidationErrorKind::Unreadable,
+                None,
+                format!("Couldn't open file {file_path}: {error}"),
+            );
+            return report;
+        }
+    };
+
+    let mut records = reader.records();
+    let header: Vec<String> = match records.next() {
+        Some(Ok(record)) => record.iter().map(str::to_string).collect(),
+        Some(Err(error)) => {
+            report.add_error(
+                ValidationErrorKind::ParseError,
+                Some(1),
+                format!("Couldn't parse header: {error}"),
+            );
+            return report;
+        }
+        None => {
+            report.add_error(
+                ValidationErrorKind::Empty,
+                None,
+                "File is empty".to_string(),
+            );
+            return report;
+        }
+    };
+
+    let mut seen = HashSet::new();
+    for column in &header {
+        if !seen.insert(column) {
+            report.add_error(
+                ValidationErrorKind::DuplicateHeader,
+                Some(1),
+                format!("Column {column} appears more than once"),
+            );
+        }
+    }
+    if !expected_columns.is_empty() {
+        for column in expected_columns {
+            if !header.contains(column) {
+                report.add_error(
+                    ValidationErrorKind::MissingColumn,
+                    Some(1),
+                    format!("Expected column {column} is missing"),
+                );
+            }
+        }
+        for column in &header {
+            if !expected_columns.contains(column) {
+                report.add_error(
+                    ValidationErrorKind::UnexpectedColumn,
+                    Some(1),
+                    format!("Column {column} is not expected"),
+                );
+            }
+        }
+    }
+
+    for record in records {
+        match record {
+            Ok(record) => {
+                report.rows += 1;
+                if record.len() != header.len() {
+                    report.add_error(
+                        ValidationErrorKind::WrongFieldCount,
+                        record.position().map(|position| position.line()),
+                        format!(
+                            "Row has {} fields but the header has {}",
+                            record.len(),
+                            header.len()
+                        ),
+                    );
+                }
+            }
+            Err(error) => {
+                report.add_error(
+                    ValidationErrorKind::ParseError,
+                    error.position().map(|position| position.line()),
+                    error.to_string(),
+                );
+            }
+        }
+    }
+
+    report.columns = header;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn validate(content: &str, expected_columns: &[&str]) -> ValidationReport {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let expected_columns: Vec<String> = expected_columns
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+        validate_file(file.path().to_str().unwrap(), ',', &expected_columns)
+    }
+
+    fn kinds(report: &ValidationReport) -> Vec<ValidationErrorKind> {
+        report.errors.iter().map(|error| error.kind).collect()
+    }
+
+    #[test]
+    fn a_well_formed_file_is_valid() {
+        let report = validate("id,name\n1,Ann\n2,Bob\n", &["id", "name"]);
+        assert!(report.is_valid());
+        assert_eq!(report.rows, 2);
+        assert_eq!(report.columns, ["id", "name"]);
+    }
+
+    #[test]
+    fn finds_rows_with_a_wrong_number_of_fields() {
+        let report = validate("id,name\n1,Ann\n2\n3,Carl,x\n", &[]);
+        assert_eq!(report.error_count, 2);
+        assert_eq!(kinds(&report), [ValidationErrorKind::WrongFieldCount; 2]);
+        assert_eq!(report.errors[0].line, Some(3));
+    }
+
+    #[test]
+    fn checks_the_header() {
+        let report = validate("id,name,name\n1,Ann,Ann\n", &["id", "age"]);
+        assert_eq!(
+            kinds(&report),
+            [
+                ValidationErrorKind::DuplicateHeader,
+                ValidationErrorKind::MissingColumn,
+                ValidationErrorKind::UnexpectedColumn,
+                ValidationErrorKind::UnexpectedColumn,
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_and_missing_files_are_invalid() {
+        assert_eq!(kinds(&validate("", &[])), [ValidationErrorKind::Empty]);
+        let report = validate_file("no-such-file.csv", ',', &[]);
+        assert_eq!(kinds(&report), [ValidationErrorKind::Unreadable]);
+    }
+
+    #[test]
+    fn only_the_first_errors_are_kept() {
+        let content = format!("id,name\n{}", "1\n".repeat(MAX_REPORTED_ERRORS + 5));
+        let report = validate(&content, &[]);
+        assert_eq!(report.error_count, MAX_REPORTED_ERRORS as u64 + 5);
+        assert_eq!(report.errors.len(), MAX_REPORTED_ERRORS);
+    }
+}
@@ -0,0 +1,162 @@
+//! Lightweight comparison engine for the browser. Polars doesn't build for wasm32, so this engine
+//! loads both files in memory, but follows the same rules and produces the same JSON report
+//! fields as the command line tool (result, rows, column_differences...)
+
+use serde::Serialize;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// Examples of different values kept for each different column
+const MAX_SAMPLES: usize = 5;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ComparisonResult {
+    Identical,
+    DifferentColumns,
+    DifferentValues,
+    DifferentRows,
+}
+
+#[derive(Serialize, Debug)]
+struct ComparisonReport {
+    result: ComparisonResult,
+    rows: u64,
+    column_differences: Vec<ColumnDifference>,
+}
+
+#[derive(Serialize, Debug)]
+struct ColumnDifference {
+    column: String,
+    differing_rows: u64,
+    samples: Vec<DifferenceSample>,
+}
+
+#[derive(Serialize, Debug)]
+struct DifferenceSample {
+    key: Option<String>,
+    first_value: Option<String>,
+    second_value: Option<String>,
+}
+
+struct CsvContent {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Compares the content of two CSV files and returns the JSON report.
+/// Rows are sorted by the first column of the first file before comparing them
+#[wasm_bindgen]
+pub fn compare(
+    first_csv: &str,
+    second_csv: &str,
+    separator: char,
+    strict_column_order: bool,
+) -> Result<String, JsError> {
+    let report = compare_content(first_csv, second_csv, separator, strict_column_order)
+        .map_err(|error| JsError::new(&error))?;
+    serde_json::to_string(&report).map_err(|error| JsError::new(&error.to_string()))
+}
+
+fn compare_content(
+    first_csv: &str,
+    second_csv: &str,
+    separator: char,
+    strict_column_order: bool,
+) -> Result<ComparisonReport, String> {
+    let first = parse(first_csv, separator)?;
+    let second = parse(second_csv, separator)?;
+    let mut report = ComparisonReport {
+        result: ComparisonResult::Identical,
+        rows: first.rows.len() as u64,
+        column_differences: vec![],
+    };
+
+    if first.rows.len() != second.rows.len() {
+        report.result = ComparisonResult::DifferentRows;
+        return Ok(report);
+    }
+
+    let have_same_columns = if strict_column_order {
+        first.headers == second.headers
+    } else {
+        first.headers.iter().collect::<HashSet<_>>()
+            == second.headers.iter().collect::<HashSet<_>>()
+    };
+    if !have_same_columns || first.headers.is_empty() {
+        report.result = ComparisonResult::DifferentColumns;
+        return Ok(report);
+    }
+
+    let sorting_column = &first.headers[0];
+    let first_rows = sorted_rows(&first, 0);
+    let second_key_index = column_index(&second, sorting_column);
+    let second_rows = sorted_rows(&second, second_key_index);
+
+    for (first_index, column) in first.headers.iter().enumerate().skip(1) {
+        let second_index = column_index(&second, column);
+        let mut difference = ColumnDifference {
+            column: column.to_string(),
+            differing_rows: 0,
+            samples: vec![],
+        };
+        for (first_row, second_row) in first_rows.iter().zip(&second_rows) {
+            let first_value = first_row.get(first_index);
+            let second_value = second_row.get(second_index);
+            if first_value != second_value {
+                difference.differing_rows += 1;
+                if difference.samples.len() < MAX_SAMPLES {
+                    difference.samples.push(DifferenceSample {
+                        key: first_row.first().cloned(),
+                        first_value: first_value.cloned(),
+                        second_value: second_value.cloned(),
+                    });
+                }
+            }
+        }
+        if difference.differing_rows > 0 {
+            report.column_differences.push(difference);
+        }
+    }
+
+    if !report.column_differences.is_empty() {
+        report.result = ComparisonResult::DifferentValues;
+    }
+    Ok(report)
+}
+
+fn parse(content: &str, separator: char) -> Result<CsvContent, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(separator as u8)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|error| format!("Couldn't read CSV header: {error}"))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let rows = reader
+        .records()
+        .map(|record| {
+            record
+                .map(|record| record.iter().map(str::to_string).collect())
+                .map_err(|error| format!("Couldn't parse CSV row: {error}"))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(CsvContent { headers, rows })
+}
+
+fn column_index(content: &CsvContent, column: &str) -> usize {
+    content
+        .headers
+        .iter()
+        .position(|header| header == column)
+        .expect("Columns were already checked to exist in both files")
+}
+
+fn sorted_rows(content: &CsvContent, key_index: usize) -> Vec<&Vec<String>> {
+    let mut rows: Vec<&Vec<String>> = content.rows.iter().collect();
+    rows.sort_by(|a, b| a.get(key_index).cmp(&b.get(key_index)));
+    rows
+}